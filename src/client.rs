@@ -136,14 +136,20 @@
 //! [`Error`]: ../struct.Error.html
 
 use crate::codec::{Codec, RecvError, SendError, UserError};
-use crate::frame::{Headers, Pseudo, Reason, Settings, StreamId};
+use crate::ext::{AltSvc, ExtensionFrame, Protocol};
+use crate::frame::{self, Headers, Pseudo, Reason, Settings, StreamId};
+use crate::observer::{FrameObserver, ObserverHandle};
 use crate::proto;
-use crate::{FlowControl, PingPong, RecvStream, SendStream};
+use crate::remote_settings::RemoteSettings;
+use crate::shutdown::GracefulShutdown;
+use crate::stats::ConnectionStats;
+use crate::timer::{Timer, TimerHandle};
+use crate::{FlowControl, PingPong, RecvStream, SendStream, StreamPriority, Tunnel};
 
 use bytes::{Buf, Bytes};
 use futures_io::{AsyncRead, AsyncWrite};
 use futures_util::io::AsyncWriteExt;
-use http::{uri, HeaderMap, Method, Request, Response, Version};
+use http::{uri, HeaderMap, Method, Request, Response, Uri, Version};
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
@@ -151,6 +157,9 @@ use std::task::{Context, Poll};
 use std::time::Duration;
 use std::usize;
 
+/// What an upgraded handshake resolves to.
+type Upgraded<T, B> = (SendRequest<B>, Connection<T, B>, ResponseFuture);
+
 /// Initializes new HTTP/2.0 streams on a connection by sending a request.
 ///
 /// This type does no work itself. Instead, it is a handle to the inner
@@ -175,6 +184,9 @@ use std::usize;
 pub struct SendRequest<B: Buf> {
     inner: proto::Streams<B, Peer>,
     pending: Option<proto::OpaqueStreamRef>,
+    /// Generation of the remote settings last returned by
+    /// `poll_remote_settings`.
+    remote_settings_seen: u64,
 }
 
 /// Returns a `SendRequest` instance once it is ready to send at least one
@@ -230,6 +242,9 @@ pub struct ReadySendRequest<B: Buf> {
 #[must_use = "futures do nothing unless polled"]
 pub struct Connection<T, B: Buf = Bytes> {
     inner: proto::Connection<T, Peer, B>,
+    /// Generation of the remote settings last returned by
+    /// `poll_remote_settings`.
+    remote_settings_seen: u64,
 }
 
 /// A future of an HTTP response.
@@ -269,6 +284,14 @@ pub struct PushPromises {
     inner: proto::OpaqueStreamRef,
 }
 
+/// A stream of the informational (1xx) responses received before the final
+/// response
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct InformationalResponses {
+    inner: proto::OpaqueStreamRef,
+}
+
 /// Builds client connections with custom configuration values.
 ///
 /// Methods can be chained in order to set the configuration values.
@@ -319,15 +342,48 @@ pub struct Builder {
     /// Initial target window size for new connections.
     initial_target_connection_window_size: Option<u32>,
 
+    /// Maximum window size when windows are sized adaptively.
+    max_adaptive_window_size: Option<u32>,
+
     /// Maximum number of locally reset streams to keep at a time.
     reset_stream_max: usize,
 
+    /// Maximum number of bytes buffered for sending on a stream.
+    max_send_buffer_size: usize,
+
+    /// If capacity is released when received data is taken.
+    auto_release_capacity: bool,
+
+    /// Maximum number of unread bytes buffered on a received stream.
+    max_recv_buffer_size: Option<usize>,
+
+    /// Budgets for frames the peer could flood the connection with.
+    flood: proto::FloodConfig,
+
     /// Initial `Settings` frame to send as part of the handshake.
     settings: Settings,
 
     /// The stream ID of the first (lowest) stream. Subsequent streams will use
     /// monotonically increasing stream IDs.
     stream_id: StreamId,
+
+    /// Timer used for keep-alive.
+    timer: Option<TimerHandle>,
+
+    /// Interval of keep-alive PINGs, if enabled.
+    keep_alive_interval: Option<Duration>,
+
+    /// How long to wait for a keep-alive PING to be acknowledged.
+    keep_alive_timeout: Duration,
+
+    /// Whether keep-alive PINGs are sent when there are no streams.
+    keep_alive_while_idle: bool,
+
+    /// Observer of the frames sent and received.
+    observer: Option<ObserverHandle>,
+
+    /// Frame types to receive as extension frames.
+    extension_types: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -421,6 +477,23 @@ where
     /// The caller should always set the request's version field to 2.0 unless
     /// specifically transmitting an HTTP 1.1 request over 2.0.
     ///
+    /// # Extensions
+    ///
+    /// A few request extensions are used when encoding the request:
+    ///
+    /// * A [`StreamPriority`] is sent in the `HEADERS` frame and sets the
+    ///   priority of the stream.
+    /// * A [`Protocol`] on a CONNECT request is sent as the `:protocol`
+    ///   pseudo header, see [`is_extended_connect_protocol_enabled`].
+    ///
+    /// # CONNECT requests
+    ///
+    /// A CONNECT request without a [`Protocol`] is sent with the `:method`
+    /// and `:authority` pseudo headers only, as the specification requires,
+    /// so its URI should be an authority such as `example.com:443`. The path
+    /// and scheme of its URI, if any, are not sent. An extended CONNECT
+    /// request is sent with `:scheme` and `:path` like any other request.
+    ///
     /// # Examples
     ///
     /// Sending a request with no body
@@ -492,6 +565,9 @@ where
     ///
     /// [`ResponseFuture`]: struct.ResponseFuture.html
     /// [`SendStream`]: ../struct.SendStream.html
+    /// [`StreamPriority`]: ../struct.StreamPriority.html
+    /// [`Protocol`]: ../ext/struct.Protocol.html
+    /// [`is_extended_connect_protocol_enabled`]: #method.is_extended_connect_protocol_enabled
     /// [`SendStream::send_data`]: ../struct.SendStream.html#method.send_data
     /// [`SendStream::send_trailers`]: ../struct.SendStream.html#method.send_trailers
     pub fn send_request(
@@ -517,6 +593,120 @@ where
                 (response, stream)
             })
     }
+
+    /// Returns whether the [extended CONNECT protocol][1] is enabled or not.
+    ///
+    /// This setting is configured by the server peer by sending the
+    /// [`SETTINGS_ENABLE_CONNECT_PROTOCOL` parameter][2] in a `SETTINGS` frame.
+    /// This method returns the currently acknowledged value received from the
+    /// remote.
+    ///
+    /// A CONNECT request carrying a [`Protocol`] extension can only be sent
+    /// once this returns `true`, otherwise [`send_request`] fails.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [2]: https://datatracker.ietf.org/doc/html/rfc8441#section-3
+    /// [`Protocol`]: ../ext/struct.Protocol.html
+    /// [`send_request`]: #method.send_request
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.inner.is_extended_connect_protocol_enabled()
+    }
+
+    /// Returns true if the connection no longer accepts new requests, because
+    /// the server sent a GOAWAY frame or the connection is closed.
+    ///
+    /// The streams the server processed before going away still complete,
+    /// but [`send_request`] fails with an error for which
+    /// [`Error::is_unprocessed`] returns `true`. A connection pool should
+    /// stop handing out this connection.
+    ///
+    /// [`send_request`]: #method.send_request
+    /// [`Error::is_unprocessed`]: ../struct.Error.html#method.is_unprocessed
+    pub fn is_draining(&self) -> bool {
+        self.inner.is_draining()
+    }
+
+    /// Returns `Ready` once the connection no longer accepts new requests,
+    /// see [`is_draining`].
+    ///
+    /// [`is_draining`]: #method.is_draining
+    pub fn poll_closed(&mut self, cx: &mut Context) -> Poll<()> {
+        self.inner.poll_closed(cx)
+    }
+
+    /// Waits until the connection no longer accepts new requests, see
+    /// [`is_draining`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use h2::client::*;
+    /// # async fn doc(mut send_request: SendRequest<&'static [u8]>)
+    /// # {
+    /// send_request.closed().await;
+    ///
+    /// if let Some((reason, debug_data)) = send_request.go_away_received() {
+    ///     println!("server going away: {}; {:?}", reason, debug_data);
+    /// }
+    /// # }
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`is_draining`]: #method.is_draining
+    pub async fn closed(&mut self) {
+        futures_util::future::poll_fn(move |cx| self.poll_closed(cx)).await
+    }
+
+    /// Returns the error code and the debug data of the last GOAWAY frame
+    /// received from the server, if any.
+    pub fn go_away_received(&self) -> Option<(Reason, Bytes)> {
+        self.inner.go_away_received()
+    }
+
+    /// Returns the origins the server advertised in ORIGIN frames (RFC 8336),
+    /// see [`Connection::origin_set`].
+    ///
+    /// [`Connection::origin_set`]: struct.Connection.html#method.origin_set
+    pub fn origin_set(&self) -> Option<Vec<Uri>> {
+        self.inner.origin_set()
+    }
+
+    /// Returns the settings of the server, see
+    /// [`Connection::remote_settings`].
+    ///
+    /// [`Connection::remote_settings`]: struct.Connection.html#method.remote_settings
+    pub fn remote_settings(&self) -> RemoteSettings {
+        self.inner.remote_settings()
+    }
+
+    /// Polls for a change of the settings of the server, see
+    /// [`Connection::poll_remote_settings`].
+    ///
+    /// Each `SendRequest` handle keeps track of the settings it returned,
+    /// clones start from those of the handle they were cloned from.
+    ///
+    /// [`Connection::poll_remote_settings`]: struct.Connection.html#method.poll_remote_settings
+    pub fn poll_remote_settings(&mut self, cx: &mut Context) -> Poll<Option<RemoteSettings>> {
+        let settings = ready!(self
+            .inner
+            .poll_remote_settings(cx, self.remote_settings_seen));
+
+        if let Some(ref settings) = settings {
+            self.remote_settings_seen = settings.generation();
+        }
+
+        Poll::Ready(settings)
+    }
+
+    /// Returns a snapshot of the statistics of the connection.
+    ///
+    /// This is the same as [`Connection::stats`], for use where only the
+    /// `SendRequest` handle is at hand.
+    ///
+    /// [`Connection::stats`]: struct.Connection.html#method.stats
+    pub fn stats(&self) -> ConnectionStats {
+        self.inner.stats()
+    }
 }
 
 impl<B> fmt::Debug for SendRequest<B>
@@ -536,6 +726,65 @@ where
         SendRequest {
             inner: self.inner.clone(),
             pending: None,
+            remote_settings_seen: self.remote_settings_seen,
+        }
+    }
+}
+
+impl SendRequest<Bytes> {
+    /// Sends a CONNECT request and waits for the response, whose body is the
+    /// [`Tunnel`] to the target.
+    ///
+    /// The request is sent when this is called, so the returned future doesn't
+    /// borrow the `SendRequest`, which may send more requests while waiting
+    /// for the response.
+    ///
+    /// The tunnel is established if the response status is 2xx. Otherwise,
+    /// reading the tunnel returns the body of the response.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request is not a CONNECT, in addition to the errors of
+    /// [`send_request`] and of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use h2::client::*;
+    /// # use http::*;
+    /// # async fn doc(mut send_request: SendRequest<bytes::Bytes>)
+    /// # {
+    /// let request = Request::connect("example.com:443")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let response = send_request.send_tunnel(request).await.unwrap();
+    ///
+    /// if response.status().is_success() {
+    ///     let tunnel = response.into_body();
+    ///     // Use `tunnel` as an `AsyncRead + AsyncWrite`.
+    /// }
+    /// # }
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Tunnel`]: ../struct.Tunnel.html
+    /// [`send_request`]: #method.send_request
+    pub fn send_tunnel(
+        &mut self,
+        request: Request<()>,
+    ) -> impl Future<Output = Result<Response<Tunnel>, crate::Error>> {
+        let sent = if request.method() == Method::CONNECT {
+            self.send_request(request, false)
+        } else {
+            Err(UserError::InvalidTunnel.into())
+        };
+
+        async move {
+            let (response, send) = sent?;
+            let response = response.await?;
+
+            Ok(response.map(|recv| Tunnel::new(send, recv)))
         }
     }
 }
@@ -616,10 +865,21 @@ impl Builder {
         Builder {
             reset_stream_duration: Duration::from_secs(proto::DEFAULT_RESET_STREAM_SECS),
             reset_stream_max: proto::DEFAULT_RESET_STREAM_MAX,
+            max_send_buffer_size: proto::DEFAULT_MAX_SEND_BUFFER_SIZE,
+            auto_release_capacity: false,
+            max_recv_buffer_size: None,
+            flood: proto::FloodConfig::default(),
             initial_target_connection_window_size: None,
+            max_adaptive_window_size: None,
             initial_max_send_streams: usize::MAX,
             settings: Default::default(),
             stream_id: 1.into(),
+            timer: None,
+            keep_alive_interval: None,
+            keep_alive_timeout: Duration::from_secs(proto::DEFAULT_KEEP_ALIVE_TIMEOUT_SECS),
+            keep_alive_while_idle: false,
+            observer: None,
+            extension_types: Vec::new(),
         }
     }
 
@@ -693,6 +953,57 @@ impl Builder {
         self
     }
 
+    /// Enables adaptive flow control for received data, growing the windows
+    /// up to `max_window_size`.
+    ///
+    /// The bandwidth-delay product of the connection is estimated by sending
+    /// a PING when DATA is received and counting the bytes received until it
+    /// is acknowledged. When the windows are found to limit the throughput,
+    /// the connection window and the initial stream window are grown to fit
+    /// the estimate. The stream window is updated with a SETTINGS frame,
+    /// which never makes the SETTINGS sent through [`Connection`] fail.
+    ///
+    /// The windows configured with [`initial_window_size`] and
+    /// [`initial_connection_window_size`] are used as a starting point, they
+    /// are never shrunk.
+    ///
+    /// Adaptive flow control is disabled by default.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_window_size` exceeds the maximum window
+    /// size of 2^31-1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .adaptive_window(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`initial_connection_window_size`]: #method.initial_connection_window_size
+    /// [`Connection`]: struct.Connection.html
+    pub fn adaptive_window(&mut self, max_window_size: u32) -> &mut Self {
+        assert!(max_window_size <= proto::MAX_WINDOW_SIZE);
+        self.max_adaptive_window_size = Some(max_window_size);
+        self
+    }
+
     /// Indicates the size (in octets) of the largest HTTP/2.0 frame payload that the
     /// configured client is able to accept.
     ///
@@ -767,6 +1078,46 @@ impl Builder {
         self
     }
 
+    /// Sets the size of the HPACK dynamic table used to decode received
+    /// headers.
+    ///
+    /// This is sent to the peer as `SETTINGS_HEADER_TABLE_SIZE`, the largest
+    /// table its encoder may use. A smaller table saves memory, a larger one
+    /// compresses repeated headers better. Setting it to 0 disables the
+    /// dynamic table.
+    ///
+    /// The new size is applied once the peer acknowledges the settings. If it
+    /// is smaller than the table currently in use, the peer must then shrink
+    /// its table at the start of the next header block, or the connection is
+    /// closed with a protocol error.
+    ///
+    /// The default value is 4,096.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .header_table_size(0)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn header_table_size(&mut self, size: u32) -> &mut Self {
+        self.settings.set_header_table_size(Some(size));
+        self
+    }
+
     /// Sets the maximum number of concurrent streams.
     ///
     /// The maximum concurrent streams setting only controls the maximum number
@@ -902,32 +1253,26 @@ impl Builder {
         self
     }
 
-    /// Sets the duration to remember locally reset streams.
-    ///
-    /// When a stream is explicitly reset, the HTTP/2.0 specification requires
-    /// that any further frames received for that stream must be ignored for
-    /// "some time".
+    /// Sets the maximum number of bytes buffered for sending on each stream.
     ///
-    /// In order to satisfy the specification, internal state must be maintained
-    /// to implement the behavior. This state grows linearly with the number of
-    /// streams that are locally reset.
+    /// Data passed to `SendStream::send_data` is buffered until the peer's
+    /// flow control window and the connection allow it to be written. This
+    /// setting bounds that buffer: the capacity assigned to a stream never
+    /// lets more than `max` bytes be buffered, and
+    /// `SendStream::poll_send_buffer_ready` only becomes ready once the
+    /// buffered data drops below `max`.
     ///
-    /// The `reset_stream_duration` setting configures the max amount of time
-    /// this state will be maintained in memory. Once the duration elapses, the
-    /// stream state is purged from memory.
+    /// The default value is 400KB.
     ///
-    /// Once the stream has been fully purged from memory, any additional frames
-    /// received for that stream will result in a connection level protocol
-    /// error, forcing the connection to terminate.
+    /// # Panics
     ///
-    /// The default value is 30 seconds.
+    /// This function panics if `max` is larger than `u32::MAX`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
-    /// # use std::time::Duration;
     /// # use bytes::Bytes;
     /// #
     /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
@@ -936,36 +1281,35 @@ impl Builder {
     /// // `client_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let client_fut = Builder::new()
-    ///     .reset_stream_duration(Duration::from_secs(10))
+    ///     .max_send_buffer_size(64 * 1024)
     ///     .handshake(my_io);
     /// # client_fut.await
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
-    pub fn reset_stream_duration(&mut self, dur: Duration) -> &mut Self {
-        self.reset_stream_duration = dur;
+    pub fn max_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        assert!(max <= u32::MAX as usize);
+        self.max_send_buffer_size = max;
         self
     }
 
-    /// Enables or disables server push promises.
-    ///
-    /// This value is included in the initial SETTINGS handshake. When set, the
-    /// server MUST NOT send a push promise. Setting this value to value to
-    /// false in the initial SETTINGS handshake guarantees that the remote server
-    /// will never send a push promise.
+    /// Sets whether received data releases its capacity when taken.
     ///
-    /// This setting can be changed during the life of a single HTTP/2.0
-    /// connection by sending another settings frame updating the value.
+    /// When enabled, the capacity of each data frame is released back to the
+    /// peer as soon as `RecvStream` hands it to the caller, so calling
+    /// `FlowControl::release_capacity` is not needed. When disabled, which is
+    /// the default, the caller must release capacity itself, or the stream and
+    /// connection windows eventually stall.
     ///
-    /// Default value: `true`.
+    /// This can be overridden per stream with
+    /// `FlowControl::set_auto_release_capacity`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
-    /// # use std::time::Duration;
     /// # use bytes::Bytes;
     /// #
     /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
@@ -974,100 +1318,710 @@ impl Builder {
     /// // `client_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let client_fut = Builder::new()
-    ///     .enable_push(false)
+    ///     .auto_release_capacity(true)
     ///     .handshake(my_io);
     /// # client_fut.await
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
-    pub fn enable_push(&mut self, enabled: bool) -> &mut Self {
-        self.settings.set_enable_push(enabled);
-        self
-    }
-
-    /// Sets the first stream ID to something other than 1.
-    #[cfg(feature = "unstable")]
-    pub fn initial_stream_id(&mut self, stream_id: u32) -> &mut Self {
-        self.stream_id = stream_id.into();
-        assert!(
-            self.stream_id.is_client_initiated(),
-            "stream id must be odd"
-        );
+    pub fn auto_release_capacity(&mut self, enabled: bool) -> &mut Self {
+        self.auto_release_capacity = enabled;
         self
     }
 
-    /// Creates a new configured HTTP/2.0 client backed by `io`.
-    ///
-    /// It is expected that `io` already be in an appropriate state to commence
-    /// the [HTTP/2.0 handshake]. The handshake is completed once both the connection
-    /// preface and the initial settings frame is sent by the client.
-    ///
-    /// The handshake future does not wait for the initial settings frame from the
-    /// server.
-    ///
-    /// Returns a future which resolves to the [`Connection`] / [`SendRequest`]
-    /// tuple once the HTTP/2.0 handshake has been completed.
+    /// Sets the maximum number of unread bytes buffered on each received
+    /// stream.
     ///
-    /// This function also allows the caller to configure the send payload data
-    /// type. See [Outbound data type] for more details.
+    /// Received data is buffered until taken from the `RecvStream`. With a
+    /// maximum set, `WINDOW_UPDATE` frames for a stream are held back while
+    /// they would let the buffered data grow past `max`, whether or not the
+    /// capacity was released. The peer may still send up to the initial
+    /// window size before any data is taken.
     ///
-    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
-    /// [`Connection`]: struct.Connection.html
-    /// [`SendRequest`]: struct.SendRequest.html
-    /// [Outbound data type]: ../index.html#outbound-data-type.
+    /// By default there is no maximum other than the window size.
     ///
     /// # Examples
     ///
-    /// Basic usage:
-    ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
     /// # use bytes::Bytes;
     /// #
     /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
-    ///     -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
     /// # {
     /// // `client_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let client_fut = Builder::new()
+    ///     .max_recv_buffer_size(256 * 1024)
     ///     .handshake(my_io);
     /// # client_fut.await
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
+    pub fn max_recv_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_recv_buffer_size = Some(max);
+        self
+    }
+
+    /// Sets the duration to remember locally reset streams.
     ///
-    /// Configures the send-payload data type. In this case, the outbound data
-    /// type will be `&'static [u8]`.
+    /// When a stream is explicitly reset, the HTTP/2.0 specification requires
+    /// that any further frames received for that stream must be ignored for
+    /// "some time".
+    ///
+    /// In order to satisfy the specification, internal state must be maintained
+    /// to implement the behavior. This state grows linearly with the number of
+    /// streams that are locally reset.
+    ///
+    /// The `reset_stream_duration` setting configures the max amount of time
+    /// this state will be maintained in memory. Once the duration elapses, the
+    /// stream state is purged from memory.
+    ///
+    /// Once the stream has been fully purged from memory, any additional frames
+    /// received for that stream will result in a connection level protocol
+    /// error, forcing the connection to terminate.
+    ///
+    /// The default value is 30 seconds.
+    ///
+    /// # Examples
     ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
+    /// # use std::time::Duration;
+    /// # use bytes::Bytes;
     /// #
     /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
-    /// # -> Result<((SendRequest<&'static [u8]>, Connection<T, &'static [u8]>)), h2::Error>
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
     /// # {
     /// // `client_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let client_fut = Builder::new()
-    ///     .handshake::<_, &'static [u8]>(my_io);
+    ///     .reset_stream_duration(Duration::from_secs(10))
+    ///     .handshake(my_io);
     /// # client_fut.await
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
-    pub fn handshake<T, B>(
-        &self,
-        io: T,
-    ) -> impl Future<Output = Result<(SendRequest<B>, Connection<T, B>), crate::Error>>
-    where
-        T: AsyncRead + AsyncWrite + Unpin,
+    pub fn reset_stream_duration(&mut self, dur: Duration) -> &mut Self {
+        self.reset_stream_duration = dur;
+        self
+    }
+
+    /// Sets the maximum number of PING and SETTINGS acknowledgements that may
+    /// be waiting to be written to the socket.
+    ///
+    /// Every PING and SETTINGS frame received has to be acknowledged. A peer
+    /// sending them faster than it reads the acknowledgements makes these
+    /// pile up (CVE-2019-9512 and CVE-2019-9515). Once more than `max` are
+    /// pending, the connection is closed with a GOAWAY frame carrying
+    /// `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 100.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_pending_control_replies(200)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_pending_control_replies(&mut self, max: usize) -> &mut Self {
+        self.flood.max_pending_replies = max;
+        self
+    }
+
+    /// Sets the maximum number of empty DATA frames the peer may send in a
+    /// row.
+    ///
+    /// A DATA frame with neither payload nor the END_STREAM flag carries
+    /// nothing, yet has to be processed like any other (CVE-2019-9518). When
+    /// the peer sends more than `max` of them without a DATA frame with
+    /// payload in between, the connection is closed with a GOAWAY frame
+    /// carrying `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 10.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_empty_data_frames(20)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_empty_data_frames(&mut self, max: usize) -> &mut Self {
+        self.flood.max_empty_data_frames = max;
+        self
+    }
+
+    /// Sets the budget of WINDOW_UPDATE and PRIORITY frames the peer may send.
+    ///
+    /// The peer may send `max` WINDOW_UPDATE, PRIORITY and PRIORITY_UPDATE
+    /// frames up front, and `max` more for every stream opened and every DATA
+    /// frame sent on the connection. Beyond that, these frames only make the
+    /// connection churn its flow control and priority state, and the
+    /// connection is closed with a GOAWAY frame carrying `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 10.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_churn_frames(20)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_churn_frames(&mut self, max: usize) -> &mut Self {
+        self.flood.max_churn_frames = max;
+        self
+    }
+
+    /// Sets the timer used by the connection.
+    ///
+    /// A timer is required by the keep-alive options and by
+    /// [`Connection::graceful_shutdown_with_timeout`], which need to wait
+    /// without depending on a specific runtime. See [`Timer`] for how to
+    /// provide one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use std::time::Duration;
+    /// # use bytes::Bytes;
+    /// #
+    /// # fn my_timer() -> impl h2::Timer {
+    /// #     struct T;
+    /// #     impl h2::Timer for T {
+    /// #         fn sleep(&self, _: Duration) -> h2::Sleep { Box::pin(async {}) }
+    /// #     }
+    /// #     T
+    /// # }
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .timer(my_timer())
+    ///     .keep_alive_interval(Duration::from_secs(30))
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Timer`]: ../trait.Timer.html
+    /// [`Connection::graceful_shutdown_with_timeout`]: struct.Connection.html#method.graceful_shutdown_with_timeout
+    pub fn timer<T: Timer + 'static>(&mut self, timer: T) -> &mut Self {
+        self.timer = Some(TimerHandle::new(timer));
+        self
+    }
+
+    /// Enables keep-alive PINGs, sent whenever nothing has been received from
+    /// the peer for `interval`.
+    ///
+    /// If the peer doesn't acknowledge a PING within the
+    /// [`keep_alive_timeout`], the connection is closed with a GOAWAY and
+    /// fails with a timeout error.
+    ///
+    /// This requires a [`timer`] to be set.
+    ///
+    /// Keep-alive is disabled by default.
+    ///
+    /// # Errors
+    ///
+    /// The handshake fails if this is set without a [`timer`].
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_timeout`]: #method.keep_alive_timeout
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_interval(&mut self, interval: Duration) -> &mut Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long to wait for a keep-alive PING to be acknowledged before
+    /// closing the connection.
+    ///
+    /// This has no effect unless [`keep_alive_interval`] is set.
+    ///
+    /// The default value is 20 seconds.
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_interval`]: #method.keep_alive_interval
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.keep_alive_timeout = timeout;
+        self
+    }
+
+    /// Sets whether keep-alive PINGs are sent while the connection has no
+    /// open streams.
+    ///
+    /// If disabled, an idle connection is left alone and can be closed
+    /// silently by the peer or an intermediary.
+    ///
+    /// This has no effect unless [`keep_alive_interval`] is set.
+    ///
+    /// The default value is `false`.
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_interval`]: #method.keep_alive_interval
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_while_idle(&mut self, enabled: bool) -> &mut Self {
+        self.keep_alive_while_idle = enabled;
+        self
+    }
+
+    /// Sets an observer to tell about every frame sent and received.
+    ///
+    /// The observer is given a description of each frame, including the type
+    /// specific fields and the payload length but not the payload itself.
+    /// See [`FrameObserver`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use h2::{FrameInfo, FrameObserver};
+    /// # use bytes::Bytes;
+    /// #
+    /// #[derive(Debug)]
+    /// struct Tracer;
+    ///
+    /// impl FrameObserver for Tracer {
+    ///     fn frame_received(&self, frame: &FrameInfo) {
+    ///         println!("<- {:?}", frame);
+    ///     }
+    ///
+    ///     fn frame_sent(&self, frame: &FrameInfo) {
+    ///         println!("-> {:?}", frame);
+    ///     }
+    /// }
+    ///
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .frame_observer(Tracer)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`FrameObserver`]: ../trait.FrameObserver.html
+    pub fn frame_observer<O: FrameObserver + 'static>(&mut self, observer: O) -> &mut Self {
+        self.observer = Some(ObserverHandle::new(observer));
+        self
+    }
+
+    /// Registers a frame type to receive as an extension frame.
+    ///
+    /// Frames of an unknown type are ignored by default, as required by the
+    /// specification. Those of a registered type are instead made available
+    /// with [`Connection::poll_extension_frame`], once the maximum frame size
+    /// has been checked. This can be called multiple times, to register
+    /// several types.
+    ///
+    /// The frames received are buffered until they are taken. The peer is
+    /// sent a GOAWAY with `ENHANCE_YOUR_CALM` if more than 1 MiB of them,
+    /// frame headers included, pile up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame_type` is the type of a frame implemented by the
+    /// connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .extension_frame_type(0xb)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Connection::poll_extension_frame`]: struct.Connection.html#method.poll_extension_frame
+    pub fn extension_frame_type(&mut self, frame_type: u8) -> &mut Self {
+        assert!(
+            frame::Kind::new(frame_type) == frame::Kind::Unknown,
+            "frame type {} is implemented by the connection",
+            frame_type
+        );
+
+        if !self.extension_types.contains(&frame_type) {
+            self.extension_types.push(frame_type);
+        }
+        self
+    }
+
+    /// Enables or disables server push promises.
+    ///
+    /// This value is included in the initial SETTINGS handshake. When set, the
+    /// server MUST NOT send a push promise. Setting this value to value to
+    /// false in the initial SETTINGS handshake guarantees that the remote server
+    /// will never send a push promise.
+    ///
+    /// This setting can be changed during the life of a single HTTP/2.0
+    /// connection by sending another settings frame updating the value.
+    ///
+    /// Default value: `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use std::time::Duration;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .enable_push(false)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn enable_push(&mut self, enabled: bool) -> &mut Self {
+        self.settings.set_enable_push(enabled);
+        self
+    }
+
+    /// Adds a setting this crate doesn't implement to the initial SETTINGS
+    /// frame.
+    ///
+    /// This is how many extensions are negotiated. Calling this again with
+    /// the same identifier replaces the value. The settings received from
+    /// the peer that this crate doesn't implement are available with
+    /// [`Connection::remote_custom_settings`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is the identifier of a setting implemented by the
+    /// connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .custom_setting(0xf0f0, 1)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Connection::remote_custom_settings`]: struct.Connection.html#method.remote_custom_settings
+    pub fn custom_setting(&mut self, id: u16, value: u32) -> &mut Self {
+        self.settings.set_unknown_setting(id, value);
+        self
+    }
+
+    /// Enables the [extended CONNECT protocol].
+    ///
+    /// This advertises `SETTINGS_ENABLE_CONNECT_PROTOCOL` in the initial
+    /// SETTINGS frame, telling the remote that it may send CONNECT requests
+    /// carrying a `:protocol` pseudo-header. Since only clients open streams,
+    /// this is mostly useful for servers; it is offered here for symmetry.
+    ///
+    /// Sending an extended CONNECT request is governed by the remote's
+    /// setting instead, see
+    /// [`SendRequest::is_extended_connect_protocol_enabled`].
+    ///
+    /// Default value: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .enable_connect_protocol()
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [extended CONNECT protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [`SendRequest::is_extended_connect_protocol_enabled`]: struct.SendRequest.html#method.is_extended_connect_protocol_enabled
+    pub fn enable_connect_protocol(&mut self) -> &mut Self {
+        self.settings.set_enable_connect_protocol(Some(1));
+        self
+    }
+
+    /// Sets the first stream ID to something other than 1.
+    #[cfg(feature = "unstable")]
+    pub fn initial_stream_id(&mut self, stream_id: u32) -> &mut Self {
+        self.stream_id = stream_id.into();
+        assert!(
+            self.stream_id.is_client_initiated(),
+            "stream id must be odd"
+        );
+        self
+    }
+
+    /// Creates a new configured HTTP/2.0 client backed by `io`.
+    ///
+    /// It is expected that `io` already be in an appropriate state to commence
+    /// the [HTTP/2.0 handshake]. The handshake is completed once both the connection
+    /// preface and the initial settings frame is sent by the client.
+    ///
+    /// The handshake future does not wait for the initial settings frame from the
+    /// server.
+    ///
+    /// Returns a future which resolves to the [`Connection`] / [`SendRequest`]
+    /// tuple once the HTTP/2.0 handshake has been completed.
+    ///
+    /// This function also allows the caller to configure the send payload data
+    /// type. See [Outbound data type] for more details.
+    ///
+    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
+    /// [`Connection`]: struct.Connection.html
+    /// [`SendRequest`]: struct.SendRequest.html
+    /// [Outbound data type]: ../index.html#outbound-data-type.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    ///     -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// Configures the send-payload data type. In this case, the outbound data
+    /// type will be `&'static [u8]`.
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<&'static [u8]>, Connection<T, &'static [u8]>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .handshake::<_, &'static [u8]>(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake<T, B>(
+        &self,
+        io: T,
+    ) -> impl Future<Output = Result<(SendRequest<B>, Connection<T, B>), crate::Error>>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
         B: Buf + 'static,
     {
         Connection::handshake2(io, self.clone())
     }
+
+    /// Returns the value of the `HTTP2-Settings` header to send, along with
+    /// `Connection: Upgrade, HTTP2-Settings` and `Upgrade: h2c`, on an
+    /// HTTP/1.1 request asking to upgrade the connection to HTTP/2.0.
+    ///
+    /// The value is the base64url encoded SETTINGS frame that
+    /// [`handshake_upgraded`] assumes the server applied, so it has to be
+    /// taken from the same builder.
+    ///
+    /// [`handshake_upgraded`]: #method.handshake_upgraded
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::client::*;
+    /// # use http::Request;
+    /// #
+    /// # pub fn main() {
+    /// let builder = Builder::new();
+    ///
+    /// let request = Request::get("http://example.com/")
+    ///     .header("connection", "Upgrade, HTTP2-Settings")
+    ///     .header("upgrade", "h2c")
+    ///     .header("http2-settings", builder.http2_settings())
+    ///     .body(())
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn http2_settings(&self) -> String {
+        self.settings.encode_http2_settings()
+    }
+
+    /// Creates a new configured HTTP/2.0 client on a connection upgraded from
+    /// HTTP/1.1.
+    ///
+    /// `request` is the HTTP/1.1 request carrying the `Upgrade: h2c` header,
+    /// with the `HTTP2-Settings` header returned by [`http2_settings`], that
+    /// the server answered with `101 Switching Protocols`. `io` is the
+    /// connection, positioned right after that response.
+    ///
+    /// The request continues as stream 1, in the half-closed (local) state:
+    /// its body, if any, was sent over HTTP/1.1 and the response is received
+    /// over HTTP/2.0 through the returned [`ResponseFuture`]. The future
+    /// resolves once the connection preface and the initial SETTINGS frame
+    /// are sent, like [`handshake`].
+    ///
+    /// [`http2_settings`]: #method.http2_settings
+    /// [`handshake`]: #method.handshake
+    /// [`ResponseFuture`]: struct.ResponseFuture.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// # use http::Request;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T, request: Request<()>)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>, ResponseFuture)), h2::Error>
+    /// # {
+    /// // `request` was sent over HTTP/1.1 and the server switched protocols.
+    /// let client_fut = Builder::new()
+    ///     .handshake_upgraded(my_io, request);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake_upgraded<T, B>(
+        &self,
+        io: T,
+        request: Request<()>,
+    ) -> impl Future<Output = Result<Upgraded<T, B>, crate::Error>>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        let mut builder = self.clone();
+        // The upgraded request is implicitly stream 1
+        builder.stream_id = 1.into();
+
+        Connection::handshake_upgraded2(io, builder, request)
+    }
+
+    fn keep_alive_config(&self) -> Result<Option<proto::KeepAliveConfig>, UserError> {
+        let interval = match self.keep_alive_interval {
+            Some(interval) => interval,
+            None => return Ok(None),
+        };
+        let timer = self.timer.clone().ok_or(UserError::MissingTimer)?;
+
+        Ok(Some(proto::KeepAliveConfig {
+            interval,
+            timeout: self.keep_alive_timeout,
+            while_idle: self.keep_alive_while_idle,
+            timer,
+        }))
+    }
 }
 
 impl Default for Builder {
@@ -1132,6 +2086,8 @@ where
     ) -> Result<(SendRequest<B>, Connection<T, B>), crate::Error> {
         log::debug!("binding client connection");
 
+        let keep_alive = builder.keep_alive_config()?;
+
         let msg: &'static [u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
         io.write_all(msg).await.map_err(crate::Error::from_io)?;
 
@@ -1148,6 +2104,14 @@ where
             codec.set_max_recv_header_list_size(max as usize);
         }
 
+        if let Some(ref observer) = builder.observer {
+            codec.set_observer(observer.clone());
+        }
+
+        if !builder.extension_types.is_empty() {
+            codec.set_extension_types(builder.extension_types.clone());
+        }
+
         // Send initial settings frame
         codec
             .buffer(builder.settings.clone().into())
@@ -1160,15 +2124,27 @@ where
                 initial_max_send_streams: builder.initial_max_send_streams,
                 reset_stream_duration: builder.reset_stream_duration,
                 reset_stream_max: builder.reset_stream_max,
+                max_send_buffer_size: builder.max_send_buffer_size,
+                auto_release_capacity: builder.auto_release_capacity,
+                max_recv_buffer_size: builder.max_recv_buffer_size,
+                remote_reset_limit: None,
                 settings: builder.settings.clone(),
+                keep_alive,
+                max_adaptive_window_size: builder.max_adaptive_window_size,
+                flood: builder.flood.clone(),
+                timer: builder.timer.clone(),
             },
         );
         let send_request = SendRequest {
             inner: inner.streams().clone(),
             pending: None,
+            remote_settings_seen: 0,
         };
 
-        let mut connection = Connection { inner };
+        let mut connection = Connection {
+            inner,
+            remote_settings_seen: 0,
+        };
         if let Some(sz) = builder.initial_target_connection_window_size {
             connection.set_target_window_size(sz);
         }
@@ -1176,6 +2152,26 @@ where
         Ok((send_request, connection))
     }
 
+    async fn handshake_upgraded2(
+        io: T,
+        builder: Builder,
+        request: Request<()>,
+    ) -> Result<Upgraded<T, B>, crate::Error> {
+        let (mut send_request, connection) = Self::handshake2(io, builder).await?;
+
+        let stream = send_request
+            .inner
+            .send_upgrade_request(&request)
+            .map_err(crate::Error::from)?;
+
+        let response = ResponseFuture {
+            inner: stream.clone_to_opaque(),
+            push_promise_consumed: false,
+        };
+
+        Ok((send_request, connection, response))
+    }
+
     /// Sets the target window size for the whole connection.
     ///
     /// If `size` is greater than the current value, then a `WINDOW_UPDATE`
@@ -1210,13 +2206,118 @@ where
     /// # Errors
     ///
     /// Returns an error if a previous call is still pending acknowledgement
-    /// from the remote endpoint.
+    /// from the remote endpoint. The SETTINGS sent by the connection for the
+    /// [adaptive window] don't count, they are merged with these or sent
+    /// before them, and this window size overrides theirs.
+    ///
+    /// [adaptive window]: struct.Builder.html#method.adaptive_window
     pub fn set_initial_window_size(&mut self, size: u32) -> Result<(), crate::Error> {
         assert!(size <= proto::MAX_WINDOW_SIZE);
         self.inner.set_initial_window_size(size)?;
         Ok(())
     }
 
+    /// Sends a new SETTINGS frame with settings this crate doesn't
+    /// implement, as (identifier, value) pairs.
+    ///
+    /// The `SETTINGS` will be sent to the remote, which acknowledges them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a previous SETTINGS frame is still pending
+    /// acknowledgement from the remote endpoint. The SETTINGS sent by the
+    /// connection for the [adaptive window] don't count, they are merged with
+    /// these or sent before them.
+    ///
+    /// Also returns an error if an identifier is the one of a setting
+    /// implemented by the connection, in which case nothing is sent.
+    ///
+    /// [adaptive window]: struct.Builder.html#method.adaptive_window
+    pub fn send_custom_settings(&mut self, settings: &[(u16, u32)]) -> Result<(), crate::Error> {
+        self.inner.send_custom_settings(settings)?;
+        Ok(())
+    }
+
+    /// Returns the settings received from the remote that this crate
+    /// doesn't implement, as (identifier, value) pairs with their latest
+    /// value.
+    ///
+    /// These are also available as [`RemoteSettings::custom`].
+    ///
+    /// [`RemoteSettings::custom`]: ../struct.RemoteSettings.html#method.custom
+    pub fn remote_custom_settings(&self) -> Vec<(u16, u32)> {
+        self.inner.remote_settings().custom().to_vec()
+    }
+
+    /// Returns the settings of the server, as last received.
+    ///
+    /// Until the first SETTINGS frame of the server is received, these are
+    /// the defaults of the specification.
+    pub fn remote_settings(&self) -> RemoteSettings {
+        self.inner.remote_settings()
+    }
+
+    /// Polls for a change of the settings of the server.
+    ///
+    /// Returns the settings once they differ from those last returned by
+    /// this function, so the first call returns once the initial SETTINGS
+    /// frame of the server is received. Returns `None` once the connection
+    /// is closed. The connection must still be polled for the settings to
+    /// be received.
+    pub fn poll_remote_settings(&mut self, cx: &mut Context) -> Poll<Option<RemoteSettings>> {
+        let settings = ready!(self
+            .inner
+            .poll_remote_settings(cx, self.remote_settings_seen));
+
+        if let Some(ref settings) = settings {
+            self.remote_settings_seen = settings.generation();
+        }
+
+        Poll::Ready(settings)
+    }
+
+    /// Starts a [graceful shutdown][1] process.
+    ///
+    /// Must continue being polled to close connection.
+    ///
+    /// A GOAWAY frame with `NO_ERROR` is sent to the server, and new requests
+    /// fail with an error for which [`Error::is_unprocessed`] returns true.
+    /// Once all active streams have completed, the connection is closed.
+    ///
+    /// [1]: http://httpwg.org/specs/rfc7540.html#GOAWAY
+    /// [`Error::is_unprocessed`]: ../struct.Error.html#method.is_unprocessed
+    pub fn graceful_shutdown(&mut self) {
+        self.inner.go_away_gracefully();
+    }
+
+    /// Starts a graceful shutdown process that turns abrupt once `timeout`
+    /// has passed.
+    ///
+    /// This behaves like [`graceful_shutdown`] until the deadline. If streams
+    /// are still active then, the connection is closed without waiting for
+    /// them.
+    ///
+    /// The returned [`GracefulShutdown`] resolves once the shutdown is over,
+    /// with the streams that were cut off, if any. The connection must still
+    /// be polled for the shutdown to make progress. Calling this again
+    /// returns a handle to the same shutdown, and the first deadline stays.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the connection was built without a [`timer`].
+    ///
+    /// [`graceful_shutdown`]: #method.graceful_shutdown
+    /// [`GracefulShutdown`]: ../struct.GracefulShutdown.html
+    /// [`timer`]: struct.Builder.html#method.timer
+    pub fn graceful_shutdown_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<GracefulShutdown, crate::Error> {
+        self.inner
+            .go_away_gracefully_with_timeout(timeout)
+            .map_err(Into::into)
+    }
+
     /// Takes a `PingPong` instance from the connection.
     ///
     /// # Note
@@ -1225,6 +2326,74 @@ where
     pub fn ping_pong(&mut self) -> Option<PingPong> {
         self.inner.take_user_pings().map(PingPong::new)
     }
+
+    /// Queues an extension frame to send to the peer.
+    ///
+    /// The frame is written the next time the connection is polled. Its type
+    /// must not be one implemented by the connection, and its payload must
+    /// fit in the maximum frame size advertised by the peer.
+    pub fn send_extension_frame(
+        &mut self,
+        frame_type: u8,
+        flags: u8,
+        stream_id: crate::StreamId,
+        payload: Bytes,
+    ) -> Result<(), crate::Error> {
+        let frame = frame::Extension::new(frame_type, flags, stream_id.to_internal(), payload);
+        self.inner.send_extension_frame(frame)?;
+        Ok(())
+    }
+
+    /// Polls for the next extension frame received.
+    ///
+    /// Only the frames of the types registered with
+    /// [`Builder::extension_frame_type`] are received. Returns `None` once the
+    /// connection is closed and all the frames were taken. The connection
+    /// must still be polled for frames to be received.
+    ///
+    /// [`Builder::extension_frame_type`]: struct.Builder.html#method.extension_frame_type
+    pub fn poll_extension_frame(&mut self, cx: &mut Context) -> Poll<Option<ExtensionFrame>> {
+        self.inner
+            .poll_extension_frame(cx)
+            .map(|frame| frame.map(ExtensionFrame::from_internal))
+    }
+
+    /// Polls for the next ALTSVC frame received (RFC 7838 §4).
+    ///
+    /// The server advertises alternative services with these frames, before
+    /// the `Alt-Svc` header of a response would reach the client. Returns
+    /// `None` once the connection is closed and all the frames were taken.
+    /// The connection must still be polled for frames to be received.
+    ///
+    /// Only the 16 most recent frames not taken yet are kept, so a client
+    /// that isn't interested in them doesn't have to call this.
+    pub fn poll_alt_svc(&mut self, cx: &mut Context) -> Poll<Option<AltSvc>> {
+        self.inner
+            .poll_alt_svc(cx)
+            .map(|frame| frame.map(AltSvc::from_internal))
+    }
+
+    /// Returns the origins the server advertised in ORIGIN frames (RFC 8336).
+    ///
+    /// The server is authoritative for these origins, so the connection may
+    /// be reused for requests to them. Returns `None` until an ORIGIN frame
+    /// is received, the origin the connection was made for is not included.
+    /// Each frame received adds to the set, up to 256 origins, which are
+    /// returned in no particular order.
+    pub fn origin_set(&self) -> Option<Vec<Uri>> {
+        self.inner.origin_set()
+    }
+
+    /// Returns a snapshot of the statistics of the connection.
+    ///
+    /// This includes the bytes and frames sent and received, the stream
+    /// counts, the connection level windows and the last PING round-trip
+    /// time. See [`ConnectionStats`] for details.
+    ///
+    /// [`ConnectionStats`]: ../struct.ConnectionStats.html
+    pub fn stats(&self) -> ConnectionStats {
+        self.inner.stats()
+    }
 }
 
 impl<T, B> Future for Connection<T, B>
@@ -1288,6 +2457,80 @@ impl ResponseFuture {
             inner: self.inner.clone(),
         }
     }
+
+    /// Returns a stream of the informational (1xx) responses, such as
+    /// `100 Continue` or `103 Early Hints`, received before the final
+    /// response.
+    ///
+    /// The stream ends once the final response has been received. Any
+    /// informational response not taken from the stream by then is
+    /// discarded.
+    ///
+    /// # Examples
+    ///
+    /// Waiting for `100 Continue` before sending the request body:
+    ///
+    /// ```
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// # use http::{Request, StatusCode};
+    /// #
+    /// # async fn doc(mut send_request: SendRequest<Bytes>) -> Result<(), h2::Error> {
+    /// let request = Request::post("https://example.com/upload")
+    ///     .header("expect", "100-continue")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let (mut response, mut send_stream) = send_request.send_request(request, false)?;
+    ///
+    /// if let Some(informational) = response.informational_responses().informational().await {
+    ///     if informational?.status() == StatusCode::CONTINUE {
+    ///         send_stream.send_data(Bytes::from_static(b"hello"), true)?;
+    ///     }
+    /// }
+    ///
+    /// let response = response.await?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn informational_responses(&mut self) -> InformationalResponses {
+        InformationalResponses {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// ===== impl InformationalResponses =====
+
+impl InformationalResponses {
+    /// Get the next informational response, or `None` once the final response
+    /// has been received.
+    pub async fn informational(&mut self) -> Option<Result<Response<()>, crate::Error>> {
+        futures_util::future::poll_fn(move |cx| self.poll_informational(cx)).await
+    }
+
+    #[doc(hidden)]
+    pub fn poll_informational(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Response<()>, crate::Error>>> {
+        match ready!(self.inner.poll_informational(cx)) {
+            Some(Ok(response)) => Poll::Ready(Some(Ok(response))),
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for InformationalResponses {
+    type Item = Result<Response<()>, crate::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_informational(cx)
+    }
 }
 
 // ===== impl PushPromises =====
@@ -1386,6 +2629,7 @@ impl Peer {
                 uri,
                 headers,
                 version,
+                mut extensions,
                 ..
             },
             _,
@@ -1393,9 +2637,26 @@ impl Peer {
 
         let is_connect = method == Method::CONNECT;
 
+        // The `:protocol` pseudo header is only valid on a CONNECT request.
+        let protocol = extensions.remove::<Protocol>();
+
+        let dependency = extensions
+            .remove::<StreamPriority>()
+            .map(StreamPriority::into_dependency);
+
+        if let Some(ref dependency) = dependency {
+            if dependency.dependency_id() == id {
+                return Err(UserError::InvalidStreamDependency.into());
+            }
+        }
+
+        if protocol.is_some() && !is_connect {
+            return Err(UserError::MalformedHeaders.into());
+        }
+
         // Build the set pseudo header set. All requests will include `method`
         // and `path`.
-        let mut pseudo = Pseudo::request(method, uri);
+        let mut pseudo = Pseudo::request(method, uri, protocol);
 
         if pseudo.scheme.is_none() {
             // If the scheme is not set, then there are a two options.
@@ -1428,6 +2689,10 @@ impl Peer {
         // Create the HEADERS frame
         let mut frame = Headers::new(id, pseudo, headers);
 
+        if let Some(dependency) = dependency {
+            frame.set_stream_dependency(dependency);
+        }
+
         if end_of_stream {
             frame.set_end_stream()
         }
@@ -19,6 +19,9 @@ pub enum SendError {
     /// Connection error prevents sending.
     Connection(Reason),
 
+    /// The peer sent a GOAWAY frame, so it won't process new streams.
+    GoAway(Reason),
+
     /// I/O error
     Io(io::Error),
 }
@@ -66,6 +69,33 @@ pub enum UserError {
 
     /// Tries to send push promise to peer who has disabled server push
     PeerDisabledServerPush,
+
+    /// Sends an informational response with a status other than 1xx, or 101.
+    InvalidInformationalStatusCode,
+
+    /// Upgrades a request without a valid `HTTP2-Settings` header, or with
+    /// an invalid `Host` header.
+    InvalidUpgradeRequest,
+
+    /// Tries to send an extended CONNECT request to a peer who has not
+    /// enabled the extended CONNECT protocol
+    PeerDisabledConnectProtocol,
+
+    /// Tries to make a stream depend on itself
+    InvalidStreamDependency,
+
+    /// Sends a request after starting a graceful shutdown of the connection.
+    GoingAway,
+
+    /// Uses keep-alive or a shutdown deadline without setting a timer.
+    MissingTimer,
+
+    /// Opens a tunnel without a CONNECT request, or accepts one with a
+    /// response other than 2xx.
+    InvalidTunnel,
+
+    /// Sends a custom setting with the identifier of an implemented one.
+    ImplementedSetting,
 }
 
 // ===== impl RecvError =====
@@ -101,6 +131,7 @@ impl fmt::Display for SendError {
         match *self {
             User(ref e) => e.fmt(fmt),
             Connection(ref reason) => reason.fmt(fmt),
+            GoAway(ref reason) => write!(fmt, "peer going away: {}", reason),
             Io(ref e) => e.fmt(fmt),
         }
     }
@@ -140,6 +171,18 @@ impl fmt::Display for UserError {
             SendPingWhilePending => "send_ping before received previous pong",
             SendSettingsWhilePending => "sending SETTINGS before received previous ACK",
             PeerDisabledServerPush => "sending PUSH_PROMISE to peer who disabled server push",
+            InvalidInformationalStatusCode => "informational status must be 1xx other than 101",
+            InvalidUpgradeRequest => {
+                "upgrade request must have one valid HTTP2-Settings header and a valid Host"
+            }
+            PeerDisabledConnectProtocol => {
+                "sending extended CONNECT to peer who has not enabled it"
+            }
+            InvalidStreamDependency => "stream cannot depend on itself",
+            GoingAway => "connection going away",
+            MissingTimer => "keep-alive and shutdown deadlines require a timer",
+            InvalidTunnel => "tunnel requires a CONNECT request, accepted with a 2xx response",
+            ImplementedSetting => "custom setting is implemented by the connection",
         })
     }
 }
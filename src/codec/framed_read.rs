@@ -5,12 +5,15 @@ use crate::frame::{
 };
 
 use crate::hpack;
+use crate::observer::{FrameInfo, ObserverHandle};
+use crate::stats::SharedStats;
 
 use futures_core::Stream;
 
 use bytes::BytesMut;
 
 use std::io;
+use std::sync::Arc;
 
 use crate::tokio_codec::FramedRead as InnerFramedRead;
 use crate::tokio_codec::{LengthDelimitedCodec, LengthDelimitedCodecError};
@@ -31,6 +34,13 @@ pub struct FramedRead<T> {
     max_header_list_size: usize,
 
     partial: Option<Partial>,
+
+    stats: Arc<SharedStats>,
+
+    observer: Option<ObserverHandle>,
+
+    /// Frame types to decode as extension frames instead of ignoring them.
+    extension_types: Vec<u8>,
 }
 
 /// Partially loaded headers frame
@@ -41,6 +51,12 @@ struct Partial {
 
     /// Partial header payload
     buf: BytesMut,
+
+    /// Payload length of the frames loaded so far
+    payload_len: usize,
+
+    /// Flags of the first frame of the header block
+    flags: u8,
 }
 
 #[derive(Debug)]
@@ -50,12 +66,18 @@ enum Continuable {
 }
 
 impl<T> FramedRead<T> {
-    pub fn new(inner: InnerFramedRead<T, LengthDelimitedCodec>) -> FramedRead<T> {
+    pub fn new(
+        inner: InnerFramedRead<T, LengthDelimitedCodec>,
+        stats: Arc<SharedStats>,
+    ) -> FramedRead<T> {
         FramedRead {
             inner,
             hpack: hpack::Decoder::new(DEFAULT_SETTINGS_HEADER_TABLE_SIZE),
             max_header_list_size: DEFAULT_SETTINGS_MAX_HEADER_LIST_SIZE,
             partial: None,
+            stats,
+            observer: None,
+            extension_types: Vec::new(),
         }
     }
 
@@ -76,6 +98,17 @@ impl<T> FramedRead<T> {
 
         log::trace!("    -> kind={:?}", kind);
 
+        self.stats.received.inc_bytes(bytes.len());
+
+        // Frames of an unknown type are counted once they are known to be
+        // extension frames or not
+        if kind != Kind::Unknown {
+            self.stats.received.inc_frames(kind);
+        }
+
+        let mut payload_len = bytes.len() - frame::HEADER_LEN;
+        let mut flags = head.flag();
+
         macro_rules! header_block {
             ($frame:ident, $head:ident, $bytes:ident) => ({
                 // Drop the frame header
@@ -129,6 +162,8 @@ impl<T> FramedRead<T> {
                     self.partial = Some(Partial {
                         frame: Continuable::$frame(frame),
                         buf: payload,
+                        payload_len,
+                        flags: $head.flag(),
                     });
 
                     return Ok(None);
@@ -186,11 +221,12 @@ impl<T> FramedRead<T> {
             }
             Kind::GoAway => {
                 let res = frame::GoAway::load(&bytes[frame::HEADER_LEN..]);
-                res.map_err(|e| {
+                let frame = res.map_err(|e| {
                     proto_err!(conn: "failed to load GO_AWAY frame; err={:?}", e);
                     Connection(Reason::PROTOCOL_ERROR)
-                })?
-                .into()
+                })?;
+                self.stats.received.set_go_away(frame.reason());
+                frame.into()
             }
             Kind::PushPromise => header_block!(PushPromise, head, bytes),
             Kind::Priority => {
@@ -219,6 +255,41 @@ impl<T> FramedRead<T> {
                     }
                 }
             }
+            Kind::PriorityUpdate => {
+                let res = frame::PriorityUpdate::load(head, &bytes[frame::HEADER_LEN..]);
+
+                res.map_err(|e| {
+                    proto_err!(conn: "failed to load PRIORITY_UPDATE frame; err={:?}", e);
+
+                    match e {
+                        frame::Error::BadFrameSize => Connection(Reason::FRAME_SIZE_ERROR),
+                        _ => Connection(Reason::PROTOCOL_ERROR),
+                    }
+                })?
+                .into()
+            }
+            Kind::AltSvc => {
+                match frame::AltSvc::load(head, &bytes[frame::HEADER_LEN..]) {
+                    Ok(frame) => frame.into(),
+                    Err(e) => {
+                        // Like ORIGIN, ALTSVC frames that can't be used are
+                        // ignored.
+                        log::debug!("ignoring ALTSVC frame; err={:?}", e);
+                        return Ok(None);
+                    }
+                }
+            }
+            Kind::Origin => {
+                match frame::Origin::load(head, &bytes[frame::HEADER_LEN..]) {
+                    Ok(frame) => frame.into(),
+                    Err(e) => {
+                        // ORIGIN is a non-critical extension, frames that
+                        // can't be used are ignored.
+                        log::debug!("ignoring ORIGIN frame; err={:?}", e);
+                        return Ok(None);
+                    }
+                }
+            }
             Kind::Continuation => {
                 let is_end_headers = (head.flag() & 0x4) == 0x4;
 
@@ -236,6 +307,9 @@ impl<T> FramedRead<T> {
                     return Err(Connection(Reason::PROTOCOL_ERROR));
                 }
 
+                payload_len += partial.payload_len;
+                flags = partial.flags;
+
                 // Extend the buf
                 if partial.buf.is_empty() {
                     partial.buf = bytes.split_off(frame::HEADER_LEN);
@@ -287,16 +361,39 @@ impl<T> FramedRead<T> {
                 if is_end_headers {
                     partial.frame.into()
                 } else {
+                    partial.payload_len = payload_len;
                     self.partial = Some(partial);
                     return Ok(None);
                 }
             }
             Kind::Unknown => {
-                // Unknown frames are ignored
-                return Ok(None);
+                let frame_type = bytes[3];
+
+                if !self.extension_types.contains(&frame_type) {
+                    self.stats.received.inc_frames(kind);
+
+                    // Unknown frames are ignored, once the observer has been
+                    // told about them
+                    if let Some(ref observer) = self.observer {
+                        observer.frame_received(&FrameInfo::unknown(head, frame_type, payload_len));
+                    }
+
+                    return Ok(None);
+                }
+
+                self.stats.received.inc_extension_frames();
+
+                let _ = bytes.split_to(frame::HEADER_LEN);
+                frame::Extension::load(head, frame_type, bytes.freeze()).into()
             }
         };
 
+        if let Some(ref observer) = self.observer {
+            let mut info = FrameInfo::new(&frame, payload_len);
+            info.set_flags(flags);
+            observer.frame_received(&info);
+        }
+
         Ok(Some(frame))
     }
 
@@ -308,6 +405,18 @@ impl<T> FramedRead<T> {
         self.inner.get_mut()
     }
 
+    pub fn stats(&self) -> &Arc<SharedStats> {
+        &self.stats
+    }
+
+    pub fn set_observer(&mut self, observer: ObserverHandle) {
+        self.observer = Some(observer);
+    }
+
+    pub fn set_extension_types(&mut self, types: Vec<u8>) {
+        self.extension_types = types;
+    }
+
     /// Returns the current max frame size setting
     #[cfg(feature = "unstable")]
     #[inline]
@@ -329,6 +438,12 @@ impl<T> FramedRead<T> {
     pub fn set_max_header_list_size(&mut self, val: usize) {
         self.max_header_list_size = val;
     }
+
+    /// Update the header table size setting.
+    #[inline]
+    pub fn set_header_table_size(&mut self, val: usize) {
+        self.hpack.queue_size_update(val);
+    }
 }
 
 impl<T> Stream for FramedRead<T>
@@ -349,6 +464,12 @@ where
             log::trace!("poll; bytes={}B", bytes.len());
             if let Some(frame) = self.decode_frame(bytes)? {
                 log::debug!("received; frame={:?}", frame);
+
+                if let Frame::Headers(_) | Frame::PushPromise(_) = frame {
+                    self.stats
+                        .set_hpack_decoder_table_size(self.hpack.table_size());
+                }
+
                 return Poll::Ready(Some(Ok(frame)));
             }
         }
@@ -2,6 +2,8 @@ use crate::codec::UserError;
 use crate::codec::UserError::*;
 use crate::frame::{self, Frame, FrameSize};
 use crate::hpack;
+use crate::observer::{FrameInfo, ObserverHandle};
+use crate::stats::SharedStats;
 
 use bytes::{
     buf::{BufExt, BufMutExt},
@@ -9,6 +11,7 @@ use bytes::{
 };
 use futures_io::{AsyncRead, AsyncWrite};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use std::io::{self, Cursor};
@@ -42,6 +45,18 @@ pub struct FramedWrite<T, B> {
 
     /// Max frame size, this is specified by the peer
     max_frame_size: FrameSize,
+
+    stats: Arc<SharedStats>,
+
+    observer: Option<ObserverHandle>,
+
+    /// The header block frame being encoded, waiting for its CONTINUATION
+    /// frames before being passed to the observer.
+    continued: Option<FrameInfo>,
+
+    /// Number of PING and SETTINGS acknowledgements buffered since the buffer
+    /// was last written out entirely.
+    pending_replies: usize,
 }
 
 #[derive(Debug)]
@@ -70,7 +85,7 @@ where
     T: AsyncWrite + Unpin,
     B: Buf,
 {
-    pub fn new(inner: T) -> FramedWrite<T, B> {
+    pub fn new(inner: T, stats: Arc<SharedStats>) -> FramedWrite<T, B> {
         FramedWrite {
             inner,
             hpack: hpack::Encoder::default(),
@@ -78,6 +93,10 @@ where
             next: None,
             last_data_frame: None,
             max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
+            stats,
+            observer: None,
+            continued: None,
+            pending_replies: 0,
         }
     }
 
@@ -107,6 +126,23 @@ where
         assert!(self.has_capacity());
         log::debug!("send; frame={:?}", item);
 
+        // Extension frames are counted apart from the unknown ones
+        let is_extension = matches!(item, Frame::Extension(_));
+        let kind = item.kind();
+
+        let is_reply = match item {
+            Frame::Ping(ref v) => v.is_ack(),
+            Frame::Settings(ref v) => v.is_ack(),
+            _ => false,
+        };
+
+        if is_reply {
+            self.pending_replies += 1;
+        }
+
+        let info = self.observer.as_ref().map(|_| FrameInfo::new(&item, 0));
+        let start = self.buf.get_ref().len();
+
         match item {
             Frame::Data(mut v) => {
                 // Ensure that the payload is not greater than the max frame.
@@ -140,18 +176,23 @@ where
                 if let Some(continuation) = v.encode(&mut self.hpack, &mut buf) {
                     self.next = Some(Next::Continuation(continuation));
                 }
+                self.stats
+                    .set_hpack_encoder_table_size(self.hpack.table_size());
             }
             Frame::PushPromise(v) => {
                 let mut buf = limited_write_buf!(self);
                 if let Some(continuation) = v.encode(&mut self.hpack, &mut buf) {
                     self.next = Some(Next::Continuation(continuation));
                 }
+                self.stats
+                    .set_hpack_encoder_table_size(self.hpack.table_size());
             }
             Frame::Settings(v) => {
                 v.encode(self.buf.get_mut());
                 log::trace!("encoded settings; rem={:?}", self.buf.remaining());
             }
             Frame::GoAway(v) => {
+                self.stats.sent.set_go_away(v.reason());
                 v.encode(self.buf.get_mut());
                 log::trace!("encoded go_away; rem={:?}", self.buf.remaining());
             }
@@ -164,17 +205,67 @@ where
                 log::trace!("encoded window_update; rem={:?}", self.buf.remaining());
             }
 
-            Frame::Priority(_) => {
-                /*
+            Frame::Priority(v) => {
                 v.encode(self.buf.get_mut());
                 log::trace!("encoded priority; rem={:?}", self.buf.remaining());
-                */
-                unimplemented!();
+            }
+            Frame::PriorityUpdate(v) => {
+                v.encode(self.buf.get_mut());
+                log::trace!("encoded priority_update; rem={:?}", self.buf.remaining());
             }
             Frame::Reset(v) => {
                 v.encode(self.buf.get_mut());
                 log::trace!("encoded reset; rem={:?}", self.buf.remaining());
             }
+            Frame::AltSvc(v) => {
+                if v.payload_len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                log::trace!("encoded alt_svc; rem={:?}", self.buf.remaining());
+            }
+            Frame::Origin(v) => {
+                if v.payload_len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                log::trace!("encoded origin; rem={:?}", self.buf.remaining());
+            }
+            Frame::Extension(v) => {
+                if v.payload().len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                log::trace!("encoded extension; rem={:?}", self.buf.remaining());
+            }
+        }
+
+        // Only count the frames that were accepted
+        if is_extension {
+            self.stats.sent.inc_extension_frames();
+        } else {
+            self.stats.sent.inc_frames(kind);
+        }
+
+        if let Some(mut info) = info {
+            info.set_flags(self.buf.get_ref()[start + 4]);
+            info.add_payload_len(self.buf.get_ref().len() - start - frame::HEADER_LEN);
+
+            match self.next {
+                Some(Next::Data(ref frame)) => {
+                    info.add_payload_len(frame.payload().remaining());
+                }
+                Some(Next::Continuation(_)) => {
+                    self.continued = Some(info);
+                    return Ok(());
+                }
+                None => {}
+            }
+
+            self.frame_sent(&info);
         }
 
         Ok(())
@@ -192,11 +283,13 @@ where
                         let mut buf = (&mut self.buf).chain(frame.payload_mut());
                         let n = ready!(Pin::new(&mut self.inner).poll_write(cx, buf.bytes()))?;
                         buf.advance(n);
+                        self.stats.sent.inc_bytes(n);
                     }
                     _ => {
                         log::trace!("  -> not a queued data frame");
                         let n = ready!(Pin::new(&mut self.inner).poll_write(cx, self.buf.bytes()))?;
                         self.buf.advance(n);
+                        self.stats.sent.inc_bytes(n);
                     }
                 }
             }
@@ -204,6 +297,7 @@ where
             // Clear internal buffer
             self.buf.set_position(0);
             self.buf.get_mut().clear();
+            self.pending_replies = 0;
 
             // The data frame has been written, so unset it
             match self.next.take() {
@@ -214,6 +308,8 @@ where
                 }
                 Some(Next::Continuation(frame)) => {
                     // Buffer the continuation frame, then try to write again
+                    self.stats.sent.inc_frames(frame::Kind::Continuation);
+
                     let mut buf = limited_write_buf!(self);
                     if let Some(continuation) = frame.encode(&mut self.hpack, &mut buf) {
                         // We previously had a CONTINUATION, and after encoding
@@ -228,6 +324,16 @@ where
 
                         self.next = Some(Next::Continuation(continuation));
                     }
+
+                    if let Some(mut info) = self.continued.take() {
+                        info.add_payload_len(self.buf.get_ref().len() - frame::HEADER_LEN);
+
+                        if self.next.is_some() {
+                            self.continued = Some(info);
+                        } else {
+                            self.frame_sent(&info);
+                        }
+                    }
                 }
                 None => {
                     break;
@@ -248,6 +354,12 @@ where
         Pin::new(&mut self.inner).poll_close(cx)
     }
 
+    fn frame_sent(&self, info: &FrameInfo) {
+        if let Some(ref observer) = self.observer {
+            observer.frame_sent(info);
+        }
+    }
+
     fn has_capacity(&self) -> bool {
         self.next.is_none() && self.buf.get_ref().remaining_mut() >= MIN_BUFFER_CAPACITY
     }
@@ -277,6 +389,15 @@ impl<T, B> FramedWrite<T, B> {
         self.hpack.update_max_size(val);
     }
 
+    /// Returns the number of acknowledgements not yet written to the socket.
+    pub fn pending_replies(&self) -> usize {
+        self.pending_replies
+    }
+
+    pub fn set_observer(&mut self, observer: ObserverHandle) {
+        self.observer = Some(observer);
+    }
+
     /// Retrieve the last data frame that has been sent
     pub fn take_last_data_frame(&mut self) -> Option<frame::Data<B>> {
         self.last_data_frame.take()
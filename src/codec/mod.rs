@@ -8,6 +8,8 @@ use self::framed_read::FramedRead;
 use self::framed_write::FramedWrite;
 
 use crate::frame::{self, Data, Frame};
+use crate::observer::ObserverHandle;
+use crate::stats::SharedStats;
 
 use bytes::Buf;
 use futures_core::Stream;
@@ -18,6 +20,7 @@ use futures_io::{AsyncRead, AsyncWrite};
 use crate::tokio_codec::length_delimited;
 
 use std::io;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Codec<T, B> {
@@ -37,8 +40,10 @@ where
 
     /// Returns a new `Codec` with the given maximum frame size
     pub fn with_max_recv_frame_size(io: T, max_frame_size: usize) -> Self {
+        let stats = Arc::new(SharedStats::default());
+
         // Wrap with writer
-        let framed_write = FramedWrite::new(io);
+        let framed_write = FramedWrite::new(io, stats.clone());
 
         // Delimit the frames
         let delimited = length_delimited::Builder::new()
@@ -48,7 +53,7 @@ where
             .num_skip(0) // Don't skip the header
             .new_read(framed_write);
 
-        let mut inner = FramedRead::new(delimited);
+        let mut inner = FramedRead::new(delimited, stats);
 
         // Use FramedRead's method since it checks the value is within range.
         inner.set_max_frame_size(max_frame_size);
@@ -94,6 +99,12 @@ impl<T, B> Codec<T, B> {
         self.framed_write().set_header_table_size(val)
     }
 
+    /// Set the header table size that can be used by the peer to encode
+    /// headers.
+    pub fn set_recv_header_table_size(&mut self, val: usize) {
+        self.inner.set_header_table_size(val);
+    }
+
     /// Set the max header list size that can be received.
     pub fn set_max_recv_header_list_size(&mut self, val: usize) {
         self.inner.set_max_header_list_size(val);
@@ -110,6 +121,29 @@ impl<T, B> Codec<T, B> {
         self.inner.get_mut().get_mut()
     }
 
+    /// Sets the observer to tell about every frame read and written.
+    pub(crate) fn set_observer(&mut self, observer: ObserverHandle) {
+        self.framed_write().set_observer(observer.clone());
+        self.inner.set_observer(observer);
+    }
+
+    /// Sets the frame types to decode as extension frames instead of ignoring
+    /// them.
+    pub fn set_extension_types(&mut self, types: Vec<u8>) {
+        self.inner.set_extension_types(types);
+    }
+
+    /// Returns the number of PING and SETTINGS acknowledgements buffered but
+    /// not yet written to the socket.
+    pub(crate) fn pending_replies(&self) -> usize {
+        self.inner.get_ref().pending_replies()
+    }
+
+    /// Returns the counters updated by this codec.
+    pub(crate) fn stats(&self) -> &Arc<SharedStats> {
+        self.inner.stats()
+    }
+
     /// Takes the data payload value that was fully written to the socket
     pub(crate) fn take_last_data_frame(&mut self) -> Option<Data<B>> {
         self.framed_write().take_last_data_frame()
@@ -28,6 +28,14 @@ enum Kind {
     /// action taken by the peer (i.e. a protocol error).
     Proto(Reason),
 
+    /// The stream was not processed by the remote peer, which sent a GOAWAY
+    /// frame with a lower last stream identifier.
+    GoAway(Reason),
+
+    /// The stream was not processed by the remote peer, which reset it with
+    /// `REFUSED_STREAM`.
+    Refused,
+
     /// An error resulting from an invalid action taken by the user of this
     /// library.
     User(UserError),
@@ -45,17 +53,31 @@ impl Error {
     /// action taken by the peer (i.e. a protocol error).
     pub fn reason(&self) -> Option<Reason> {
         match self.kind {
-            Kind::Proto(reason) => Some(reason),
+            Kind::Proto(reason) | Kind::GoAway(reason) => Some(reason),
+            Kind::Refused => Some(Reason::REFUSED_STREAM),
             _ => None,
         }
     }
 
+    /// Returns true if the stream was never processed by the remote peer, so
+    /// that the request is safe to retry on another connection, even if it
+    /// isn't idempotent.
+    ///
+    /// This is the case of the streams above the last stream identifier of a
+    /// GOAWAY frame received, of the requests sent after it or after a local
+    /// graceful shutdown started, and of the streams reset by the peer with
+    /// `REFUSED_STREAM`. Streams refused locally, e.g. because the response
+    /// headers were too large, may well have been processed.
+    pub fn is_unprocessed(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::GoAway(_) | Kind::Refused | Kind::User(UserError::GoingAway)
+        )
+    }
+
     /// Returns the true if the error is an io::Error
     pub fn is_io(&self) -> bool {
-        match self.kind {
-            Kind::Io(_) => true,
-            _ => false,
-        }
+        matches!(self.kind, Kind::Io(_))
     }
 
     /// Returns the error if the error is an io::Error
@@ -88,6 +110,8 @@ impl From<proto::Error> for Error {
         Error {
             kind: match src {
                 Proto(reason) => Kind::Proto(reason),
+                GoAway(reason) => Kind::GoAway(reason),
+                Refused => Kind::Refused,
                 Io(e) => Kind::Io(e),
             },
         }
@@ -107,6 +131,9 @@ impl From<SendError> for Error {
         match src {
             SendError::User(e) => e.into(),
             SendError::Connection(reason) => reason.into(),
+            SendError::GoAway(reason) => Error {
+                kind: Kind::GoAway(reason),
+            },
             SendError::Io(e) => Error::from_io(e),
         }
     }
@@ -126,6 +153,12 @@ impl fmt::Display for Error {
 
         match self.kind {
             Proto(ref reason) => write!(fmt, "protocol error: {}", reason),
+            GoAway(ref reason) => write!(fmt, "stream not processed, peer going away: {}", reason),
+            Refused => write!(
+                fmt,
+                "stream not processed, refused by peer: {}",
+                Reason::REFUSED_STREAM
+            ),
             User(ref e) => write!(fmt, "user error: {}", e),
             Io(ref e) => fmt::Display::fmt(e, fmt),
         }
@@ -0,0 +1,391 @@
+//! Extensions specific to the HTTP/2.0 protocol.
+
+use crate::frame;
+use crate::hpack::BytesStr;
+use crate::share::StreamId;
+
+use bytes::Bytes;
+use std::fmt;
+
+/// A frame of a type registered as an extension.
+///
+/// Frame types are registered with the `extension_frame_type` function of
+/// [`client::Builder`] or [`server::Builder`], and the frames received are
+/// taken with the `poll_extension_frame` function of the connection. Their
+/// flags and payload are left to the extension to interpret.
+///
+/// [`client::Builder`]: ../client/struct.Builder.html
+/// [`server::Builder`]: ../server/struct.Builder.html
+#[derive(Debug, Clone)]
+pub struct ExtensionFrame {
+    frame_type: u8,
+    flags: u8,
+    stream_id: StreamId,
+    payload: Bytes,
+}
+
+/// Alternative services advertised by the server in an ALTSVC frame
+/// (RFC 7838 §4).
+///
+/// Taken with the `poll_alt_svc` function of [`client::Connection`]. A frame
+/// sent on stream 0 applies to the origin it carries, one sent on a request
+/// stream applies to the origin of that request.
+///
+/// [`client::Connection`]: ../client/struct.Connection.html
+#[derive(Debug, Clone)]
+pub struct AltSvc {
+    stream_id: StreamId,
+    origin: Option<String>,
+    value: Bytes,
+    entries: Vec<AltSvcEntry>,
+}
+
+/// An alternative service, as parsed from the value of an ALTSVC frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AltSvcEntry {
+    protocol_id: String,
+    host: String,
+    port: u16,
+    max_age: Option<u64>,
+    persist: bool,
+}
+
+/// Represents the `:protocol` pseudo-header used by
+/// the [Extended CONNECT Protocol].
+///
+/// When sending a request, set it as an extension on the `http::Request`.
+/// Received requests carrying a `:protocol` will have it available through
+/// `Request::extensions`.
+///
+/// [Extended CONNECT Protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+#[derive(Clone, Eq, PartialEq)]
+pub struct Protocol {
+    value: BytesStr,
+}
+
+impl Protocol {
+    /// Converts a static string to a protocol name.
+    pub fn from_static(value: &'static str) -> Self {
+        Self {
+            value: unsafe { BytesStr::from_utf8_unchecked(Bytes::from_static(value.as_bytes())) },
+        }
+    }
+
+    /// Returns a str representation of the header.
+    pub fn as_str(&self) -> &str {
+        self.value.as_str()
+    }
+
+    pub(crate) fn try_from(bytes: Bytes) -> Result<Self, std::str::Utf8Error> {
+        Ok(Self {
+            value: BytesStr::try_from(bytes)?,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.value.len()
+    }
+}
+
+impl<'a> From<&'a str> for Protocol {
+    fn from(value: &'a str) -> Self {
+        Self {
+            value: unsafe {
+                BytesStr::from_utf8_unchecked(Bytes::copy_from_slice(value.as_bytes()))
+            },
+        }
+    }
+}
+
+impl AsRef<[u8]> for Protocol {
+    fn as_ref(&self) -> &[u8] {
+        self.value.as_ref()
+    }
+}
+
+impl fmt::Debug for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+// ===== impl ExtensionFrame =====
+
+impl ExtensionFrame {
+    pub(crate) fn from_internal(frame: frame::Extension) -> Self {
+        ExtensionFrame {
+            frame_type: frame.kind(),
+            flags: frame.flags(),
+            stream_id: StreamId::from_internal(frame.stream_id()),
+            payload: frame.into_payload(),
+        }
+    }
+
+    /// Returns the frame type code.
+    pub fn frame_type(&self) -> u8 {
+        self.frame_type
+    }
+
+    /// Returns the flags of the frame.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the stream the frame was sent on, which is stream 0 for
+    /// connection level frames.
+    pub fn stream_id(&self) -> &StreamId {
+        &self.stream_id
+    }
+
+    /// Returns the payload of the frame.
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Consumes the frame, returning its payload.
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+}
+
+// ===== impl AltSvc =====
+
+impl AltSvc {
+    pub(crate) fn from_internal(frame: frame::AltSvc) -> Self {
+        let origin = if frame.origin().is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(frame.origin()).into_owned())
+        };
+
+        let entries = match std::str::from_utf8(frame.value()) {
+            Ok(value) if !is_clear(value) => split_unquoted(value, ',')
+                .into_iter()
+                .filter_map(AltSvcEntry::parse)
+                .collect(),
+            _ => vec![],
+        };
+
+        AltSvc {
+            stream_id: StreamId::from_internal(frame.stream_id()),
+            origin,
+            value: frame.value().clone(),
+            entries,
+        }
+    }
+
+    /// Returns the stream the frame was sent on, which is stream 0 when the
+    /// frame carries its origin.
+    pub fn stream_id(&self) -> &StreamId {
+        &self.stream_id
+    }
+
+    /// Returns the origin the alternative services apply to, or `None` when
+    /// the frame was sent on a request stream.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Returns true if the value is `clear`, meaning that all the
+    /// alternative services of the origin are invalidated.
+    pub fn is_clear(&self) -> bool {
+        match std::str::from_utf8(&self.value) {
+            Ok(value) => is_clear(value),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the alternative services advertised. Those that could not be
+    /// parsed are left out.
+    pub fn entries(&self) -> &[AltSvcEntry] {
+        &self.entries
+    }
+
+    /// Returns the raw value, in the syntax of the `Alt-Svc` header field.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+}
+
+// ===== impl AltSvcEntry =====
+
+impl AltSvcEntry {
+    /// Parses an `alt-value` (RFC 7838 §3), unknown parameters are ignored.
+    fn parse(alt: &str) -> Option<Self> {
+        let mut params = split_unquoted(alt, ';').into_iter();
+        let (protocol_id, authority) = parse_param(params.next()?)?;
+
+        let port_at = authority.rfind(':')?;
+        let port = authority[port_at + 1..].parse().ok()?;
+
+        let mut entry = AltSvcEntry {
+            protocol_id: percent_decode(protocol_id)?,
+            host: authority[..port_at].to_string(),
+            port,
+            max_age: None,
+            persist: false,
+        };
+
+        for (name, value) in params.filter_map(parse_param) {
+            if name.eq_ignore_ascii_case("ma") {
+                entry.max_age = value.parse().ok();
+            } else if name.eq_ignore_ascii_case("persist") {
+                entry.persist = value == "1";
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Returns the ALPN protocol identifier, such as `h2` or `h3`.
+    pub fn protocol_id(&self) -> &str {
+        &self.protocol_id
+    }
+
+    /// Returns the host of the alternative service, which is empty when it
+    /// is the host of the origin.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the port of the alternative service.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the number of seconds the alternative service may be used
+    /// for, or `None` for the default of 24 hours.
+    pub fn max_age(&self) -> Option<u64> {
+        self.max_age
+    }
+
+    /// Returns true if the alternative service should be kept when the
+    /// network configuration changes.
+    pub fn is_persist(&self) -> bool {
+        self.persist
+    }
+}
+
+fn is_clear(value: &str) -> bool {
+    value.trim() == "clear"
+}
+
+/// Splits on the separators that are not in a quoted string.
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Parses a `name=value` pair, where the value is a token or a quoted
+/// string.
+fn parse_param(param: &str) -> Option<(&str, String)> {
+    let eq = param.find('=')?;
+    let name = param[..eq].trim();
+    let value = param[eq + 1..].trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    if !value.starts_with('"') {
+        return Some((name, value.to_string()));
+    }
+
+    if value.len() < 2 || !value.ends_with('"') {
+        return None;
+    }
+
+    let mut unquoted = String::with_capacity(value.len() - 2);
+    let mut escaped = false;
+
+    for c in value[1..value.len() - 1].chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else {
+            unquoted.push(c);
+            escaped = false;
+        }
+    }
+
+    Some((name, unquoted))
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn alt_svc(value: &'static str) -> AltSvc {
+        let frame = frame::AltSvc::new(
+            0.into(),
+            Bytes::from_static(b"https://example.com"),
+            Bytes::from_static(value.as_bytes()),
+        );
+        AltSvc::from_internal(frame)
+    }
+
+    #[test]
+    fn test_alt_svc_entries() {
+        let alt_svc = alt_svc(
+            "h3=\":443\"; ma=3600, h2=\"alt.example.com:8443\"; persist=1, w%3Dx%3Ay=\"[::1]:80\", bad",
+        );
+
+        assert_eq!(alt_svc.origin(), Some("https://example.com"));
+        assert!(!alt_svc.is_clear());
+
+        let entries = alt_svc.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].protocol_id(), "h3");
+        assert_eq!(entries[0].host(), "");
+        assert_eq!(entries[0].port(), 443);
+        assert_eq!(entries[0].max_age(), Some(3600));
+        assert_eq!(entries[1].host(), "alt.example.com");
+        assert!(entries[1].is_persist());
+        assert_eq!(entries[2].protocol_id(), "w=x:y");
+        assert_eq!(entries[2].host(), "[::1]");
+    }
+
+    #[test]
+    fn test_alt_svc_clear() {
+        let alt_svc = alt_svc(" clear ");
+
+        assert!(alt_svc.is_clear());
+        assert!(alt_svc.entries().is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::frame::{Error, Frame, Head, Kind, StreamId};
+
+use bytes::{BufMut, Bytes};
+
+/// The ALTSVC frame, defined in RFC 7838 §4.
+///
+/// Sent by servers to advertise alternative services. On stream 0 it carries
+/// the origin the services apply to, on a request stream the origin is the
+/// one of the request and must be left empty.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AltSvc {
+    stream_id: StreamId,
+    origin: Bytes,
+    /// The value, in the syntax of the `Alt-Svc` header field.
+    value: Bytes,
+}
+
+impl AltSvc {
+    pub fn new(stream_id: StreamId, origin: Bytes, value: Bytes) -> Self {
+        AltSvc {
+            stream_id,
+            origin,
+            value,
+        }
+    }
+
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
+    pub fn origin(&self) -> &Bytes {
+        &self.origin
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    pub fn payload_len(&self) -> usize {
+        2 + self.origin.len() + self.value.len()
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() < 2 {
+            return Err(Error::BadFrameSize);
+        }
+
+        let origin_len = ((payload[0] as usize) << 8) | payload[1] as usize;
+        let rest = &payload[2..];
+
+        if rest.len() < origin_len {
+            return Err(Error::BadFrameSize);
+        }
+
+        let (origin, value) = rest.split_at(origin_len);
+
+        Ok(AltSvc {
+            stream_id: head.stream_id(),
+            origin: Bytes::copy_from_slice(origin),
+            value: Bytes::copy_from_slice(value),
+        })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        log::trace!(
+            "encoding ALTSVC; stream_id={:?}; origin={:?}; value={:?}",
+            self.stream_id,
+            self.origin,
+            self.value
+        );
+        let head = Head::new(Kind::AltSvc, 0, self.stream_id);
+        head.encode(self.payload_len(), dst);
+
+        dst.put_u16(self.origin.len() as u16);
+        dst.put_slice(&self.origin);
+        dst.put_slice(&self.value);
+    }
+}
+
+impl<B> From<AltSvc> for Frame<B> {
+    fn from(src: AltSvc) -> Self {
+        Frame::AltSvc(src)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_load() {
+        let frame = AltSvc::new(
+            StreamId::ZERO,
+            Bytes::from_static(b"https://example.com"),
+            Bytes::from_static(b"h2=\"alt.example.com:443\""),
+        );
+
+        let mut buf = Vec::new();
+        frame.encode(&mut buf);
+        assert_eq!(buf.len(), crate::frame::HEADER_LEN + frame.payload_len());
+        assert_eq!(buf[3], 0xa);
+
+        let head = Head::parse(&buf);
+        assert_eq!(
+            AltSvc::load(head, &buf[crate::frame::HEADER_LEN..]),
+            Ok(frame)
+        );
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let head = Head::new(Kind::AltSvc, 0, 1.into());
+
+        assert_eq!(AltSvc::load(head, b"\x00"), Err(Error::BadFrameSize));
+        assert_eq!(AltSvc::load(head, b"\x00\x05abc"), Err(Error::BadFrameSize));
+        let clear = AltSvc::new(1.into(), Bytes::new(), Bytes::from_static(b"clear"));
+        assert_eq!(AltSvc::load(head, b"\x00\x00clear"), Ok(clear));
+    }
+}
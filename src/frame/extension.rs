@@ -0,0 +1,98 @@
+use crate::frame::{Frame, Head, StreamId};
+
+use bytes::{BufMut, Bytes};
+
+/// A frame of a type that was registered as an extension.
+///
+/// Its payload is carried as is, the connection only enforces the maximum
+/// frame size.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Extension {
+    kind: u8,
+    flags: u8,
+    stream_id: StreamId,
+    payload: Bytes,
+}
+
+impl Extension {
+    pub fn new(kind: u8, flags: u8, stream_id: StreamId, payload: Bytes) -> Self {
+        Extension {
+            kind,
+            flags,
+            stream_id,
+            payload,
+        }
+    }
+
+    /// Loads an extension frame. The frame type is given separately, as
+    /// `Head` knows nothing of the types it doesn't implement.
+    pub fn load(head: Head, kind: u8, payload: Bytes) -> Self {
+        Extension {
+            kind,
+            flags: head.flag(),
+            stream_id: head.stream_id(),
+            payload,
+        }
+    }
+
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        log::trace!(
+            "encoding extension frame; type={}; flags={:#x}; len={}",
+            self.kind,
+            self.flags,
+            self.payload.len()
+        );
+        dst.put_uint(self.payload.len() as u64, 3);
+        dst.put_u8(self.kind);
+        dst.put_u8(self.flags);
+        dst.put_u32(self.stream_id.into());
+        dst.put_slice(&self.payload);
+    }
+}
+
+impl<B> From<Extension> for Frame<B> {
+    fn from(src: Extension) -> Self {
+        Frame::Extension(src)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_load() {
+        let frame = Extension::new(0xb, 0x1, 3.into(), Bytes::from_static(b"hello"));
+
+        let mut buf = Vec::new();
+        frame.encode(&mut buf);
+        assert_eq!(
+            &buf[..crate::frame::HEADER_LEN],
+            &[0, 0, 5, 0xb, 0x1, 0, 0, 0, 3]
+        );
+
+        let head = Head::parse(&buf);
+        let payload = Bytes::copy_from_slice(&buf[crate::frame::HEADER_LEN..]);
+        assert_eq!(Extension::load(head, buf[3], payload), frame);
+    }
+}
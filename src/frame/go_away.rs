@@ -8,7 +8,6 @@ use crate::frame::{self, Error, Head, Kind, Reason, StreamId};
 pub struct GoAway {
     last_stream_id: StreamId,
     error_code: Reason,
-    #[allow(unused)]
     debug_data: Bytes,
 }
 
@@ -29,8 +28,7 @@ impl GoAway {
         self.error_code
     }
 
-    #[cfg(feature = "unstable")]
-    pub fn debug_data(&self) -> &[u8] {
+    pub fn debug_data(&self) -> &Bytes {
         &self.debug_data
     }
 
@@ -1,4 +1,5 @@
 use super::{util, StreamDependency, StreamId};
+use crate::ext::Protocol;
 use crate::frame::{Error, Frame, Head, Kind};
 use crate::hpack::{self, BytesStr};
 
@@ -71,6 +72,7 @@ pub struct Pseudo {
     pub scheme: Option<BytesStr>,
     pub authority: Option<BytesStr>,
     pub path: Option<BytesStr>,
+    pub protocol: Option<Protocol>,
 
     // Response
     pub status: Option<StatusCode>,
@@ -241,6 +243,23 @@ impl Headers {
         self.header_block.is_over_size
     }
 
+    /// Returns true if the frame carries an informational (1xx) response.
+    pub fn is_informational(&self) -> bool {
+        match self.header_block.pseudo.status {
+            Some(status) => status.is_informational(),
+            None => false,
+        }
+    }
+
+    pub fn stream_dependency(&self) -> Option<&StreamDependency> {
+        self.stream_dep.as_ref()
+    }
+
+    pub fn set_stream_dependency(&mut self, dependency: StreamDependency) {
+        self.stream_dep = Some(dependency);
+        self.flags.set_priority();
+    }
+
     pub(crate) fn has_too_big_field(&self) -> bool {
         self.header_block.has_too_big_field()
     }
@@ -272,10 +291,15 @@ impl Headers {
 
         // Get the HEADERS frame head
         let head = self.head();
+        let stream_dep = self.stream_dep;
 
         self.header_block
             .into_encoding()
-            .encode(&head, encoder, dst, |_| {})
+            .encode(&head, encoder, dst, |dst| {
+                if let Some(dep) = stream_dep {
+                    dep.encode(dst);
+                }
+            })
     }
 
     fn head(&self) -> Head {
@@ -539,7 +563,7 @@ impl Continuation {
 // ===== impl Pseudo =====
 
 impl Pseudo {
-    pub fn request(method: Method, uri: Uri) -> Self {
+    pub fn request(method: Method, uri: Uri, protocol: Option<Protocol>) -> Self {
         let parts = uri::Parts::from(uri);
 
         let mut path = parts
@@ -547,15 +571,26 @@ impl Pseudo {
             .map(|v| Bytes::copy_from_slice(v.as_str().as_bytes()))
             .unwrap_or_else(Bytes::new);
 
-        if path.is_empty() && method != Method::OPTIONS {
+        // A plain CONNECT has no :path, its target is the :authority. An
+        // extended CONNECT (RFC 8441) carries a :path like any other request.
+        let is_plain_connect = method == Method::CONNECT && protocol.is_none();
+
+        if path.is_empty() && method != Method::OPTIONS && !is_plain_connect {
             path = Bytes::from_static(b"/");
         }
 
+        let path = if is_plain_connect {
+            None
+        } else {
+            Some(unsafe { BytesStr::from_utf8_unchecked(path) })
+        };
+
         let mut pseudo = Pseudo {
             method: Some(method),
             scheme: None,
             authority: None,
-            path: Some(unsafe { BytesStr::from_utf8_unchecked(path) }),
+            path,
+            protocol,
             status: None,
         };
 
@@ -583,6 +618,7 @@ impl Pseudo {
             scheme: None,
             authority: None,
             path: None,
+            protocol: None,
             status: Some(status),
         }
     }
@@ -682,6 +718,10 @@ impl Iterator for Iter {
                 return Some(Path(path));
             }
 
+            if let Some(protocol) = pseudo.protocol.take() {
+                return Some(Protocol(protocol));
+            }
+
             if let Some(status) = pseudo.status.take() {
                 return Some(Status(status));
             }
@@ -726,6 +766,10 @@ impl HeadersFlag {
         self.0 & PADDED == PADDED
     }
 
+    pub fn set_priority(&mut self) {
+        self.0 |= PRIORITY
+    }
+
     pub fn is_priority(&self) -> bool {
         self.0 & PRIORITY == PRIORITY
     }
@@ -877,6 +921,7 @@ impl HeaderBlock {
                 Method(v) => set_pseudo!(method, v),
                 Scheme(v) => set_pseudo!(scheme, v),
                 Path(v) => set_pseudo!(path, v),
+                Protocol(v) => set_pseudo!(protocol, v),
                 Status(v) => set_pseudo!(status, v),
             }
         });
@@ -927,6 +972,7 @@ impl HeaderBlock {
             + pseudo_size!(status)
             + pseudo_size!(authority)
             + pseudo_size!(path)
+            + pseudo_size!(protocol)
             + self
                 .fields
                 .iter()
@@ -963,6 +1009,10 @@ impl HeaderBlock {
             return true;
         }
 
+        if pseudo_size!(protocol) > MAX_HEADER_LENGTH {
+            return true;
+        }
+
         // skip :status, its never going to be too big
 
         for (name, value) in &self.fields {
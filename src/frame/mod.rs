@@ -26,12 +26,16 @@ macro_rules! unpack_octets_4 {
     };
 }
 
+mod alt_svc;
 mod data;
+mod extension;
 mod go_away;
 mod head;
 mod headers;
+mod origin;
 mod ping;
 mod priority;
+mod priority_update;
 mod reason;
 mod reset;
 mod settings;
@@ -39,14 +43,18 @@ mod stream_id;
 mod util;
 mod window_update;
 
+pub use self::alt_svc::AltSvc;
 pub use self::data::Data;
+pub use self::extension::Extension;
 pub use self::go_away::GoAway;
 pub use self::head::{Head, Kind};
 pub use self::headers::{
     parse_u64, Continuation, Headers, Pseudo, PushPromise, PushPromiseHeaderError,
 };
+pub use self::origin::Origin;
 pub use self::ping::Ping;
 pub use self::priority::{Priority, StreamDependency};
+pub use self::priority_update::{PriorityParams, PriorityUpdate};
 pub use self::reason::Reason;
 pub use self::reset::Reset;
 pub use self::settings::Settings;
@@ -72,12 +80,16 @@ pub enum Frame<T = Bytes> {
     Data(Data<T>),
     Headers(Headers),
     Priority(Priority),
+    PriorityUpdate(PriorityUpdate),
     PushPromise(PushPromise),
     Settings(Settings),
     Ping(Ping),
     GoAway(GoAway),
     WindowUpdate(WindowUpdate),
     Reset(Reset),
+    AltSvc(AltSvc),
+    Origin(Origin),
+    Extension(Extension),
 }
 
 impl<T> Frame<T> {
@@ -91,12 +103,36 @@ impl<T> Frame<T> {
             Data(frame) => frame.map(f).into(),
             Headers(frame) => frame.into(),
             Priority(frame) => frame.into(),
+            PriorityUpdate(frame) => frame.into(),
             PushPromise(frame) => frame.into(),
             Settings(frame) => frame.into(),
             Ping(frame) => frame.into(),
             GoAway(frame) => frame.into(),
             WindowUpdate(frame) => frame.into(),
             Reset(frame) => frame.into(),
+            AltSvc(frame) => frame.into(),
+            Origin(frame) => frame.into(),
+            Extension(frame) => frame.into(),
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        use self::Frame::*;
+
+        match *self {
+            Data(..) => Kind::Data,
+            Headers(..) => Kind::Headers,
+            Priority(..) => Kind::Priority,
+            PriorityUpdate(..) => Kind::PriorityUpdate,
+            PushPromise(..) => Kind::PushPromise,
+            Settings(..) => Kind::Settings,
+            Ping(..) => Kind::Ping,
+            GoAway(..) => Kind::GoAway,
+            WindowUpdate(..) => Kind::WindowUpdate,
+            Reset(..) => Kind::Reset,
+            AltSvc(..) => Kind::AltSvc,
+            Origin(..) => Kind::Origin,
+            Extension(..) => Kind::Unknown,
         }
     }
 }
@@ -109,12 +145,16 @@ impl<T> fmt::Debug for Frame<T> {
             Data(ref frame) => fmt::Debug::fmt(frame, fmt),
             Headers(ref frame) => fmt::Debug::fmt(frame, fmt),
             Priority(ref frame) => fmt::Debug::fmt(frame, fmt),
+            PriorityUpdate(ref frame) => fmt::Debug::fmt(frame, fmt),
             PushPromise(ref frame) => fmt::Debug::fmt(frame, fmt),
             Settings(ref frame) => fmt::Debug::fmt(frame, fmt),
             Ping(ref frame) => fmt::Debug::fmt(frame, fmt),
             GoAway(ref frame) => fmt::Debug::fmt(frame, fmt),
             WindowUpdate(ref frame) => fmt::Debug::fmt(frame, fmt),
             Reset(ref frame) => fmt::Debug::fmt(frame, fmt),
+            AltSvc(ref frame) => fmt::Debug::fmt(frame, fmt),
+            Origin(ref frame) => fmt::Debug::fmt(frame, fmt),
+            Extension(ref frame) => fmt::Debug::fmt(frame, fmt),
         }
     }
 }
@@ -144,8 +184,9 @@ pub enum Error {
 
     /// An invalid stream identifier was provided.
     ///
-    /// This is returned if a SETTINGS or PING frame is received with a stream
-    /// identifier other than zero.
+    /// This is returned if a SETTINGS, PING or PRIORITY_UPDATE frame is
+    /// received with a stream identifier other than zero, or if a
+    /// PRIORITY_UPDATE frame refers to stream zero.
     InvalidStreamId,
 
     /// A request or response is malformed.
@@ -0,0 +1,119 @@
+use crate::frame::{Error, Frame, Head, Kind, StreamId};
+
+use bytes::BufMut;
+
+/// The ORIGIN frame, defined in RFC 8336 §2.
+///
+/// It is sent by servers on stream 0 to list the origins they are
+/// authoritative for, so that clients may reuse the connection for them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Origin {
+    /// The ASCII serializations of the origins.
+    origins: Vec<String>,
+}
+
+impl Origin {
+    pub fn new(origins: Vec<String>) -> Self {
+        Origin { origins }
+    }
+
+    pub fn origins(&self) -> &[String] {
+        &self.origins
+    }
+
+    pub fn into_origins(self) -> Vec<String> {
+        self.origins
+    }
+
+    pub fn payload_len(&self) -> usize {
+        self.origins.iter().map(|origin| 2 + origin.len()).sum()
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<Self, Error> {
+        if !head.stream_id().is_zero() {
+            return Err(Error::InvalidStreamId);
+        }
+
+        let mut origins = Vec::new();
+        let mut rest = payload;
+
+        while !rest.is_empty() {
+            if rest.len() < 2 {
+                return Err(Error::BadFrameSize);
+            }
+
+            let len = ((rest[0] as usize) << 8) | rest[1] as usize;
+            rest = &rest[2..];
+
+            if rest.len() < len {
+                return Err(Error::BadFrameSize);
+            }
+
+            let (origin, tail) = rest.split_at(len);
+            rest = tail;
+
+            if origin.is_ascii() {
+                origins.push(String::from_utf8(origin.to_vec()).expect("ASCII is UTF-8"));
+            } else {
+                log::debug!("ignoring non ASCII origin; len={}", len);
+            }
+        }
+
+        Ok(Origin { origins })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        log::trace!("encoding ORIGIN; origins={:?}", self.origins);
+        let head = Head::new(Kind::Origin, 0, StreamId::ZERO);
+        head.encode(self.payload_len(), dst);
+
+        for origin in &self.origins {
+            dst.put_u16(origin.len() as u16);
+            dst.put_slice(origin.as_bytes());
+        }
+    }
+}
+
+impl<B> From<Origin> for Frame<B> {
+    fn from(src: Origin) -> Self {
+        Frame::Origin(src)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn load(payload: &[u8]) -> Result<Origin, Error> {
+        Origin::load(Head::new(Kind::Origin, 0, StreamId::ZERO), payload)
+    }
+
+    #[test]
+    fn test_encode_load() {
+        let frame = Origin::new(vec![
+            "https://a.example.com".to_string(),
+            "https://b.example.com:8443".to_string(),
+        ]);
+
+        let mut buf = Vec::new();
+        frame.encode(&mut buf);
+        assert_eq!(buf.len(), crate::frame::HEADER_LEN + frame.payload_len());
+        assert_eq!(buf[3], 0xc);
+
+        assert_eq!(load(&buf[crate::frame::HEADER_LEN..]), Ok(frame));
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        assert_eq!(load(b""), Ok(Origin::new(vec![])));
+        assert_eq!(load(b"\x00"), Err(Error::BadFrameSize));
+        assert_eq!(load(b"\x00\x05abc"), Err(Error::BadFrameSize));
+        assert_eq!(
+            load(b"\x00\x02\xc3\xa9\x00\x01a"),
+            Ok(Origin::new(vec!["a".to_string()]))
+        );
+
+        let head = Head::new(Kind::Origin, 0, 1.into());
+        assert_eq!(Origin::load(head, b""), Err(Error::InvalidStreamId));
+    }
+}
@@ -15,6 +15,8 @@ pub struct Ping {
 // zeroes to distinguish this specific PING from any other.
 const SHUTDOWN_PAYLOAD: Payload = [0x0b, 0x7b, 0xa2, 0xf0, 0x8b, 0x9b, 0xfe, 0x54];
 const USER_PAYLOAD: Payload = [0x3b, 0x7c, 0xdb, 0x7a, 0x0b, 0x87, 0x16, 0xb4];
+const KEEP_ALIVE_PAYLOAD: Payload = [0x1c, 0x52, 0x9e, 0x6d, 0xa8, 0x03, 0xf5, 0x31];
+const BDP_PAYLOAD: Payload = [0x64, 0xe2, 0x0f, 0x39, 0xc7, 0x5a, 0x8d, 0x16];
 
 impl Ping {
     #[cfg(feature = "unstable")]
@@ -29,6 +31,18 @@ impl Ping {
     #[cfg(not(feature = "unstable"))]
     pub(crate) const USER: Payload = USER_PAYLOAD;
 
+    #[cfg(feature = "unstable")]
+    pub const KEEP_ALIVE: Payload = KEEP_ALIVE_PAYLOAD;
+
+    #[cfg(not(feature = "unstable"))]
+    pub(crate) const KEEP_ALIVE: Payload = KEEP_ALIVE_PAYLOAD;
+
+    #[cfg(feature = "unstable")]
+    pub const BDP: Payload = BDP_PAYLOAD;
+
+    #[cfg(not(feature = "unstable"))]
+    pub(crate) const BDP: Payload = BDP_PAYLOAD;
+
     pub fn new(payload: Payload) -> Ping {
         Ping {
             ack: false,
@@ -0,0 +1,236 @@
+use crate::frame::*;
+
+use bytes::BufMut;
+
+/// The PRIORITY_UPDATE frame, defined in RFC 9218 §7.1.
+///
+/// It is sent by clients on stream 0 to change the priority of a request.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PriorityUpdate {
+    prioritized_id: StreamId,
+    params: PriorityParams,
+}
+
+/// The priority parameters of a request, as carried by the `priority` header
+/// field or a PRIORITY_UPDATE frame (RFC 9218 §4).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PriorityParams {
+    /// The urgency, in the range [0, 7]. Lower values are more urgent.
+    urgency: u8,
+
+    /// True if the response can be processed incrementally.
+    is_incremental: bool,
+}
+
+const DEFAULT_URGENCY: u8 = 3;
+const MAX_URGENCY: u8 = 7;
+
+impl PriorityUpdate {
+    pub fn new(prioritized_id: StreamId, params: PriorityParams) -> Self {
+        PriorityUpdate {
+            prioritized_id,
+            params,
+        }
+    }
+
+    pub fn prioritized_id(&self) -> StreamId {
+        self.prioritized_id
+    }
+
+    pub fn params(&self) -> PriorityParams {
+        self.params
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<Self, Error> {
+        if !head.stream_id().is_zero() {
+            return Err(Error::InvalidStreamId);
+        }
+
+        if payload.len() < 4 {
+            return Err(Error::BadFrameSize);
+        }
+
+        let (prioritized_id, _) = StreamId::parse(&payload[..4]);
+
+        if prioritized_id.is_zero() {
+            return Err(Error::InvalidStreamId);
+        }
+
+        Ok(PriorityUpdate {
+            prioritized_id,
+            params: PriorityParams::parse(&payload[4..]),
+        })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        log::trace!(
+            "encoding PRIORITY_UPDATE; prioritized_id={:?} params={:?}",
+            self.prioritized_id,
+            self.params
+        );
+        let value = self.params.to_field_value();
+        let head = Head::new(Kind::PriorityUpdate, 0, StreamId::ZERO);
+        head.encode(4 + value.len(), dst);
+        dst.put_u32(self.prioritized_id.into());
+        dst.put_slice(value.as_bytes());
+    }
+}
+
+impl<B> From<PriorityUpdate> for Frame<B> {
+    fn from(src: PriorityUpdate) -> Self {
+        Frame::PriorityUpdate(src)
+    }
+}
+
+// ===== impl PriorityParams =====
+
+impl PriorityParams {
+    pub fn new(urgency: u8, is_incremental: bool) -> Self {
+        debug_assert!(urgency <= MAX_URGENCY);
+
+        PriorityParams {
+            urgency,
+            is_incremental,
+        }
+    }
+
+    /// Parses a `priority` field value, which is a Structured Fields
+    /// dictionary.
+    ///
+    /// Unknown members are ignored, as are urgencies out of range. If the
+    /// value cannot be parsed, the default priority is returned.
+    pub fn parse(src: &[u8]) -> Self {
+        let mut params = PriorityParams::default();
+
+        for member in src.split(|b| *b == b',') {
+            let member = trim_ows(member);
+
+            // Parameters of a member are not used by any known key.
+            let member = match member.iter().position(|b| *b == b';') {
+                Some(pos) => &member[..pos],
+                None => member,
+            };
+
+            let (key, value) = match member.iter().position(|b| *b == b'=') {
+                Some(pos) => (&member[..pos], Some(&member[pos + 1..])),
+                None => (member, None),
+            };
+
+            if !is_valid_key(key) {
+                log::trace!("PriorityParams::parse; invalid dictionary; src={:?}", src);
+                return PriorityParams::default();
+            }
+
+            match (key, value) {
+                (b"u", Some(value)) => match parse_urgency(value) {
+                    Some(urgency) => params.urgency = urgency,
+                    None => params.urgency = DEFAULT_URGENCY,
+                },
+                (b"i", None) | (b"i", Some(b"?1")) => params.is_incremental = true,
+                (b"i", Some(_)) => params.is_incremental = false,
+                _ => {}
+            }
+        }
+
+        params
+    }
+
+    pub fn urgency(&self) -> u8 {
+        self.urgency
+    }
+
+    pub fn is_incremental(&self) -> bool {
+        self.is_incremental
+    }
+
+    /// Returns the `priority` field value for these parameters, leaving out
+    /// the ones that have their default value.
+    pub fn to_field_value(self) -> String {
+        match (self.urgency, self.is_incremental) {
+            (DEFAULT_URGENCY, false) => String::new(),
+            (DEFAULT_URGENCY, true) => "i".to_string(),
+            (urgency, false) => format!("u={}", urgency),
+            (urgency, true) => format!("u={}, i", urgency),
+        }
+    }
+}
+
+impl Default for PriorityParams {
+    fn default() -> Self {
+        PriorityParams {
+            urgency: DEFAULT_URGENCY,
+            is_incremental: false,
+        }
+    }
+}
+
+fn trim_ows(src: &[u8]) -> &[u8] {
+    let is_ows = |b: &u8| *b == b' ' || *b == b'\t';
+
+    let start = src.iter().position(|b| !is_ows(b)).unwrap_or(src.len());
+    let end = src
+        .iter()
+        .rposition(|b| !is_ows(b))
+        .map_or(start, |pos| pos + 1);
+
+    &src[start..end]
+}
+
+fn is_valid_key(key: &[u8]) -> bool {
+    match key.split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_lowercase() || *first == b'*')
+                && rest
+                    .iter()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.*".contains(b))
+        }
+        None => false,
+    }
+}
+
+fn parse_urgency(value: &[u8]) -> Option<u8> {
+    match value {
+        [digit] if digit.is_ascii_digit() && *digit - b'0' <= MAX_URGENCY => Some(*digit - b'0'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_priority_params() {
+        let parse = |src: &str| {
+            let params = PriorityParams::parse(src.as_bytes());
+            (params.urgency(), params.is_incremental())
+        };
+
+        assert_eq!(parse(""), (3, false));
+        assert_eq!(parse("u=0"), (0, false));
+        assert_eq!(parse("u=5, i"), (5, true));
+        assert_eq!(parse("i=?1,u=7"), (7, true));
+        assert_eq!(parse("u=1, i=?0"), (1, false));
+        assert_eq!(parse("u=2;foo=bar, x=1"), (2, false));
+        assert_eq!(parse("u=1, u=6"), (6, false));
+
+        // Out of range urgencies are ignored
+        assert_eq!(parse("u=8, i"), (3, true));
+        assert_eq!(parse("u=-1"), (3, false));
+
+        // Invalid dictionaries are ignored entirely
+        assert_eq!(parse("U=1"), (3, false));
+        assert_eq!(parse("u=1,,i"), (3, false));
+    }
+
+    #[test]
+    fn test_field_value_roundtrip() {
+        for urgency in 0..=MAX_URGENCY {
+            for &is_incremental in &[false, true] {
+                let params = PriorityParams::new(urgency, is_incremental);
+                let value = params.to_field_value();
+                assert_eq!(PriorityParams::parse(value.as_bytes()), params);
+            }
+        }
+    }
+}
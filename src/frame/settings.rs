@@ -13,6 +13,10 @@ pub struct Settings {
     initial_window_size: Option<u32>,
     max_frame_size: Option<u32>,
     max_header_list_size: Option<u32>,
+    enable_connect_protocol: Option<u32>,
+    no_rfc7540_priorities: Option<u32>,
+    /// Settings this crate doesn't implement, sorted by identifier.
+    unknown: Vec<(u16, u32)>,
 }
 
 /// An enum that lists all valid settings that can be sent in a SETTINGS
@@ -27,6 +31,9 @@ pub enum Setting {
     InitialWindowSize(u32),
     MaxFrameSize(u32),
     MaxHeaderListSize(u32),
+    EnableConnectProtocol(u32),
+    NoRfc7540Priorities(u32),
+    Unknown(u16, u32),
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Default)]
@@ -107,15 +114,55 @@ impl Settings {
         self.enable_push = Some(enable as u32);
     }
 
+    pub fn is_extended_connect_protocol_enabled(&self) -> Option<bool> {
+        self.enable_connect_protocol.map(|val| val != 0)
+    }
+
+    pub fn set_enable_connect_protocol(&mut self, val: Option<u32>) {
+        self.enable_connect_protocol = val;
+    }
+
+    pub fn is_no_rfc7540_priorities(&self) -> Option<bool> {
+        self.no_rfc7540_priorities.map(|val| val != 0)
+    }
+
+    pub fn set_no_rfc7540_priorities(&mut self, val: Option<u32>) {
+        self.no_rfc7540_priorities = val;
+    }
+
+    /// Returns the identifier and value of the settings this crate doesn't
+    /// implement, sorted by identifier.
+    pub fn unknown_settings(&self) -> &[(u16, u32)] {
+        &self.unknown
+    }
+
+    /// Returns true if `id` is the identifier of a setting this crate
+    /// doesn't implement.
+    pub fn is_unknown_setting(id: u16) -> bool {
+        matches!(Setting::from_id(id, 0), Setting::Unknown(..))
+    }
+
+    /// Sets a setting this crate doesn't implement, replacing its previous
+    /// value if any.
+    pub fn set_unknown_setting(&mut self, id: u16, val: u32) {
+        match Setting::from_id(id, val) {
+            Setting::Unknown(..) => {}
+            setting => panic!("setting is implemented; setting={:?}", setting),
+        }
+
+        match self.unknown.binary_search_by_key(&id, |&(id, _)| id) {
+            Ok(i) => self.unknown[i].1 = val,
+            Err(i) => self.unknown.insert(i, (id, val)),
+        }
+    }
+
     pub fn header_table_size(&self) -> Option<u32> {
         self.header_table_size
     }
 
-    /*
     pub fn set_header_table_size(&mut self, size: Option<u32>) {
         self.header_table_size = size;
     }
-    */
 
     pub fn load(head: Head, payload: &[u8]) -> Result<Settings, Error> {
         use self::Setting::*;
@@ -150,10 +197,10 @@ impl Settings {
 
         for raw in payload.chunks(6) {
             match Setting::load(raw) {
-                Some(HeaderTableSize(val)) => {
+                HeaderTableSize(val) => {
                     settings.header_table_size = Some(val);
                 }
-                Some(EnablePush(val)) => match val {
+                EnablePush(val) => match val {
                     0 | 1 => {
                         settings.enable_push = Some(val);
                     }
@@ -161,30 +208,58 @@ impl Settings {
                         return Err(Error::InvalidSettingValue);
                     }
                 },
-                Some(MaxConcurrentStreams(val)) => {
+                MaxConcurrentStreams(val) => {
                     settings.max_concurrent_streams = Some(val);
                 }
-                Some(InitialWindowSize(val)) => {
+                InitialWindowSize(val) => {
                     if val as usize > MAX_INITIAL_WINDOW_SIZE {
                         return Err(Error::InvalidSettingValue);
                     } else {
                         settings.initial_window_size = Some(val);
                     }
                 }
-                Some(MaxFrameSize(val)) => {
+                MaxFrameSize(val) => {
                     if val < DEFAULT_MAX_FRAME_SIZE || val > MAX_MAX_FRAME_SIZE {
                         return Err(Error::InvalidSettingValue);
                     } else {
                         settings.max_frame_size = Some(val);
                     }
                 }
-                Some(MaxHeaderListSize(val)) => {
+                MaxHeaderListSize(val) => {
                     settings.max_header_list_size = Some(val);
                 }
-                None => {}
+                EnableConnectProtocol(val) => match val {
+                    0 | 1 => {
+                        settings.enable_connect_protocol = Some(val);
+                    }
+                    _ => {
+                        return Err(Error::InvalidSettingValue);
+                    }
+                },
+                NoRfc7540Priorities(val) => match val {
+                    0 | 1 => {
+                        settings.no_rfc7540_priorities = Some(val);
+                    }
+                    _ => {
+                        return Err(Error::InvalidSettingValue);
+                    }
+                },
+                Unknown(id, val) => {
+                    settings.unknown.push((id, val));
+                }
             }
         }
 
+        // A setting sent more than once takes its last value.
+        settings.unknown.sort_by_key(|&(id, _)| id);
+        settings.unknown.dedup_by(|later, earlier| {
+            let is_dup = later.0 == earlier.0;
+            if is_dup {
+                earlier.1 = later.1;
+            }
+            is_dup
+        });
+
         Ok(settings)
     }
 
@@ -210,6 +285,31 @@ impl Settings {
         });
     }
 
+    /// Loads the settings sent in the `HTTP2-Settings` header of an HTTP/1.1
+    /// request upgrading to HTTP/2.0, which is the payload of a SETTINGS
+    /// frame encoded in base64url.
+    pub fn load_http2_settings(value: &[u8]) -> Result<Settings, Error> {
+        let payload = base64url_decode(value).ok_or(Error::InvalidSettingValue)?;
+        let head = Head::new(Kind::Settings, 0, StreamId::zero());
+
+        Settings::load(head, &payload)
+    }
+
+    /// Encodes the settings as the value of the `HTTP2-Settings` header.
+    pub fn encode_http2_settings(&self) -> String {
+        let mut buf = BytesMut::new();
+        self.encode(&mut buf);
+
+        base64url_encode(&buf[super::HEADER_LEN..])
+    }
+
+    /// Returns the identifier and value of each setting in the frame.
+    pub fn values(&self) -> Vec<(u16, u32)> {
+        let mut values = Vec::new();
+        self.for_each(|setting| values.push(setting.id_value()));
+        values
+    }
+
     fn for_each<F: FnMut(Setting)>(&self, mut f: F) {
         use self::Setting::*;
 
@@ -236,6 +336,18 @@ impl Settings {
         if let Some(v) = self.max_header_list_size {
             f(MaxHeaderListSize(v));
         }
+
+        if let Some(v) = self.enable_connect_protocol {
+            f(EnableConnectProtocol(v));
+        }
+
+        if let Some(v) = self.no_rfc7540_priorities {
+            f(NoRfc7540Priorities(v));
+        }
+
+        for &(id, v) in &self.unknown {
+            f(Unknown(id, v));
+        }
     }
 }
 
@@ -269,6 +381,15 @@ impl fmt::Debug for Settings {
             Setting::MaxHeaderListSize(v) => {
                 builder.field("max_header_list_size", &v);
             }
+            Setting::EnableConnectProtocol(v) => {
+                builder.field("enable_connect_protocol", &v);
+            }
+            Setting::NoRfc7540Priorities(v) => {
+                builder.field("no_rfc7540_priorities", &v);
+            }
+            Setting::Unknown(id, v) => {
+                builder.field("unknown", &(id, v));
+            }
         });
 
         builder.finish()
@@ -280,18 +401,21 @@ impl fmt::Debug for Settings {
 impl Setting {
     /// Creates a new `Setting` with the correct variant corresponding to the
     /// given setting id, based on the settings IDs defined in section
-    /// 6.5.2.
-    pub fn from_id(id: u16, val: u32) -> Option<Setting> {
+    /// 6.5.2. Identifiers this crate doesn't implement are kept as
+    /// `Unknown`.
+    pub fn from_id(id: u16, val: u32) -> Setting {
         use self::Setting::*;
 
         match id {
-            1 => Some(HeaderTableSize(val)),
-            2 => Some(EnablePush(val)),
-            3 => Some(MaxConcurrentStreams(val)),
-            4 => Some(InitialWindowSize(val)),
-            5 => Some(MaxFrameSize(val)),
-            6 => Some(MaxHeaderListSize(val)),
-            _ => None,
+            1 => HeaderTableSize(val),
+            2 => EnablePush(val),
+            3 => MaxConcurrentStreams(val),
+            4 => InitialWindowSize(val),
+            5 => MaxFrameSize(val),
+            6 => MaxHeaderListSize(val),
+            8 => EnableConnectProtocol(val),
+            9 => NoRfc7540Priorities(val),
+            _ => Unknown(id, val),
         }
     }
 
@@ -305,24 +429,32 @@ impl Setting {
     /// # Panics
     ///
     /// If given a buffer shorter than 6 bytes, the function will panic.
-    fn load(raw: &[u8]) -> Option<Setting> {
+    fn load(raw: &[u8]) -> Setting {
         let id: u16 = (u16::from(raw[0]) << 8) | u16::from(raw[1]);
         let val: u32 = unpack_octets_4!(raw, 2, u32);
 
         Setting::from_id(id, val)
     }
 
-    fn encode(&self, dst: &mut BytesMut) {
+    /// Returns the setting identifier and value.
+    fn id_value(&self) -> (u16, u32) {
         use self::Setting::*;
 
-        let (kind, val) = match *self {
+        match *self {
             HeaderTableSize(v) => (1, v),
             EnablePush(v) => (2, v),
             MaxConcurrentStreams(v) => (3, v),
             InitialWindowSize(v) => (4, v),
             MaxFrameSize(v) => (5, v),
             MaxHeaderListSize(v) => (6, v),
-        };
+            EnableConnectProtocol(v) => (8, v),
+            NoRfc7540Priorities(v) => (9, v),
+            Unknown(id, v) => (id, v),
+        }
+    }
+
+    fn encode(&self, dst: &mut BytesMut) {
+        let (kind, val) = self.id_value();
 
         dst.put_u16(kind);
         dst.put_u32(val);
@@ -362,3 +494,145 @@ impl fmt::Debug for SettingsFlags {
             .finish()
     }
 }
+
+// ===== base64url =====
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes without padding, as `HTTP2-Settings` is a token68.
+fn base64url_encode(src: &[u8]) -> String {
+    let mut dst = String::new();
+
+    for chunk in src.chunks(3) {
+        let mut n = 0u32;
+
+        for (i, &byte) in chunk.iter().enumerate() {
+            n |= u32::from(byte) << (16 - 8 * i);
+        }
+
+        for i in 0..=chunk.len() {
+            dst.push(BASE64URL[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+        }
+    }
+
+    dst
+}
+
+/// Decodes, tolerating padding.
+fn base64url_decode(src: &[u8]) -> Option<Vec<u8>> {
+    let mut len = src.len();
+
+    while len > 0 && src[len - 1] == b'=' {
+        len -= 1;
+    }
+
+    let src = &src[..len];
+
+    if src.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut dst = Vec::with_capacity(src.len() * 3 / 4);
+
+    for chunk in src.chunks(4) {
+        let mut n = 0u32;
+
+        for (i, &c) in chunk.iter().enumerate() {
+            let val = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'-' => 62,
+                b'_' => 63,
+                _ => return None,
+            };
+
+            n |= u32::from(val) << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            dst.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(dst)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64url() {
+        assert_eq!(base64url_encode(b""), "");
+        assert_eq!(base64url_encode(b"f"), "Zg");
+        assert_eq!(base64url_encode(b"fo"), "Zm8");
+        assert_eq!(base64url_encode(b"foo"), "Zm9v");
+        assert_eq!(base64url_encode(&[0xfb, 0xff]), "-_8");
+
+        assert_eq!(base64url_decode(b"Zg").unwrap(), b"f");
+        assert_eq!(base64url_decode(b"Zm8=").unwrap(), b"fo");
+        assert_eq!(base64url_decode(b"Zm9v").unwrap(), b"foo");
+        assert_eq!(base64url_decode(b"-_8").unwrap(), [0xfb, 0xff]);
+        assert!(base64url_decode(b"Z").is_none());
+        assert!(base64url_decode(b"Zm+v").is_none());
+    }
+
+    #[test]
+    fn test_http2_settings() {
+        let mut settings = Settings::default();
+        settings.set_max_concurrent_streams(Some(100));
+        settings.set_initial_window_size(Some(1 << 20));
+
+        let value = settings.encode_http2_settings();
+        assert_eq!(value, "AAMAAABkAAQAEAAA");
+        assert_eq!(
+            Settings::load_http2_settings(value.as_bytes()).unwrap(),
+            settings
+        );
+
+        let empty = Settings::load_http2_settings(b"").unwrap();
+        assert!(empty.values().is_empty());
+        assert!(Settings::load_http2_settings(b"AAMAAABk!").is_err());
+        assert!(Settings::load_http2_settings(b"AAMAAA").is_err());
+    }
+
+    #[test]
+    fn test_unknown_settings() {
+        let mut settings = Settings::default();
+        settings.set_max_concurrent_streams(Some(100));
+        settings.set_unknown_setting(0xf0, 1);
+        settings.set_unknown_setting(0x7, 2);
+        settings.set_unknown_setting(0xf0, 3);
+        assert_eq!(settings.unknown_settings(), &[(0x7, 2), (0xf0, 3)]);
+
+        let mut buf = BytesMut::new();
+        settings.encode(&mut buf);
+
+        let head = Head::parse(&buf);
+        let loaded = Settings::load(head, &buf[crate::frame::HEADER_LEN..]).unwrap();
+        assert_eq!(loaded, settings);
+        assert_eq!(loaded.values(), vec![(3, 100), (0x7, 2), (0xf0, 3)]);
+    }
+
+    #[test]
+    fn test_load_repeated_unknown_settings() {
+        let mut buf = BytesMut::new();
+        let head = Head::new(Kind::Settings, 0, StreamId::zero());
+        head.encode(5 * 6, &mut buf);
+        for &(id, val) in &[(0xf0u16, 1u32), (0x7, 2), (0xf0, 3), (0xa, 4), (0x7, 5)] {
+            buf.put_u16(id);
+            buf.put_u32(val);
+        }
+
+        let head = Head::parse(&buf);
+        let loaded = Settings::load(head, &buf[crate::frame::HEADER_LEN..]).unwrap();
+        assert_eq!(loaded.unknown_settings(), &[(0x7, 5), (0xa, 4), (0xf0, 3)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_settings_implemented() {
+        Settings::default().set_unknown_setting(4, 1);
+    }
+}
@@ -17,6 +17,9 @@ pub struct Decoder {
     // Protocol indicated that the max table size will update
     max_size_update: Option<usize>,
     last_max_update: usize,
+    // The max table size went below the size of the table in use, so the next
+    // header block must start with a size update
+    size_update_required: bool,
     table: Table,
     buffer: BytesMut,
 }
@@ -33,6 +36,7 @@ pub enum DecoderError {
     InvalidStatusCode,
     InvalidPseudoheader,
     InvalidMaxDynamicSize,
+    MissingSizeUpdate,
     IntegerOverflow,
     NeedMore(NeedMore),
 }
@@ -150,13 +154,18 @@ impl Decoder {
         Decoder {
             max_size_update: None,
             last_max_update: size,
+            size_update_required: false,
             table: Table::new(size),
             buffer: BytesMut::with_capacity(4096),
         }
     }
 
+    /// Returns the size of the dynamic table.
+    pub fn table_size(&self) -> usize {
+        self.table.size()
+    }
+
     /// Queues a potential size update
-    #[allow(dead_code)]
     pub fn queue_size_update(&mut self, size: usize) {
         let size = match self.max_size_update {
             Some(v) => cmp::max(v, size),
@@ -181,6 +190,13 @@ impl Decoder {
 
         if let Some(size) = self.max_size_update.take() {
             self.last_max_update = size;
+
+            // > This dynamic table size update MUST occur at the beginning of
+            // > the first header block following the change to the dynamic
+            // > table size.
+            if size < self.table.max_size {
+                self.size_update_required = true;
+            }
         }
 
         log::trace!("decode");
@@ -189,7 +205,16 @@ impl Decoder {
             // At this point we are always at the beginning of the next block
             // within the HPACK data. The type of the block can always be
             // determined from the first byte.
-            match Representation::load(ty)? {
+            let ty = Representation::load(ty)?;
+
+            if self.size_update_required {
+                match ty {
+                    SizeUpdate => {}
+                    _ => return Err(DecoderError::MissingSizeUpdate),
+                }
+            }
+
+            match ty {
                 Indexed => {
                     log::trace!("    Indexed; rem={:?}", src.remaining());
                     can_resize = false;
@@ -255,6 +280,7 @@ impl Decoder {
         );
 
         self.table.set_max_size(new_size);
+        self.size_update_required = false;
 
         Ok(())
     }
@@ -850,6 +876,49 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_decode_requires_size_update_after_shrink() {
+        let mut de = Decoder::new(4096);
+        de.queue_size_update(0);
+
+        // Indexed `:method: GET`
+        let mut buf = BytesMut::new();
+        buf.extend(&[0x82]);
+        let err = de.decode(&mut Cursor::new(&mut buf), |_| {}).unwrap_err();
+        assert_eq!(err, DecoderError::MissingSizeUpdate);
+
+        // Size update to 0, then `:method: GET`
+        let mut buf = BytesMut::new();
+        buf.extend(&[0x20, 0x82]);
+        let mut res = vec![];
+        de.decode(&mut Cursor::new(&mut buf), |h| res.push(h))
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(de.table.max_size, 0);
+    }
+
+    #[test]
+    fn test_decode_allows_size_update_after_grow() {
+        let mut de = Decoder::new(4096);
+        de.queue_size_update(8192);
+
+        let mut buf = BytesMut::new();
+        buf.extend(&[0x82]);
+        de.decode(&mut Cursor::new(&mut buf), |_| {}).unwrap();
+
+        // Size update to 8192
+        let mut buf = BytesMut::new();
+        buf.extend(&[0x3f, 0xe1, 0x3f]);
+        de.decode(&mut Cursor::new(&mut buf), |_| {}).unwrap();
+        assert_eq!(de.table.max_size, 8192);
+
+        // Size update to more than what was allowed
+        let mut buf = BytesMut::new();
+        buf.extend(&[0x3f, 0xe2, 0x3f]);
+        let err = de.decode(&mut Cursor::new(&mut buf), |_| {}).unwrap_err();
+        assert_eq!(err, DecoderError::InvalidMaxDynamicSize);
+    }
+
     fn huff_encode(src: &[u8]) -> BytesMut {
         let mut buf = BytesMut::new();
         huffman::encode(src, &mut buf).unwrap();
@@ -43,6 +43,11 @@ impl Encoder {
         }
     }
 
+    /// Returns the size of the dynamic table.
+    pub fn table_size(&self) -> usize {
+        self.table.size()
+    }
+
     /// Queues a max size update.
     ///
     /// The next call to `encode` will include a dynamic size update frame.
@@ -847,6 +852,29 @@ mod test {
         assert_eq!("sup", huff_decode(&dst[9..]));
     }
 
+    #[test]
+    fn test_encode_protocol_pseudo() {
+        use crate::ext::Protocol;
+        use crate::hpack::Decoder;
+        use std::io::Cursor;
+
+        let mut encoder = Encoder::default();
+        let protocol = Protocol::from_static("websocket");
+        let mut res = encode(&mut encoder, vec![Header::Protocol(protocol.clone())]);
+
+        // `:protocol` is not in the static table, the name is sent literally
+        assert_eq!(res[0], 0b01000000);
+        assert_eq!(encoder.table.len(), 1);
+
+        let mut decoder = Decoder::new(4096);
+        let mut decoded = vec![];
+        decoder
+            .decode(&mut Cursor::new(&mut res), |h| decoded.push(h))
+            .unwrap();
+
+        assert_eq!(decoded, vec![Header::Protocol(protocol)]);
+    }
+
     #[test]
     #[ignore]
     fn test_evicted_overflow() {
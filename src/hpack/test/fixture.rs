@@ -138,6 +138,7 @@ fn key_str(e: &Header) -> &str {
         Header::Method(..) => ":method",
         Header::Scheme(..) => ":scheme",
         Header::Path(..) => ":path",
+        Header::Protocol(..) => ":protocol",
         Header::Status(..) => ":status",
     }
 }
@@ -149,6 +150,7 @@ fn value_str(e: &Header) -> &str {
         Header::Method(ref m) => m.as_str(),
         Header::Scheme(ref v) => &**v,
         Header::Path(ref v) => &**v,
+        Header::Protocol(ref v) => v.as_str(),
         Header::Status(ref v) => v.as_str(),
     }
 }
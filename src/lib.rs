@@ -46,6 +46,12 @@
 //!
 //! See the [Starting HTTP/2] in the specification for more details.
 //!
+//! A connection upgraded from HTTP/1.1 with `Upgrade: h2c` is passed to
+//! `handshake_upgraded` on [`client::Builder`] or [`server::Builder`]
+//! instead, which continue the request that carried the upgrade as stream 1.
+//! A server accepting both HTTP/1.1 and HTTP/2.0 with prior knowledge on the
+//! same listener can detect the client preface with `server::Builder::sniff`.
+//!
 //! # Flow control
 //!
 //! [Flow control] is a fundamental feature of HTTP/2.0. The `h2` library
@@ -77,6 +83,8 @@
 //! [upgrade]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Protocol_upgrade_mechanism
 //! [`server::handshake`]: server/fn.handshake.html
 //! [`client::handshake`]: client/fn.handshake.html
+//! [`client::Builder`]: client/struct.Builder.html
+//! [`server::Builder`]: server/struct.Builder.html
 
 #![doc(html_root_url = "https://docs.rs/h2/0.2.7")]
 #![deny(missing_debug_implementations, missing_docs)]
@@ -104,6 +112,7 @@ macro_rules! ready {
 mod codec;
 mod error;
 mod hpack;
+mod observer;
 mod proto;
 
 #[allow(dead_code)]
@@ -117,11 +126,24 @@ mod frame;
 pub mod frame;
 
 pub mod client;
+pub mod ext;
+mod remote_settings;
 pub mod server;
 mod share;
+mod shutdown;
+mod stats;
+mod timer;
 
 pub use crate::error::{Error, Reason};
-pub use crate::share::{FlowControl, Ping, PingPong, Pong, RecvStream, SendStream, StreamId};
+pub use crate::observer::{FrameDetails, FrameInfo, FrameObserver};
+pub use crate::remote_settings::RemoteSettings;
+pub use crate::share::{
+    FlowControl, Ping, PingPong, Pong, RecvStream, RecvStreamReader, SendStream, SendStreamWriter,
+    StreamId, StreamPriority, Tunnel,
+};
+pub use crate::shutdown::{GracefulShutdown, ShutdownReport};
+pub use crate::stats::{ConnectionStats, FrameCounts, StreamStats};
+pub use crate::timer::{Sleep, Timer};
 
 #[cfg(feature = "unstable")]
 pub use codec::{Codec, RecvError, SendError, UserError};
@@ -0,0 +1,487 @@
+use crate::frame::{self, Frame, Reason};
+use crate::share::StreamId;
+
+use bytes::Bytes;
+use std::fmt;
+use std::sync::Arc;
+
+/// Observes the frames sent and received on a connection.
+///
+/// An observer is registered with the `frame_observer` function of
+/// [`client::Builder`] or [`server::Builder`]. It is told about every frame
+/// as the codec decodes or encodes it, which is cheap enough to build
+/// protocol traces or per frame type metrics with in production.
+///
+/// The functions are called from the task driving the connection, they
+/// should return quickly.
+///
+/// # Examples
+///
+/// ```
+/// # use h2::{FrameInfo, FrameObserver};
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// #
+/// #[derive(Debug, Default)]
+/// struct DataCounter {
+///     bytes: AtomicUsize,
+/// }
+///
+/// impl FrameObserver for DataCounter {
+///     fn frame_received(&self, frame: &FrameInfo) {
+///         if frame.frame_type() == 0 {
+///             self.bytes.fetch_add(frame.payload_len(), Ordering::Relaxed);
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`client::Builder`]: client/struct.Builder.html
+/// [`server::Builder`]: server/struct.Builder.html
+pub trait FrameObserver: Send + Sync {
+    /// Called for every frame received, once it has been decoded.
+    fn frame_received(&self, _frame: &FrameInfo) {}
+
+    /// Called for every frame sent, once it has been encoded.
+    fn frame_sent(&self, _frame: &FrameInfo) {}
+}
+
+/// A description of a frame, without its payload.
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    stream_id: StreamId,
+    flags: u8,
+    payload_len: usize,
+    details: FrameDetails,
+}
+
+/// The type specific fields of a frame.
+///
+/// A header block split into CONTINUATION frames is described as a single
+/// `Headers` or `PushPromise` frame.
+#[derive(Debug, Clone)]
+pub enum FrameDetails {
+    /// A DATA frame.
+    Data {
+        /// Whether the END_STREAM flag is set.
+        end_stream: bool,
+    },
+    /// A HEADERS frame.
+    Headers {
+        /// Whether the END_STREAM flag is set.
+        end_stream: bool,
+        /// The stream dependency, exclusive flag and weight of the frame, if
+        /// the PRIORITY flag is set.
+        dependency: Option<(StreamId, bool, u16)>,
+    },
+    /// A PRIORITY frame.
+    Priority {
+        /// The stream this stream depends on.
+        dependency: StreamId,
+        /// Whether the dependency is exclusive.
+        exclusive: bool,
+        /// The weight, in the range [1, 256].
+        weight: u16,
+    },
+    /// A RST_STREAM frame.
+    Reset {
+        /// The error code.
+        reason: Reason,
+    },
+    /// A SETTINGS frame.
+    Settings {
+        /// Whether the ACK flag is set.
+        ack: bool,
+        /// The identifier and value of each setting.
+        values: Vec<(u16, u32)>,
+    },
+    /// A PUSH_PROMISE frame.
+    PushPromise {
+        /// The promised stream.
+        promised_id: StreamId,
+    },
+    /// A PING frame.
+    Ping {
+        /// Whether the ACK flag is set.
+        ack: bool,
+        /// The opaque data.
+        opaque_data: [u8; 8],
+    },
+    /// A GOAWAY frame.
+    GoAway {
+        /// The last stream processed by the sender.
+        last_stream_id: StreamId,
+        /// The error code.
+        reason: Reason,
+    },
+    /// A WINDOW_UPDATE frame.
+    WindowUpdate {
+        /// The window size increment.
+        size_increment: u32,
+    },
+    /// A PRIORITY_UPDATE frame.
+    PriorityUpdate {
+        /// The stream being reprioritized.
+        prioritized_id: StreamId,
+        /// The urgency, in the range [0, 7].
+        urgency: u8,
+        /// Whether the response is incremental.
+        incremental: bool,
+    },
+    /// An ALTSVC frame.
+    AltSvc {
+        /// The origin the alternative services apply to, empty on a request
+        /// stream.
+        origin: Bytes,
+        /// The value, in the syntax of the `Alt-Svc` header field.
+        value: Bytes,
+    },
+    /// An ORIGIN frame.
+    Origin {
+        /// The ASCII serialization of each origin.
+        origins: Vec<String>,
+    },
+    /// A frame of a type registered as an extension.
+    Extension {
+        /// The frame type code.
+        frame_type: u8,
+    },
+    /// A received frame of a type neither implemented nor registered as an
+    /// extension, which is discarded.
+    Unknown {
+        /// The frame type code.
+        frame_type: u8,
+    },
+}
+
+/// A shared `FrameObserver`, as kept by the builders.
+#[derive(Clone)]
+pub(crate) struct ObserverHandle(Arc<dyn FrameObserver>);
+
+// ===== impl FrameInfo =====
+
+impl FrameInfo {
+    pub(crate) fn new<T>(frame: &Frame<T>, payload_len: usize) -> Self {
+        use crate::frame::Frame::*;
+
+        let id = StreamId::from_internal;
+
+        let (stream_id, details) = match *frame {
+            Data(ref v) => (
+                v.stream_id(),
+                FrameDetails::Data {
+                    end_stream: v.is_end_stream(),
+                },
+            ),
+            Headers(ref v) => (
+                v.stream_id(),
+                FrameDetails::Headers {
+                    end_stream: v.is_end_stream(),
+                    dependency: v.stream_dependency().map(|dep| {
+                        (
+                            id(dep.dependency_id()),
+                            dep.is_exclusive(),
+                            u16::from(dep.weight()) + 1,
+                        )
+                    }),
+                },
+            ),
+            Priority(ref v) => (
+                v.stream_id(),
+                FrameDetails::Priority {
+                    dependency: id(v.dependency().dependency_id()),
+                    exclusive: v.dependency().is_exclusive(),
+                    weight: u16::from(v.dependency().weight()) + 1,
+                },
+            ),
+            PriorityUpdate(ref v) => (
+                frame::StreamId::ZERO,
+                FrameDetails::PriorityUpdate {
+                    prioritized_id: id(v.prioritized_id()),
+                    urgency: v.params().urgency(),
+                    incremental: v.params().is_incremental(),
+                },
+            ),
+            PushPromise(ref v) => (
+                v.stream_id(),
+                FrameDetails::PushPromise {
+                    promised_id: id(v.promised_id()),
+                },
+            ),
+            Settings(ref v) => (
+                frame::StreamId::ZERO,
+                FrameDetails::Settings {
+                    ack: v.is_ack(),
+                    values: v.values(),
+                },
+            ),
+            Ping(ref v) => (
+                frame::StreamId::ZERO,
+                FrameDetails::Ping {
+                    ack: v.is_ack(),
+                    opaque_data: *v.payload(),
+                },
+            ),
+            GoAway(ref v) => (
+                frame::StreamId::ZERO,
+                FrameDetails::GoAway {
+                    last_stream_id: id(v.last_stream_id()),
+                    reason: v.reason(),
+                },
+            ),
+            WindowUpdate(ref v) => (
+                v.stream_id(),
+                FrameDetails::WindowUpdate {
+                    size_increment: v.size_increment(),
+                },
+            ),
+            Reset(ref v) => (v.stream_id(), FrameDetails::Reset { reason: v.reason() }),
+            AltSvc(ref v) => (
+                v.stream_id(),
+                FrameDetails::AltSvc {
+                    origin: v.origin().clone(),
+                    value: v.value().clone(),
+                },
+            ),
+            Origin(ref v) => (
+                frame::StreamId::ZERO,
+                FrameDetails::Origin {
+                    origins: v.origins().to_vec(),
+                },
+            ),
+            Extension(ref v) => (
+                v.stream_id(),
+                FrameDetails::Extension {
+                    frame_type: v.kind(),
+                },
+            ),
+        };
+
+        FrameInfo {
+            stream_id: id(stream_id),
+            flags: 0,
+            payload_len,
+            details,
+        }
+    }
+
+    /// Describes a received frame of a type that is discarded.
+    pub(crate) fn unknown(head: frame::Head, frame_type: u8, payload_len: usize) -> Self {
+        FrameInfo {
+            stream_id: StreamId::from_internal(head.stream_id()),
+            flags: head.flag(),
+            payload_len,
+            details: FrameDetails::Unknown { frame_type },
+        }
+    }
+
+    /// Returns the frame type code, as registered with IANA.
+    pub fn frame_type(&self) -> u8 {
+        match self.details {
+            FrameDetails::Data { .. } => 0,
+            FrameDetails::Headers { .. } => 1,
+            FrameDetails::Priority { .. } => 2,
+            FrameDetails::Reset { .. } => 3,
+            FrameDetails::Settings { .. } => 4,
+            FrameDetails::PushPromise { .. } => 5,
+            FrameDetails::Ping { .. } => 6,
+            FrameDetails::GoAway { .. } => 7,
+            FrameDetails::WindowUpdate { .. } => 8,
+            FrameDetails::AltSvc { .. } => 10,
+            FrameDetails::Origin { .. } => 12,
+            FrameDetails::PriorityUpdate { .. } => 16,
+            FrameDetails::Extension { frame_type } | FrameDetails::Unknown { frame_type } => {
+                frame_type
+            }
+        }
+    }
+
+    /// Returns the flags of the frame header.
+    ///
+    /// For a header block, these are the flags of its first frame.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the stream the frame was sent on, which is stream 0 for
+    /// connection level frames.
+    pub fn stream_id(&self) -> &StreamId {
+        &self.stream_id
+    }
+
+    /// Returns the length of the payload of the frame, in bytes, as given by
+    /// the frame header.
+    ///
+    /// For a header block, this includes the payload of the CONTINUATION
+    /// frames.
+    pub fn payload_len(&self) -> usize {
+        self.payload_len
+    }
+
+    /// Returns the type specific fields of the frame.
+    pub fn details(&self) -> &FrameDetails {
+        &self.details
+    }
+
+    pub(crate) fn set_flags(&mut self, flags: u8) {
+        self.flags = flags;
+    }
+
+    /// Accounts for the payload of a CONTINUATION frame.
+    pub(crate) fn add_payload_len(&mut self, len: usize) {
+        self.payload_len += len;
+    }
+}
+
+// ===== impl ObserverHandle =====
+
+impl ObserverHandle {
+    pub(crate) fn new<O: FrameObserver + 'static>(observer: O) -> Self {
+        ObserverHandle(Arc::new(observer))
+    }
+
+    pub(crate) fn frame_received(&self, info: &FrameInfo) {
+        self.0.frame_received(info);
+    }
+
+    pub(crate) fn frame_sent(&self, info: &FrameInfo) {
+        self.0.frame_sent(info);
+    }
+}
+
+impl fmt::Debug for ObserverHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("FrameObserver").finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codec::Codec;
+
+    use bytes::Bytes;
+    use futures_core::Stream;
+    use futures_util::io::Cursor;
+    use futures_util::task::noop_waker_ref;
+    use http::HeaderMap;
+    use std::pin::Pin;
+    use std::sync::Mutex;
+    use std::task::{Context, Poll};
+
+    #[derive(Default)]
+    struct Recorder {
+        received: Mutex<Vec<FrameInfo>>,
+        sent: Mutex<Vec<FrameInfo>>,
+    }
+
+    impl FrameObserver for Arc<Recorder> {
+        fn frame_received(&self, frame: &FrameInfo) {
+            self.received.lock().unwrap().push(frame.clone());
+        }
+
+        fn frame_sent(&self, frame: &FrameInfo) {
+            self.sent.lock().unwrap().push(frame.clone());
+        }
+    }
+
+    #[test]
+    fn test_continued_header_block() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let recorder = Arc::new(Recorder::default());
+
+        let mut fields = HeaderMap::new();
+        for i in 0..10 {
+            let name = format!("x-large-{}", i);
+            let value = "~".repeat(3_000);
+            fields.insert(
+                http::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        let headers = frame::Headers::trailers(1.into(), fields);
+
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(Vec::new()));
+        codec.set_observer(ObserverHandle::new(recorder.clone()));
+        codec.buffer(headers.into()).unwrap();
+        assert!(codec.flush(&mut cx).is_ready());
+
+        let written = codec.get_mut().get_ref().clone();
+        let sent = recorder.sent.lock().unwrap().clone();
+
+        // Described as one frame, with the payload of all the CONTINUATION
+        // frames
+        let frames = codec.stats().snapshot().frames_sent().total() as usize;
+        assert!(frames > 1);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].frame_type(), 1);
+        assert_eq!(
+            sent[0].payload_len(),
+            written.len() - frames * frame::HEADER_LEN
+        );
+
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(written));
+        codec.set_observer(ObserverHandle::new(recorder.clone()));
+        match Pin::new(&mut codec).poll_next(&mut cx) {
+            Poll::Ready(Some(Ok(Frame::Headers(_)))) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+
+        let received = recorder.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].payload_len(), sent[0].payload_len());
+        match *received[0].details() {
+            FrameDetails::Headers { end_stream, .. } => assert!(end_stream),
+            ref details => panic!("unexpected; details={:?}", details),
+        }
+    }
+
+    #[test]
+    fn test_unknown_frame_observed() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let recorder = Arc::new(Recorder::default());
+
+        // An unknown frame of type 0xfa with flags 0x3 on stream 3, then a
+        // DATA frame with END_STREAM set
+        let mut input = vec![0, 0, 2, 0xfa, 0x3, 0, 0, 0, 3, 1, 2];
+        input.extend_from_slice(&[0, 0, 1, 0, 0x1, 0, 0, 0, 1, 9]);
+
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(input));
+        codec.set_observer(ObserverHandle::new(recorder.clone()));
+        match Pin::new(&mut codec).poll_next(&mut cx) {
+            Poll::Ready(Some(Ok(Frame::Data(_)))) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+
+        let received = recorder.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 2);
+
+        assert_eq!(received[0].frame_type(), 0xfa);
+        assert_eq!(received[0].flags(), 0x3);
+        assert_eq!(*received[0].stream_id(), StreamId::from_internal(3.into()));
+        assert_eq!(received[0].payload_len(), 2);
+        match *received[0].details() {
+            FrameDetails::Unknown { frame_type } => assert_eq!(frame_type, 0xfa),
+            ref details => panic!("unexpected; details={:?}", details),
+        }
+
+        assert_eq!(received[1].frame_type(), 0);
+        assert_eq!(received[1].flags(), 0x1);
+    }
+
+    #[test]
+    fn test_sent_flags() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let recorder = Arc::new(Recorder::default());
+
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(Vec::new()));
+        codec.set_observer(ObserverHandle::new(recorder.clone()));
+        codec.buffer(frame::Ping::pong([0; 8]).into()).unwrap();
+        codec.buffer(frame::Ping::new([0; 8]).into()).unwrap();
+        assert!(codec.flush(&mut cx).is_ready());
+
+        let sent = recorder.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].flags(), 0x1);
+        assert_eq!(sent[1].flags(), 0);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::proto::WindowSize;
+
+use std::time::Instant;
+
+/// Estimates the bandwidth-delay product of the connection, to size the
+/// receive windows adaptively.
+///
+/// A PING is sent when DATA starts arriving, and the bytes received until it
+/// is acknowledged are counted. If those fill most of the current window, the
+/// window was the bottleneck of that round-trip and it is grown to twice the
+/// sampled amount, up to `max_window`.
+#[derive(Debug)]
+pub(super) struct Bdp {
+    /// The window may not be grown beyond this.
+    max_window: WindowSize,
+
+    /// The current estimate, which is the window to use.
+    window: WindowSize,
+
+    /// True if `window` changed and still has to be applied.
+    is_window_changed: bool,
+
+    state: State,
+
+    /// Bytes received since the sample started.
+    bytes: usize,
+
+    /// Smoothed round-trip time, in seconds.
+    rtt: f64,
+
+    /// Highest bandwidth sampled so far, in bytes per second.
+    max_bandwidth: f64,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Not sampling.
+    Idle,
+    /// DATA was received, the PING still has to be sent.
+    PingUnsent,
+    /// Waiting for the PING sent at the given instant to be acknowledged.
+    PingSent(Instant),
+}
+
+impl Bdp {
+    pub(super) fn new(init_window: WindowSize, max_window: WindowSize) -> Self {
+        Bdp {
+            max_window,
+            window: init_window,
+            is_window_changed: false,
+            state: State::Idle,
+            bytes: 0,
+            rtt: 0.0,
+            max_bandwidth: 0.0,
+        }
+    }
+
+    /// Records received DATA, starting a new sample if there is none yet.
+    pub(super) fn recv_data(&mut self, len: usize) {
+        if self.window >= self.max_window {
+            // Nothing left to grow
+            return;
+        }
+
+        if self.state == State::Idle {
+            self.state = State::PingUnsent;
+            self.bytes = 0;
+        }
+
+        self.bytes += len;
+    }
+
+    pub(super) fn is_ping_unsent(&self) -> bool {
+        self.state == State::PingUnsent
+    }
+
+    pub(super) fn ping_sent(&mut self) {
+        if self.state == State::PingUnsent {
+            self.state = State::PingSent(Instant::now());
+        }
+    }
+
+    /// Completes the sample on acknowledgement of the PING.
+    pub(super) fn recv_pong(&mut self) {
+        let sent_at = match self.state {
+            State::PingSent(at) => at,
+            _ => return,
+        };
+
+        self.state = State::Idle;
+
+        let elapsed = sent_at.elapsed();
+        let sample = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+
+        if self.rtt == 0.0 {
+            self.rtt = sample;
+        } else {
+            // Exponential moving average, weighing in 1/8 of the sample
+            self.rtt += (sample - self.rtt) / 8.0;
+        }
+
+        if self.rtt <= 0.0 {
+            return;
+        }
+
+        let bandwidth = self.bytes as f64 / self.rtt;
+
+        log::trace!(
+            "bdp sample; bytes={}; rtt={}; bandwidth={}; window={}",
+            self.bytes,
+            self.rtt,
+            bandwidth,
+            self.window
+        );
+
+        if bandwidth < self.max_bandwidth {
+            return;
+        }
+
+        self.max_bandwidth = bandwidth;
+
+        if self.bytes >= self.window as usize / 3 * 2 {
+            let window = (self.bytes * 2).min(self.max_window as usize) as WindowSize;
+
+            if window > self.window {
+                log::debug!("bdp; growing window; old={}; new={}", self.window, window);
+                self.window = window;
+                self.is_window_changed = true;
+            }
+        }
+    }
+
+    /// Returns the window to apply, if it has changed.
+    pub(super) fn window_update(&self) -> Option<WindowSize> {
+        if self.is_window_changed {
+            Some(self.window)
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn window_applied(&mut self) {
+        self.is_window_changed = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grows_window_when_filled() {
+        let mut bdp = Bdp::new(100, 1_000);
+
+        bdp.recv_data(50);
+        assert!(bdp.is_ping_unsent());
+        bdp.ping_sent();
+        bdp.recv_data(40);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        bdp.recv_pong();
+
+        assert_eq!(bdp.window_update(), Some(180));
+        bdp.window_applied();
+        assert_eq!(bdp.window_update(), None);
+
+        // Capped by the max window
+        bdp.recv_data(900);
+        bdp.ping_sent();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        bdp.recv_pong();
+
+        assert_eq!(bdp.window_update(), Some(1_000));
+        bdp.window_applied();
+
+        // No more sampling once at the max
+        bdp.recv_data(900);
+        assert!(!bdp.is_ping_unsent());
+    }
+
+    #[test]
+    fn test_keeps_window_when_not_filled() {
+        let mut bdp = Bdp::new(100, 1_000);
+
+        bdp.recv_data(10);
+        bdp.ping_sent();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        bdp.recv_pong();
+
+        assert_eq!(bdp.window_update(), None);
+        assert_eq!(bdp.state, State::Idle);
+    }
+}
@@ -4,15 +4,19 @@ use crate::{client, frame, proto, server};
 
 use crate::frame::DEFAULT_INITIAL_WINDOW_SIZE;
 use crate::proto::*;
+use crate::remote_settings::RemoteSettings;
+use crate::shutdown::{GracefulShutdown, ShutdownNotifier};
+use crate::stats::ConnectionStats;
+use crate::timer::{Sleep, TimerHandle};
 
 use bytes::{Buf, Bytes};
 use futures_core::Stream;
 use futures_io::{AsyncRead, AsyncWrite};
-use std::io;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use std::{fmt, io};
 
 /// An H2 connection
 #[derive(Debug)]
@@ -38,12 +42,24 @@ where
     /// Ping/pong handler
     ping_pong: PingPong,
 
+    /// Flood protection
+    flood: Flood,
+
+    /// Extension frames received and to send
+    extensions: Extensions,
+
     /// Connection settings
     settings: Settings,
 
     /// Stream state handler
     streams: Streams<B, P>,
 
+    /// Timer for the graceful shutdown deadline, if one was given
+    timer: Option<TimerHandle>,
+
+    /// Graceful shutdown started with a deadline, if any
+    shutdown: Option<Shutdown>,
+
     /// Client or server
     _phantom: PhantomData<P>,
 }
@@ -54,7 +70,24 @@ pub(crate) struct Config {
     pub initial_max_send_streams: usize,
     pub reset_stream_duration: Duration,
     pub reset_stream_max: usize,
+    pub max_send_buffer_size: usize,
+    pub auto_release_capacity: bool,
+    pub max_recv_buffer_size: Option<usize>,
+    pub remote_reset_limit: Option<(usize, Duration)>,
     pub settings: frame::Settings,
+    pub keep_alive: Option<KeepAliveConfig>,
+    /// Maximum window size, if windows are sized adaptively.
+    pub max_adaptive_window_size: Option<WindowSize>,
+    pub flood: FloodConfig,
+    pub timer: Option<TimerHandle>,
+}
+
+/// A graceful shutdown with a deadline.
+struct Shutdown {
+    /// Fires when the deadline passes, taken once it did.
+    sleep: Option<Sleep>,
+
+    notifier: ShutdownNotifier,
 }
 
 #[derive(Debug)]
@@ -76,30 +109,49 @@ where
     B: Buf,
 {
     pub fn new(codec: Codec<T, Prioritized<B>>, config: Config) -> Connection<T, P, B> {
-        let streams = Streams::new(streams::Config {
-            local_init_window_sz: config
-                .settings
-                .initial_window_size()
-                .unwrap_or(DEFAULT_INITIAL_WINDOW_SIZE),
+        let local_init_window_sz = config
+            .settings
+            .initial_window_size()
+            .unwrap_or(DEFAULT_INITIAL_WINDOW_SIZE);
+        let streams_config = streams::Config {
+            local_init_window_sz,
             initial_max_send_streams: config.initial_max_send_streams,
             local_next_stream_id: config.next_stream_id,
             local_push_enabled: config.settings.is_push_enabled().unwrap_or(true),
+            local_extended_connect_protocol_enabled: config
+                .settings
+                .is_extended_connect_protocol_enabled()
+                .unwrap_or(false),
             local_reset_duration: config.reset_stream_duration,
             local_reset_max: config.reset_stream_max,
+            local_max_buffer_size: config.max_send_buffer_size,
+            local_auto_release_capacity: config.auto_release_capacity,
+            local_max_recv_buffer_size: config.max_recv_buffer_size,
             remote_init_window_sz: DEFAULT_INITIAL_WINDOW_SIZE,
             remote_max_initiated: config
                 .settings
                 .max_concurrent_streams()
                 .map(|max| max as usize),
-        });
+            remote_reset_limit: config.remote_reset_limit,
+        };
+        let streams = Streams::new(streams_config, codec.stats().clone());
         Connection {
             state: State::Open,
             error: None,
             codec,
             go_away: GoAway::new(),
-            ping_pong: PingPong::new(),
+            ping_pong: PingPong::new(
+                config.keep_alive,
+                config
+                    .max_adaptive_window_size
+                    .map(|max| (local_init_window_sz, max)),
+            ),
+            flood: Flood::new(config.flood),
+            extensions: Extensions::default(),
             settings: Settings::new(config.settings),
             streams,
+            timer: config.timer,
+            shutdown: None,
             _phantom: PhantomData,
         }
     }
@@ -116,6 +168,61 @@ where
         self.settings.send_settings(settings)
     }
 
+    /// Send a new SETTINGS frame with settings this crate doesn't implement.
+    pub(crate) fn send_custom_settings(&mut self, custom: &[(u16, u32)]) -> Result<(), UserError> {
+        if !custom
+            .iter()
+            .all(|&(id, _)| frame::Settings::is_unknown_setting(id))
+        {
+            return Err(UserError::ImplementedSetting);
+        }
+
+        let mut settings = frame::Settings::default();
+
+        for &(id, val) in custom {
+            settings.set_unknown_setting(id, val);
+        }
+
+        self.settings.send_settings(settings)
+    }
+
+    pub(crate) fn remote_settings(&self) -> RemoteSettings {
+        self.streams.remote_settings()
+    }
+
+    pub(crate) fn poll_remote_settings(
+        &mut self,
+        cx: &Context,
+        seen: u64,
+    ) -> Poll<Option<RemoteSettings>> {
+        self.streams.poll_remote_settings(cx, seen)
+    }
+
+    /// Opens stream 1 with the headers of a request received over HTTP/1.1
+    /// and upgraded to HTTP/2.0, applying the settings of its `HTTP2-Settings`
+    /// header first.
+    pub(crate) fn recv_upgrade(
+        &mut self,
+        settings: &frame::Settings,
+        headers: frame::Headers,
+    ) -> Result<(), proto::Error> {
+        let res = self
+            .settings
+            .recv_implicit(settings, &mut self.codec, &mut self.streams)
+            .and_then(|_| self.streams.recv_headers(headers));
+
+        match res {
+            Ok(()) => Ok(()),
+            Err(RecvError::Stream { id, reason }) => {
+                log::trace!("upgraded stream error; id={:?}; reason={:?}", id, reason);
+                self.streams.send_reset(id, reason);
+                Ok(())
+            }
+            Err(RecvError::Connection(reason)) => Err(reason.into()),
+            Err(RecvError::Io(e)) => Err(e.into()),
+        }
+    }
+
     /// Returns `Ready` when the connection is ready to receive a frame.
     ///
     /// Returns `RecvError` as this may raise errors that are caused by delayed
@@ -128,6 +235,7 @@ where
             .settings
             .poll_send(cx, &mut self.codec, &mut self.streams))?;
         ready!(self.streams.send_pending_refusal(cx, &mut self.codec))?;
+        ready!(self.extensions.poll_send(cx, &mut self.codec))?;
 
         Poll::Ready(Ok(()))
     }
@@ -162,6 +270,10 @@ where
     }
 
     fn take_error(&mut self, ours: Reason) -> Poll<Result<(), proto::Error>> {
+        if self.ping_pong.is_keep_alive_timed_out() {
+            return Poll::Ready(Err(keep_alive::timed_out().into()));
+        }
+
         let reason = if let Some(theirs) = self.error.take() {
             match (ours, theirs) {
                 // If either side reported an error, return that
@@ -194,10 +306,50 @@ where
         }
     }
 
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        self.streams.stats()
+    }
+
     pub(crate) fn take_user_pings(&mut self) -> Option<UserPings> {
         self.ping_pong.take_user_pings()
     }
 
+    /// Queues an extension frame, to be written when the connection is next
+    /// polled.
+    pub(crate) fn send_extension_frame(
+        &mut self,
+        frame: frame::Extension,
+    ) -> Result<(), UserError> {
+        let max_frame_size = self.codec.max_send_frame_size();
+        self.extensions.send(frame, max_frame_size)
+    }
+
+    pub(crate) fn poll_extension_frame(&mut self, cx: &Context) -> Poll<Option<frame::Extension>> {
+        self.extensions.poll_recv(cx)
+    }
+
+    /// Queues an ALTSVC frame, to be written when the connection is next
+    /// polled.
+    pub(crate) fn send_alt_svc(&mut self, frame: frame::AltSvc) -> Result<(), UserError> {
+        let max_frame_size = self.codec.max_send_frame_size();
+        self.extensions.send_alt_svc(frame, max_frame_size)
+    }
+
+    pub(crate) fn poll_alt_svc(&mut self, cx: &Context) -> Poll<Option<frame::AltSvc>> {
+        self.extensions.poll_recv_alt_svc(cx)
+    }
+
+    /// Queues an ORIGIN frame, to be written when the connection is next
+    /// polled.
+    pub(crate) fn send_origin(&mut self, frame: frame::Origin) -> Result<(), UserError> {
+        let max_frame_size = self.codec.max_send_frame_size();
+        self.extensions.send_origin(frame, max_frame_size)
+    }
+
+    pub(crate) fn origin_set(&self) -> Option<Vec<http::Uri>> {
+        self.streams.origin_set()
+    }
+
     /// Advances the internal state of the connection.
     pub fn poll(&mut self, cx: &mut Context) -> Poll<Result<(), proto::Error>> {
         // XXX(eliza): cloning the span is unfortunately necessary here in
@@ -247,6 +399,7 @@ where
                             }
 
                             // Reset all active streams
+                            self.finish_shutdown(false);
                             self.streams.recv_err(&e.into());
                             self.go_away_now(e);
                         }
@@ -266,6 +419,8 @@ where
                             let e = e.into();
 
                             // Reset all active streams
+                            self.finish_shutdown(false);
+                            self.extensions.close();
                             self.streams.recv_err(&e);
 
                             // Return the error
@@ -281,7 +436,11 @@ where
                     // Transition the state to error
                     self.state = State::Closed(reason);
                 }
-                State::Closed(reason) => return self.take_error(reason),
+                State::Closed(reason) => {
+                    self.finish_shutdown(false);
+                    self.extensions.close();
+                    return self.take_error(reason);
+                }
             }
         }
     }
@@ -294,6 +453,11 @@ where
         // second (and thus, the clock wouldn't have changed enough to matter).
         self.clear_expired_reset_streams();
 
+        // This also happens outside of the loop, as it only needs to be
+        // checked each time the connection is woken up.
+        self.poll_keep_alive(cx);
+        self.poll_shutdown_deadline(cx);
+
         loop {
             // First, ensure that the `Connection` is able to receive a frame
             //
@@ -319,13 +483,22 @@ where
             }
             ready!(self.poll_ready(cx))?;
 
-            match ready!(Pin::new(&mut self.codec).poll_next(cx)?) {
+            let frame = ready!(Pin::new(&mut self.codec).poll_next(cx)?);
+
+            if let Some(ref frame) = frame {
+                self.ping_pong.recv_frame();
+                self.flood
+                    .recv_frame(frame, self.codec.pending_replies(), self.codec.stats())?;
+            }
+
+            match frame {
                 Some(Headers(frame)) => {
                     log::trace!("recv HEADERS; frame={:?}", frame);
                     self.streams.recv_headers(frame)?;
                 }
                 Some(Data(frame)) => {
                     log::trace!("recv DATA; frame={:?}", frame);
+                    self.ping_pong.recv_data(frame.payload().len());
                     self.streams.recv_data(frame)?;
                 }
                 Some(Reset(frame)) => {
@@ -340,6 +513,7 @@ where
                     log::trace!("recv SETTINGS; frame={:?}", frame);
                     self.settings
                         .recv_settings(frame, &mut self.codec, &mut self.streams)?;
+                    self.apply_adaptive_window();
                 }
                 Some(GoAway(frame)) => {
                     log::trace!("recv GOAWAY; frame={:?}", frame);
@@ -353,6 +527,9 @@ where
                 Some(Ping(frame)) => {
                     log::trace!("recv PING; frame={:?}", frame);
                     let status = self.ping_pong.recv_ping(frame);
+                    if let Some(rtt) = self.ping_pong.last_rtt() {
+                        self.codec.stats().set_rtt(rtt);
+                    }
                     if status.is_shutdown() {
                         assert!(
                             self.go_away.is_going_away(),
@@ -362,6 +539,8 @@ where
                         let last_processed_id = self.streams.last_processed_id();
                         self.go_away(last_processed_id, Reason::NO_ERROR);
                     }
+
+                    self.apply_adaptive_window();
                 }
                 Some(WindowUpdate(frame)) => {
                     log::trace!("recv WINDOW_UPDATE; frame={:?}", frame);
@@ -369,7 +548,30 @@ where
                 }
                 Some(Priority(frame)) => {
                     log::trace!("recv PRIORITY; frame={:?}", frame);
-                    // TODO: handle
+                    self.streams.recv_priority(&frame)?;
+                }
+                Some(PriorityUpdate(frame)) => {
+                    log::trace!("recv PRIORITY_UPDATE; frame={:?}", frame);
+                    self.streams.recv_priority_update(&frame)?;
+                }
+                Some(AltSvc(frame)) => {
+                    log::trace!("recv ALTSVC; frame={:?}", frame);
+
+                    if P::is_server() {
+                        // Only servers send ALTSVC frames, a server ignores
+                        // them.
+                        log::trace!("ignoring ALTSVC frame as server");
+                    } else {
+                        self.extensions.recv_alt_svc(frame);
+                    }
+                }
+                Some(Origin(frame)) => {
+                    log::trace!("recv ORIGIN; frame={:?}", frame);
+                    self.streams.recv_origin(frame);
+                }
+                Some(Extension(frame)) => {
+                    log::trace!("recv extension frame; frame={:?}", frame);
+                    self.extensions.recv_frame(frame)?;
                 }
                 None => {
                     log::trace!("codec closed");
@@ -383,34 +585,76 @@ where
     fn clear_expired_reset_streams(&mut self) {
         self.streams.clear_expired_reset_streams();
     }
-}
 
-impl<T, B> Connection<T, client::Peer, B>
-where
-    T: AsyncRead + AsyncWrite,
-    B: Buf,
-{
-    pub(crate) fn streams(&self) -> &Streams<B, client::Peer> {
-        &self.streams
+    /// Grows the windows to the latest BDP estimate, if it has changed.
+    fn apply_adaptive_window(&mut self) {
+        if let Some(size) = self.ping_pong.adaptive_window_update() {
+            let mut settings = frame::Settings::default();
+            settings.set_initial_window_size(Some(size));
+
+            // Only one SETTINGS frame can be in flight, if there is one
+            // already this is retried when its ACK is received.
+            if self.settings.send_internal_settings(settings) {
+                self.streams.grow_target_connection_window_size(size);
+                self.ping_pong.adaptive_window_applied();
+            }
+        }
     }
-}
 
-impl<T, B> Connection<T, server::Peer, B>
-where
-    T: AsyncRead + AsyncWrite + Unpin,
-    B: Buf,
-{
-    pub fn next_incoming(&mut self) -> Option<StreamRef<B>> {
-        self.streams.next_incoming()
+    fn poll_keep_alive(&mut self, cx: &mut Context) {
+        let is_idle = !self.streams.has_streams();
+
+        if let Err(e) = self.ping_pong.poll_keep_alive(cx, is_idle) {
+            // The peer is unresponsive, fail all streams and try to let it
+            // know we're leaving.
+            self.streams.recv_err(&e.into());
+            self.go_away_now(Reason::NO_ERROR);
+        }
+    }
+
+    /// Starts a graceful shutdown, and closes the connection abruptly if
+    /// streams are still active once `timeout` has passed.
+    pub fn go_away_gracefully_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<GracefulShutdown, UserError> {
+        if let Some(ref shutdown) = self.shutdown {
+            return Ok(shutdown.notifier.handle());
+        }
+
+        let sleep = self
+            .timer
+            .as_ref()
+            .ok_or(UserError::MissingTimer)?
+            .sleep(timeout);
+
+        self.go_away_gracefully();
+
+        let notifier = ShutdownNotifier::new();
+        let handle = notifier.handle();
+
+        self.shutdown = Some(Shutdown {
+            sleep: Some(sleep),
+            notifier,
+        });
+
+        Ok(handle)
     }
 
-    // Graceful shutdown only makes sense for server peers.
     pub fn go_away_gracefully(&mut self) {
         if self.go_away.is_going_away() {
             // No reason to start a new one.
             return;
         }
 
+        if !P::is_server() {
+            // A client only has to tell the server which pushed streams it
+            // processed, new requests are refused locally.
+            let last_processed_id = self.streams.last_processed_id();
+            self.go_away(last_processed_id, Reason::NO_ERROR);
+            return;
+        }
+
         // According to http://httpwg.org/specs/rfc7540.html#GOAWAY:
         //
         // > A server that is attempting to gracefully shut down a connection
@@ -428,6 +672,61 @@ where
         // for a pong before proceeding.
         self.ping_pong.ping_shutdown();
     }
+
+    fn poll_shutdown_deadline(&mut self, cx: &mut Context) {
+        let is_expired = match self.shutdown {
+            Some(Shutdown {
+                sleep: Some(ref mut sleep),
+                ..
+            }) => sleep.as_mut().poll(cx).is_ready(),
+            _ => false,
+        };
+
+        if is_expired {
+            log::debug!("graceful shutdown timed out; closing the connection");
+            self.finish_shutdown(true);
+            self.go_away_from_user(Reason::NO_ERROR);
+        }
+    }
+
+    /// Resolves the `GracefulShutdown` futures with the streams still active.
+    fn finish_shutdown(&mut self, timed_out: bool) {
+        if let Some(ref mut shutdown) = self.shutdown {
+            shutdown.sleep = None;
+            shutdown
+                .notifier
+                .finish(timed_out, self.streams.active_stream_ids());
+        }
+    }
+}
+
+impl<T, B> Connection<T, client::Peer, B>
+where
+    T: AsyncRead + AsyncWrite,
+    B: Buf,
+{
+    pub(crate) fn streams(&self) -> &Streams<B, client::Peer> {
+        &self.streams
+    }
+}
+
+impl<T, B> Connection<T, server::Peer, B>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    B: Buf,
+{
+    pub fn next_incoming(&mut self) -> Option<StreamRef<B>> {
+        self.streams.next_incoming()
+    }
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Shutdown")
+            .field("is_expired", &self.sleep.is_none())
+            .field("notifier", &self.notifier)
+            .finish()
+    }
 }
 
 impl<T, P, B> Drop for Connection<T, P, B>
@@ -436,6 +735,12 @@ where
     B: Buf,
 {
     fn drop(&mut self) {
+        if let Some(ref shutdown) = self.shutdown {
+            shutdown
+                .notifier
+                .finish(false, self.streams.active_stream_ids());
+        }
+
         // Ignore errors as this indicates that the mutex is poisoned.
         let _ = self.streams.recv_eof(true);
     }
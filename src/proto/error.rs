@@ -7,6 +7,11 @@ use std::io;
 #[derive(Debug)]
 pub enum Error {
     Proto(Reason),
+    /// The stream was not processed by the peer, which sent a GOAWAY frame.
+    GoAway(Reason),
+    /// The stream was not processed by the peer, which reset it with
+    /// REFUSED_STREAM.
+    Refused,
     Io(io::Error),
 }
 
@@ -17,6 +22,8 @@ impl Error {
     pub(super) fn shallow_clone(&self) -> Error {
         match *self {
             Error::Proto(reason) => Error::Proto(reason),
+            Error::GoAway(reason) => Error::GoAway(reason),
+            Error::Refused => Error::Refused,
             Error::Io(ref io) => Error::Io(io::Error::from(io.kind())),
         }
     }
@@ -37,7 +44,8 @@ impl From<io::Error> for Error {
 impl From<Error> for RecvError {
     fn from(src: Error) -> RecvError {
         match src {
-            Error::Proto(reason) => RecvError::Connection(reason),
+            Error::Proto(reason) | Error::GoAway(reason) => RecvError::Connection(reason),
+            Error::Refused => RecvError::Connection(Reason::REFUSED_STREAM),
             Error::Io(e) => RecvError::Io(e),
         }
     }
@@ -47,6 +55,8 @@ impl From<Error> for SendError {
     fn from(src: Error) -> SendError {
         match src {
             Error::Proto(reason) => SendError::Connection(reason),
+            Error::GoAway(reason) => SendError::GoAway(reason),
+            Error::Refused => SendError::Connection(Reason::REFUSED_STREAM),
             Error::Io(e) => SendError::Io(e),
         }
     }
@@ -0,0 +1,386 @@
+use crate::codec::{Codec, RecvError, UserError};
+use crate::frame::{self, Kind, Reason};
+
+use bytes::Buf;
+use futures_io::AsyncWrite;
+use std::collections::VecDeque;
+use std::io;
+use std::task::{Context, Poll, Waker};
+
+/// Number of bytes of received extension frames buffered until the peer is
+/// told to calm down. Frame headers are counted too, so that empty frames
+/// are bounded as well.
+const MAX_RECEIVED_BYTES: usize = 1024 * 1024;
+
+/// Number of received ALTSVC frames kept until the user takes them, the
+/// oldest are dropped first as a client may never look at them.
+const MAX_RECEIVED_ALT_SVCS: usize = 16;
+
+/// Buffers the extension frames received until the user takes them, and the
+/// ones sent until they can be written.
+#[derive(Debug, Default)]
+pub(super) struct Extensions {
+    received: VecDeque<frame::Extension>,
+
+    /// Size of the frames in `received`, as counted by `received_size`.
+    received_bytes: usize,
+
+    /// Task waiting for a frame to be received.
+    recv_task: Option<Waker>,
+
+    pending: VecDeque<frame::Extension>,
+
+    /// ORIGIN frames queued by a server.
+    pending_origins: VecDeque<frame::Origin>,
+
+    /// ALTSVC frames received by a client.
+    received_alt_svcs: VecDeque<frame::AltSvc>,
+
+    /// Task waiting for an ALTSVC frame to be received.
+    alt_svc_task: Option<Waker>,
+
+    /// ALTSVC frames queued by a server.
+    pending_alt_svcs: VecDeque<frame::AltSvc>,
+
+    /// Task driving the connection, woken when a frame is queued.
+    send_task: Option<Waker>,
+
+    is_closed: bool,
+}
+
+impl Extensions {
+    pub(super) fn recv_frame(&mut self, frame: frame::Extension) -> Result<(), RecvError> {
+        let sz = received_size(&frame);
+
+        if self.received_bytes + sz > MAX_RECEIVED_BYTES {
+            log::debug!("connection error ENHANCE_YOUR_CALM -- too many extension frames buffered");
+            return Err(RecvError::Connection(Reason::ENHANCE_YOUR_CALM));
+        }
+
+        self.received_bytes += sz;
+        self.received.push_back(frame);
+
+        if let Some(task) = self.recv_task.take() {
+            task.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Takes the next frame received, or returns `None` once the connection
+    /// is closed and all frames were taken.
+    pub(super) fn poll_recv(&mut self, cx: &Context) -> Poll<Option<frame::Extension>> {
+        if let Some(frame) = self.received.pop_front() {
+            self.received_bytes -= received_size(&frame);
+            return Poll::Ready(Some(frame));
+        }
+
+        if self.is_closed {
+            return Poll::Ready(None);
+        }
+
+        self.recv_task = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    pub(super) fn recv_alt_svc(&mut self, frame: frame::AltSvc) {
+        // An ALTSVC frame on stream 0 must carry an origin, one on a request
+        // stream must not, others are ignored (RFC 7838 §4).
+        if frame.stream_id().is_zero() == frame.origin().is_empty() {
+            log::debug!("ignoring ALTSVC frame; invalid origin={:?}", frame.origin());
+            return;
+        }
+
+        if self.received_alt_svcs.len() >= MAX_RECEIVED_ALT_SVCS {
+            log::trace!("too many ALTSVC frames buffered, dropping the oldest");
+            self.received_alt_svcs.pop_front();
+        }
+
+        self.received_alt_svcs.push_back(frame);
+
+        if let Some(task) = self.alt_svc_task.take() {
+            task.wake();
+        }
+    }
+
+    /// Takes the next ALTSVC frame received, or returns `None` once the
+    /// connection is closed and all frames were taken.
+    pub(super) fn poll_recv_alt_svc(&mut self, cx: &Context) -> Poll<Option<frame::AltSvc>> {
+        if let Some(frame) = self.received_alt_svcs.pop_front() {
+            return Poll::Ready(Some(frame));
+        }
+
+        if self.is_closed {
+            return Poll::Ready(None);
+        }
+
+        self.alt_svc_task = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    pub(super) fn send(
+        &mut self,
+        frame: frame::Extension,
+        max_frame_size: usize,
+    ) -> Result<(), UserError> {
+        if Kind::new(frame.kind()) != Kind::Unknown {
+            return Err(UserError::UnexpectedFrameType);
+        }
+
+        if frame.payload().len() > max_frame_size {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.pending.push_back(frame);
+
+        if let Some(task) = self.send_task.take() {
+            task.wake();
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn send_origin(
+        &mut self,
+        frame: frame::Origin,
+        max_frame_size: usize,
+    ) -> Result<(), UserError> {
+        let max_origin_len = u16::MAX as usize;
+
+        let origin_too_long = frame
+            .origins()
+            .iter()
+            .any(|origin| origin.len() > max_origin_len);
+
+        if frame.payload_len() > max_frame_size || origin_too_long {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.pending_origins.push_back(frame);
+
+        if let Some(task) = self.send_task.take() {
+            task.wake();
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn send_alt_svc(
+        &mut self,
+        frame: frame::AltSvc,
+        max_frame_size: usize,
+    ) -> Result<(), UserError> {
+        if frame.payload_len() > max_frame_size || frame.origin().len() > u16::MAX as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.pending_alt_svcs.push_back(frame);
+
+        if let Some(task) = self.send_task.take() {
+            task.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Writes the frames queued by `send`, `send_origin` and `send_alt_svc`.
+    pub(super) fn poll_send<T, B>(
+        &mut self,
+        cx: &mut Context,
+        dst: &mut Codec<T, B>,
+    ) -> Poll<io::Result<()>>
+    where
+        T: AsyncWrite + Unpin,
+        B: Buf,
+    {
+        while let Some(frame) = self.pending_origins.pop_front() {
+            if !dst.poll_ready(cx)?.is_ready() {
+                self.pending_origins.push_front(frame);
+                return Poll::Pending;
+            }
+
+            if let Err(e) = dst.buffer(frame.into()) {
+                log::debug!("dropping ORIGIN frame; err={:?}", e);
+            }
+        }
+
+        while let Some(frame) = self.pending_alt_svcs.pop_front() {
+            if !dst.poll_ready(cx)?.is_ready() {
+                self.pending_alt_svcs.push_front(frame);
+                return Poll::Pending;
+            }
+
+            if let Err(e) = dst.buffer(frame.into()) {
+                log::debug!("dropping ALTSVC frame; err={:?}", e);
+            }
+        }
+
+        while let Some(frame) = self.pending.pop_front() {
+            if !dst.poll_ready(cx)?.is_ready() {
+                self.pending.push_front(frame);
+                return Poll::Pending;
+            }
+
+            if let Err(e) = dst.buffer(frame.into()) {
+                // The peer lowered its max frame size since the frame was
+                // queued.
+                log::debug!("dropping extension frame; err={:?}", e);
+            }
+        }
+
+        match self.send_task {
+            Some(ref task) if task.will_wake(cx.waker()) => {}
+            _ => self.send_task = Some(cx.waker().clone()),
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    pub(super) fn close(&mut self) {
+        self.is_closed = true;
+
+        if let Some(task) = self.recv_task.take() {
+            task.wake();
+        }
+
+        if let Some(task) = self.alt_svc_task.take() {
+            task.wake();
+        }
+    }
+}
+
+fn received_size(frame: &frame::Extension) -> usize {
+    frame::HEADER_LEN + frame.payload().len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use bytes::Bytes;
+    use futures_util::task::noop_waker_ref;
+
+    fn frame(kind: u8, len: usize) -> frame::Extension {
+        frame::Extension::new(kind, 0, 0.into(), Bytes::from(vec![0; len]))
+    }
+
+    #[test]
+    fn test_recv_until_closed() {
+        let cx = Context::from_waker(noop_waker_ref());
+        let mut extensions = Extensions::default();
+
+        assert!(extensions.poll_recv(&cx).is_pending());
+        extensions.recv_frame(frame(0xb, 1)).unwrap();
+        extensions.close();
+
+        match extensions.poll_recv(&cx) {
+            Poll::Ready(Some(frame)) => assert_eq!(frame.kind(), 0xb),
+            res => panic!("unexpected; res={:?}", res),
+        }
+        assert_eq!(extensions.poll_recv(&cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_recv_limit() {
+        let cx = Context::from_waker(noop_waker_ref());
+        let mut extensions = Extensions::default();
+
+        // Large frames reach the limit sooner.
+        let len = 16_384 - frame::HEADER_LEN;
+        for _ in 0..(MAX_RECEIVED_BYTES / 16_384) {
+            extensions.recv_frame(frame(0xb, len)).unwrap();
+        }
+
+        match extensions.recv_frame(frame(0xb, 0)) {
+            Err(RecvError::Connection(Reason::ENHANCE_YOUR_CALM)) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+
+        // Taking a frame makes room for another.
+        assert!(extensions.poll_recv(&cx).is_ready());
+        extensions.recv_frame(frame(0xb, len)).unwrap();
+
+        // Empty frames are bounded too.
+        let mut extensions = Extensions::default();
+
+        for _ in 0..(MAX_RECEIVED_BYTES / frame::HEADER_LEN) {
+            extensions.recv_frame(frame(0xb, 0)).unwrap();
+        }
+
+        match extensions.recv_frame(frame(0xb, 0)) {
+            Err(RecvError::Connection(Reason::ENHANCE_YOUR_CALM)) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_send_checks() {
+        let mut extensions = Extensions::default();
+
+        match extensions.send(frame(0x1, 0), 16_384) {
+            Err(UserError::UnexpectedFrameType) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+        match extensions.send(frame(0xb, 16_385), 16_384) {
+            Err(UserError::PayloadTooBig) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+        assert!(extensions.send(frame(0xb, 16_384), 16_384).is_ok());
+    }
+
+    #[test]
+    fn test_recv_alt_svc_origin() {
+        let cx = Context::from_waker(noop_waker_ref());
+        let mut extensions = Extensions::default();
+        let origin = Bytes::from_static(b"https://a.example.com");
+        let alt_svc =
+            |id: u32, origin: &Bytes| frame::AltSvc::new(id.into(), origin.clone(), Bytes::new());
+
+        extensions.recv_alt_svc(alt_svc(0, &Bytes::new()));
+        extensions.recv_alt_svc(alt_svc(1, &origin));
+        extensions.recv_alt_svc(alt_svc(0, &origin));
+        extensions.recv_alt_svc(alt_svc(1, &Bytes::new()));
+        extensions.close();
+
+        let mut ids = vec![];
+        while let Poll::Ready(Some(frame)) = extensions.poll_recv_alt_svc(&cx) {
+            ids.push(u32::from(frame.stream_id()));
+        }
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_recv_alt_svc_drops_oldest() {
+        let cx = Context::from_waker(noop_waker_ref());
+        let mut extensions = Extensions::default();
+
+        for id in 1..=(MAX_RECEIVED_ALT_SVCS as u32 * 4) {
+            extensions.recv_alt_svc(frame::AltSvc::new(id.into(), Bytes::new(), Bytes::new()));
+        }
+        extensions.close();
+
+        let mut ids = vec![];
+        while let Poll::Ready(Some(frame)) = extensions.poll_recv_alt_svc(&cx) {
+            ids.push(u32::from(frame.stream_id()));
+        }
+        let first = MAX_RECEIVED_ALT_SVCS as u32 * 3 + 1;
+        let last = MAX_RECEIVED_ALT_SVCS as u32 * 4;
+        assert_eq!(ids, (first..=last).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_send_origin_checks() {
+        let mut extensions = Extensions::default();
+        let origin = |len| frame::Origin::new(vec!["a".repeat(len)]);
+
+        match extensions.send_origin(origin(16_383), 16_384) {
+            Err(UserError::PayloadTooBig) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+        match extensions.send_origin(origin(70_000), 100_000) {
+            Err(UserError::PayloadTooBig) => {}
+            res => panic!("unexpected; res={:?}", res),
+        }
+        assert!(extensions.send_origin(origin(16_382), 16_384).is_ok());
+    }
+}
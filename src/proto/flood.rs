@@ -0,0 +1,173 @@
+use crate::codec::RecvError;
+use crate::frame::{Frame, Kind, Reason};
+use crate::stats::SharedStats;
+
+/// Flood protection configuration, see the `max_pending_control_replies`,
+/// `max_empty_data_frames` and `max_churn_frames` builder options.
+#[derive(Debug, Clone)]
+pub(crate) struct FloodConfig {
+    pub max_pending_replies: usize,
+    pub max_empty_data_frames: usize,
+    pub max_churn_frames: usize,
+}
+
+/// Keeps the peer within budgets for the frames that cost it close to nothing
+/// to send, but have to be processed or answered locally.
+#[derive(Debug)]
+pub(super) struct Flood {
+    config: FloodConfig,
+
+    /// Number of DATA frames received in a row without payload or END_STREAM.
+    num_empty_data_frames: usize,
+
+    /// Number of WINDOW_UPDATE, PRIORITY and PRIORITY_UPDATE frames received.
+    num_churn_frames: u64,
+}
+
+impl Default for FloodConfig {
+    fn default() -> Self {
+        FloodConfig {
+            max_pending_replies: super::DEFAULT_MAX_PENDING_CONTROL_REPLIES,
+            max_empty_data_frames: super::DEFAULT_MAX_EMPTY_DATA_FRAMES,
+            max_churn_frames: super::DEFAULT_MAX_CHURN_FRAMES,
+        }
+    }
+}
+
+impl Flood {
+    pub(super) fn new(config: FloodConfig) -> Self {
+        Flood {
+            config,
+            num_empty_data_frames: 0,
+            num_churn_frames: 0,
+        }
+    }
+
+    /// Checks a received frame against the budgets.
+    ///
+    /// `pending_replies` is the number of PING and SETTINGS acknowledgements
+    /// the codec has not yet been able to write.
+    pub(super) fn recv_frame(
+        &mut self,
+        frame: &Frame,
+        pending_replies: usize,
+        stats: &SharedStats,
+    ) -> Result<(), RecvError> {
+        match *frame {
+            Frame::Ping(ref v) if !v.is_ack() => self.recv_reply_needed(pending_replies),
+            Frame::Settings(ref v) if !v.is_ack() => self.recv_reply_needed(pending_replies),
+            Frame::Data(ref v) => {
+                if v.payload().is_empty() && !v.is_end_stream() {
+                    self.num_empty_data_frames += 1;
+
+                    if self.num_empty_data_frames > self.config.max_empty_data_frames {
+                        return Err(calm("too many empty DATA frames"));
+                    }
+                } else {
+                    self.num_empty_data_frames = 0;
+                }
+
+                Ok(())
+            }
+            Frame::WindowUpdate(_) | Frame::Priority(_) | Frame::PriorityUpdate(_) => {
+                self.num_churn_frames += 1;
+
+                // Every stream and every DATA frame sent legitimately calls for
+                // a few of these.
+                let occasions = 1
+                    + stats.sent.frames(Kind::Data)
+                    + stats.sent.frames(Kind::Headers)
+                    + stats.received.frames(Kind::Headers);
+
+                let budget = occasions.saturating_mul(self.config.max_churn_frames as u64);
+
+                if self.num_churn_frames > budget {
+                    return Err(calm("too many WINDOW_UPDATE and PRIORITY frames"));
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn recv_reply_needed(&self, pending_replies: usize) -> Result<(), RecvError> {
+        if pending_replies >= self.config.max_pending_replies {
+            return Err(calm("too many pending PING and SETTINGS acknowledgements"));
+        }
+
+        Ok(())
+    }
+}
+
+fn calm(msg: &str) -> RecvError {
+    log::debug!("connection error ENHANCE_YOUR_CALM -- {}", msg);
+    RecvError::Connection(Reason::ENHANCE_YOUR_CALM)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::{self, StreamId};
+
+    use bytes::Bytes;
+
+    fn flood() -> Flood {
+        Flood::new(FloodConfig {
+            max_pending_replies: 2,
+            max_empty_data_frames: 2,
+            max_churn_frames: 3,
+        })
+    }
+
+    fn is_calm(res: Result<(), RecvError>) -> bool {
+        matches!(res, Err(RecvError::Connection(Reason::ENHANCE_YOUR_CALM)))
+    }
+
+    #[test]
+    fn test_pending_replies() {
+        let stats = SharedStats::default();
+        let mut flood = flood();
+        let ping: Frame = frame::Ping::new([0; 8]).into();
+        let pong: Frame = frame::Ping::pong([0; 8]).into();
+
+        assert!(flood.recv_frame(&ping, 1, &stats).is_ok());
+        assert!(flood.recv_frame(&pong, 2, &stats).is_ok());
+        assert!(is_calm(flood.recv_frame(&ping, 2, &stats)));
+    }
+
+    #[test]
+    fn test_empty_data_frames() {
+        let stats = SharedStats::default();
+        let mut flood = flood();
+        let empty: Frame = frame::Data::new(1.into(), Bytes::new()).into();
+        let full: Frame = frame::Data::new(1.into(), Bytes::from_static(b"a")).into();
+
+        assert!(flood.recv_frame(&empty, 0, &stats).is_ok());
+        assert!(flood.recv_frame(&empty, 0, &stats).is_ok());
+        assert!(flood.recv_frame(&full, 0, &stats).is_ok());
+        assert!(flood.recv_frame(&empty, 0, &stats).is_ok());
+        assert!(flood.recv_frame(&empty, 0, &stats).is_ok());
+        assert!(is_calm(flood.recv_frame(&empty, 0, &stats)));
+    }
+
+    #[test]
+    fn test_churn_frames() {
+        let stats = SharedStats::default();
+        let mut flood = flood();
+        let update: Frame = frame::WindowUpdate::new(StreamId::zero(), 1).into();
+
+        for _ in 0..3 {
+            assert!(flood.recv_frame(&update, 0, &stats).is_ok());
+        }
+
+        // A DATA frame sent extends the budget
+        stats.sent.inc_frames(Kind::Data);
+
+        for _ in 0..3 {
+            assert!(flood.recv_frame(&update, 0, &stats).is_ok());
+        }
+
+        assert!(is_calm(flood.recv_frame(&update, 0, &stats)));
+    }
+}
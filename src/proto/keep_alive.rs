@@ -0,0 +1,219 @@
+use crate::timer::{Sleep, TimerHandle};
+
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use std::{fmt, io};
+
+/// Keep-alive configuration, see the `keep_alive_*` builder options.
+#[derive(Debug, Clone)]
+pub(crate) struct KeepAliveConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub while_idle: bool,
+    pub timer: TimerHandle,
+}
+
+/// Sends a PING when nothing has been received for a while, and times out the
+/// connection when the PING isn't acknowledged.
+pub(super) struct KeepAlive {
+    config: KeepAliveConfig,
+
+    state: State,
+
+    /// When the last frame was received from the peer.
+    last_read_at: Instant,
+
+    /// The running timer, if any, and when it fires.
+    sleep: Option<(Sleep, Instant)>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Waiting for the interval to pass without hearing from the peer.
+    Idle,
+    /// A PING has been decided on at the given instant. `is_sent` is false
+    /// until the frame has been buffered.
+    PingPending { at: Instant, is_sent: bool },
+    /// The PING was not acknowledged in time.
+    TimedOut,
+}
+
+impl KeepAlive {
+    pub(super) fn new(config: KeepAliveConfig) -> Self {
+        KeepAlive {
+            config,
+            state: State::Idle,
+            last_read_at: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    /// Records that a frame was received from the peer.
+    pub(super) fn recv_frame(&mut self) {
+        self.last_read_at = Instant::now();
+    }
+
+    /// Records the acknowledgement of the keep-alive PING.
+    pub(super) fn recv_pong(&mut self) {
+        if let State::PingPending { .. } = self.state {
+            log::trace!("keep-alive; recv PONG");
+            self.state = State::Idle;
+        }
+    }
+
+    /// Returns true if a PING must be sent now.
+    pub(super) fn is_ping_unsent(&self) -> bool {
+        match self.state {
+            State::PingPending { is_sent, .. } => !is_sent,
+            _ => false,
+        }
+    }
+
+    pub(super) fn ping_sent(&mut self) {
+        if let State::PingPending {
+            ref mut is_sent, ..
+        } = self.state
+        {
+            *is_sent = true;
+        }
+    }
+
+    pub(super) fn is_timed_out(&self) -> bool {
+        self.state == State::TimedOut
+    }
+
+    /// Advances the timers, registering the task to be woken up when there is
+    /// more to do.
+    ///
+    /// `is_idle` is set when the connection has no streams. Returns an error
+    /// once the PING has not been acknowledged within the timeout.
+    pub(super) fn poll(&mut self, cx: &mut Context, is_idle: bool) -> io::Result<()> {
+        loop {
+            let now = Instant::now();
+
+            let deadline = match self.state {
+                State::Idle => {
+                    if is_idle && !self.config.while_idle {
+                        self.sleep = None;
+                        return Ok(());
+                    }
+
+                    let at = self.last_read_at + self.config.interval;
+
+                    if at <= now {
+                        log::trace!("keep-alive; interval elapsed, sending PING");
+                        self.state = State::PingPending {
+                            at: now,
+                            is_sent: false,
+                        };
+                        continue;
+                    }
+
+                    at
+                }
+                State::PingPending { at, .. } => {
+                    let at = at + self.config.timeout;
+
+                    if at <= now {
+                        log::debug!("keep-alive; timed out waiting for PONG");
+                        self.state = State::TimedOut;
+                        self.sleep = None;
+                        return Err(timed_out());
+                    }
+
+                    at
+                }
+                State::TimedOut => return Ok(()),
+            };
+
+            // A timer firing early is fine, the state is checked again when it
+            // does. Only a later timer must be replaced.
+            let is_late = match self.sleep {
+                Some((_, at)) => at > deadline,
+                None => true,
+            };
+
+            if is_late {
+                let sleep = self.config.timer.sleep(deadline - now);
+                self.sleep = Some((sleep, deadline));
+            }
+
+            let (sleep, _) = self.sleep.as_mut().unwrap();
+
+            match sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => self.sleep = None,
+                Poll::Pending => return Ok(()),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for KeepAlive {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("KeepAlive")
+            .field("config", &self.config)
+            .field("state", &self.state)
+            .field("last_read_at", &self.last_read_at)
+            .finish()
+    }
+}
+
+pub(crate) fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "keep-alive timed out")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timer::Timer;
+
+    use futures_util::task::noop_waker_ref;
+
+    struct PendingTimer;
+
+    impl Timer for PendingTimer {
+        fn sleep(&self, _: Duration) -> Sleep {
+            Box::pin(futures_util::future::pending())
+        }
+    }
+
+    fn keep_alive(interval: Duration, timeout: Duration, while_idle: bool) -> KeepAlive {
+        KeepAlive::new(KeepAliveConfig {
+            interval,
+            timeout,
+            while_idle,
+            timer: TimerHandle::new(PendingTimer),
+        })
+    }
+
+    #[test]
+    fn test_ping_after_interval() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut ka = keep_alive(Duration::from_secs(0), Duration::from_secs(60), false);
+
+        // Not while idle
+        ka.poll(&mut cx, true).unwrap();
+        assert!(!ka.is_ping_unsent());
+
+        ka.poll(&mut cx, false).unwrap();
+        assert!(ka.is_ping_unsent());
+
+        ka.ping_sent();
+        ka.poll(&mut cx, false).unwrap();
+        assert!(!ka.is_ping_unsent());
+
+        ka.recv_pong();
+        ka.recv_frame();
+        assert_eq!(ka.state, State::Idle);
+    }
+
+    #[test]
+    fn test_times_out_without_pong() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut ka = keep_alive(Duration::from_secs(0), Duration::from_secs(0), true);
+
+        let err = ka.poll(&mut cx, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(ka.is_timed_out());
+    }
+}
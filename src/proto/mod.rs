@@ -1,6 +1,10 @@
+mod bdp;
 mod connection;
 mod error;
+mod extension;
+mod flood;
 mod go_away;
+mod keep_alive;
 mod peer;
 mod ping_pong;
 mod settings;
@@ -8,6 +12,8 @@ mod streams;
 
 pub(crate) use self::connection::{Config, Connection};
 pub(crate) use self::error::Error;
+pub(crate) use self::flood::FloodConfig;
+pub(crate) use self::keep_alive::KeepAliveConfig;
 pub(crate) use self::peer::{Dyn as DynPeer, Peer};
 pub(crate) use self::ping_pong::UserPings;
 pub(crate) use self::streams::{OpaqueStreamRef, StreamRef, Streams};
@@ -15,6 +21,8 @@ pub(crate) use self::streams::{Open, PollReset, Prioritized};
 
 use crate::codec::Codec;
 
+use self::extension::Extensions;
+use self::flood::Flood;
 use self::go_away::GoAway;
 use self::ping_pong::PingPong;
 use self::settings::Settings;
@@ -32,4 +40,11 @@ pub type WindowSize = u32;
 // Constants
 pub const MAX_WINDOW_SIZE: WindowSize = (1 << 31) - 1;
 pub const DEFAULT_RESET_STREAM_MAX: usize = 10;
+pub const DEFAULT_MAX_SEND_BUFFER_SIZE: usize = 1024 * 400;
 pub const DEFAULT_RESET_STREAM_SECS: u64 = 30;
+pub const DEFAULT_REMOTE_RESET_STREAM_MAX: usize = 200;
+pub const DEFAULT_REMOTE_RESET_STREAM_SECS: u64 = 10;
+pub const DEFAULT_MAX_PENDING_CONTROL_REPLIES: usize = 100;
+pub const DEFAULT_MAX_EMPTY_DATA_FRAMES: usize = 10;
+pub const DEFAULT_MAX_CHURN_FRAMES: usize = 10;
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT_SECS: u64 = 20;
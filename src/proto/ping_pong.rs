@@ -1,6 +1,8 @@
 use crate::codec::Codec;
 use crate::frame::Ping;
-use crate::proto::{self, PingPayload};
+use crate::proto::bdp::Bdp;
+use crate::proto::keep_alive::{KeepAlive, KeepAliveConfig};
+use crate::proto::{self, PingPayload, WindowSize};
 
 use bytes::Buf;
 use futures_io::AsyncWrite;
@@ -9,6 +11,7 @@ use std::io;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 /// Acknowledges ping requests from the remote.
 #[derive(Debug)]
@@ -16,6 +19,12 @@ pub(crate) struct PingPong {
     pending_ping: Option<PendingPing>,
     pending_pong: Option<PingPayload>,
     user_pings: Option<UserPingsRx>,
+    keep_alive: Option<KeepAlive>,
+    bdp: Option<Bdp>,
+    /// When the PINGs waiting for an acknowledgement were sent.
+    sent_at: Vec<(PingPayload, Instant)>,
+    /// The round-trip time of the last acknowledged PING.
+    last_rtt: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -61,11 +70,20 @@ const USER_STATE_CLOSED: usize = 4;
 // ===== impl PingPong =====
 
 impl PingPong {
-    pub(crate) fn new() -> Self {
+    /// `adaptive_window` is the initial and the maximum window size, if the
+    /// window is to be sized from BDP estimates.
+    pub(crate) fn new(
+        keep_alive: Option<KeepAliveConfig>,
+        adaptive_window: Option<(WindowSize, WindowSize)>,
+    ) -> Self {
         PingPong {
             pending_ping: None,
             pending_pong: None,
             user_pings: None,
+            keep_alive: keep_alive.map(KeepAlive::new),
+            bdp: adaptive_window.map(|(init, max)| Bdp::new(init, max)),
+            sent_at: Vec::new(),
+            last_rtt: None,
         }
     }
 
@@ -93,6 +111,55 @@ impl PingPong {
         });
     }
 
+    /// Records that a frame was received, which shows the peer is alive.
+    pub(crate) fn recv_frame(&mut self) {
+        if let Some(ref mut keep_alive) = self.keep_alive {
+            keep_alive.recv_frame();
+        }
+    }
+
+    /// Records received DATA, for the BDP estimate.
+    pub(crate) fn recv_data(&mut self, len: usize) {
+        if let Some(ref mut bdp) = self.bdp {
+            bdp.recv_data(len);
+        }
+    }
+
+    /// Returns the window size estimated from the BDP, if it has changed
+    /// since it was last applied.
+    pub(crate) fn adaptive_window_update(&self) -> Option<WindowSize> {
+        self.bdp.as_ref().and_then(Bdp::window_update)
+    }
+
+    pub(crate) fn adaptive_window_applied(&mut self) {
+        if let Some(ref mut bdp) = self.bdp {
+            bdp.window_applied();
+        }
+    }
+
+    /// Drives the keep-alive timers, if enabled.
+    ///
+    /// Returns an error if the peer did not acknowledge a keep-alive PING in
+    /// time.
+    pub(crate) fn poll_keep_alive(&mut self, cx: &mut Context, is_idle: bool) -> io::Result<()> {
+        match self.keep_alive {
+            Some(ref mut keep_alive) => keep_alive.poll(cx, is_idle),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn is_keep_alive_timed_out(&self) -> bool {
+        self.keep_alive
+            .as_ref()
+            .map(KeepAlive::is_timed_out)
+            .unwrap_or(false)
+    }
+
+    /// Returns the round-trip time of the last acknowledged PING, if any.
+    pub(crate) fn last_rtt(&self) -> Option<Duration> {
+        self.last_rtt
+    }
+
     /// Process a ping
     pub(crate) fn recv_ping(&mut self, ping: Ping) -> ReceivedPing {
         // The caller should always check that `send_pongs` returns ready before
@@ -100,6 +167,25 @@ impl PingPong {
         assert!(self.pending_pong.is_none());
 
         if ping.is_ack() {
+            if let Some(pos) = self.sent_at.iter().position(|(p, _)| p == ping.payload()) {
+                let (_, at) = self.sent_at.swap_remove(pos);
+                self.last_rtt = Some(at.elapsed());
+            }
+
+            if let Some(ref mut keep_alive) = self.keep_alive {
+                if ping.payload() == &Ping::KEEP_ALIVE {
+                    keep_alive.recv_pong();
+                    return ReceivedPing::Unknown;
+                }
+            }
+
+            if let Some(ref mut bdp) = self.bdp {
+                if ping.payload() == &Ping::BDP {
+                    bdp.recv_pong();
+                    return ReceivedPing::Unknown;
+                }
+            }
+
             if let Some(pending) = self.pending_ping.take() {
                 if &pending.payload == ping.payload() {
                     assert_eq!(
@@ -167,6 +253,32 @@ impl PingPong {
         T: AsyncWrite + Unpin,
         B: Buf,
     {
+        if let Some(ref mut keep_alive) = self.keep_alive {
+            if keep_alive.is_ping_unsent() {
+                if !dst.poll_ready(cx)?.is_ready() {
+                    return Poll::Pending;
+                }
+
+                dst.buffer(Ping::new(Ping::KEEP_ALIVE).into())
+                    .expect("invalid ping frame");
+                ping_sent(&mut self.sent_at, Ping::KEEP_ALIVE);
+                keep_alive.ping_sent();
+            }
+        }
+
+        if let Some(ref mut bdp) = self.bdp {
+            if bdp.is_ping_unsent() {
+                if !dst.poll_ready(cx)?.is_ready() {
+                    return Poll::Pending;
+                }
+
+                dst.buffer(Ping::new(Ping::BDP).into())
+                    .expect("invalid ping frame");
+                ping_sent(&mut self.sent_at, Ping::BDP);
+                bdp.ping_sent();
+            }
+        }
+
         if let Some(ref mut ping) = self.pending_ping {
             if !ping.sent {
                 if !dst.poll_ready(cx)?.is_ready() {
@@ -175,6 +287,7 @@ impl PingPong {
 
                 dst.buffer(Ping::new(ping.payload).into())
                     .expect("invalid ping frame");
+                ping_sent(&mut self.sent_at, ping.payload);
                 ping.sent = true;
             }
         } else if let Some(ref users) = self.user_pings {
@@ -185,6 +298,7 @@ impl PingPong {
 
                 dst.buffer(Ping::new(Ping::USER).into())
                     .expect("invalid ping frame");
+                ping_sent(&mut self.sent_at, Ping::USER);
                 users
                     .0
                     .state
@@ -198,6 +312,13 @@ impl PingPong {
     }
 }
 
+/// Records when a PING was sent, replacing any earlier one with the same
+/// payload.
+fn ping_sent(sent_at: &mut Vec<(PingPayload, Instant)>, payload: PingPayload) {
+    sent_at.retain(|(p, _)| *p != payload);
+    sent_at.push((payload, Instant::now()));
+}
+
 impl ReceivedPing {
     pub(crate) fn is_shutdown(&self) -> bool {
         match *self {
@@ -8,6 +8,12 @@ use std::task::{Context, Poll};
 pub(crate) struct Settings {
     /// Our local SETTINGS sync state with the remote.
     local: Local,
+    /// Whether the local SETTINGS to send or waiting for an ACK were sent by
+    /// the connection itself rather than the user, such as those of the
+    /// adaptive window.
+    is_local_internal: bool,
+    /// User SETTINGS to send once the internal SETTINGS in flight are ACKed.
+    queued: Option<frame::Settings>,
     /// Received SETTINGS frame pending processing. The ACK must be written to
     /// the socket first then the settings applied **before** receiving any
     /// further frames.
@@ -31,6 +37,8 @@ impl Settings {
             // We assume the initial local SETTINGS were flushed during
             // the handshake process.
             local: Local::WaitingAck(local),
+            is_local_internal: false,
+            queued: None,
             remote: None,
         }
     }
@@ -60,8 +68,12 @@ impl Settings {
                         codec.set_max_recv_header_list_size(max as usize);
                     }
 
+                    if let Some(val) = local.header_table_size() {
+                        codec.set_recv_header_table_size(val as usize);
+                    }
+
                     streams.apply_local_settings(local)?;
-                    self.local = Local::Synced;
+                    self.local_acked();
                     Ok(())
                 }
                 Local::ToSend(..) | Local::Synced => {
@@ -80,16 +92,102 @@ impl Settings {
         }
     }
 
-    pub(crate) fn send_settings(&mut self, frame: frame::Settings) -> Result<(), UserError> {
+    /// Queues SETTINGS from the user.
+    ///
+    /// Internal SETTINGS never make this fail: if they are not sent yet, they
+    /// are merged into the user's, and otherwise the user's are sent once
+    /// they are ACKed.
+    pub(crate) fn send_settings(&mut self, mut frame: frame::Settings) -> Result<(), UserError> {
         assert!(!frame.is_ack());
         match &self.local {
-            Local::ToSend(..) | Local::WaitingAck(..) => Err(UserError::SendSettingsWhilePending),
+            Local::ToSend(internal) if self.is_local_internal => {
+                // Internal SETTINGS only ever change the initial window
+                // size, the user's value wins.
+                if frame.initial_window_size().is_none() {
+                    frame.set_initial_window_size(internal.initial_window_size());
+                }
+
+                log::trace!("merged internal into local settings: {:?}", frame);
+                self.local = Local::ToSend(frame);
+                self.is_local_internal = false;
+            }
+            Local::WaitingAck(..) if self.is_local_internal && self.queued.is_none() => {
+                log::trace!("queue local settings after internal: {:?}", frame);
+                self.queued = Some(frame);
+            }
+            Local::ToSend(..) | Local::WaitingAck(..) => {
+                return Err(UserError::SendSettingsWhilePending);
+            }
             Local::Synced => {
                 log::trace!("queue to send local settings: {:?}", frame);
                 self.local = Local::ToSend(frame);
-                Ok(())
             }
         }
+
+        Ok(())
+    }
+
+    /// Moves on from the local SETTINGS which were just ACKed, to the user
+    /// SETTINGS queued behind them if any.
+    fn local_acked(&mut self) {
+        self.is_local_internal = false;
+        self.local = match self.queued.take() {
+            Some(queued) => Local::ToSend(queued),
+            None => Local::Synced,
+        };
+    }
+
+    /// Queues SETTINGS from the connection itself, returning `false` if other
+    /// SETTINGS are pending so that this must be retried later.
+    pub(crate) fn send_internal_settings(&mut self, frame: frame::Settings) -> bool {
+        assert!(!frame.is_ack());
+        match &self.local {
+            Local::ToSend(..) | Local::WaitingAck(..) => false,
+            Local::Synced => {
+                log::trace!("queue to send internal settings: {:?}", frame);
+                self.local = Local::ToSend(frame);
+                self.is_local_internal = true;
+                true
+            }
+        }
+    }
+
+    /// Applies remote settings that need no ACK, such as those of the
+    /// `HTTP2-Settings` header of a request upgraded to HTTP/2.0.
+    pub(crate) fn recv_implicit<T, B, C, P>(
+        &mut self,
+        settings: &frame::Settings,
+        codec: &mut Codec<T, B>,
+        streams: &mut Streams<C, P>,
+    ) -> Result<(), RecvError>
+    where
+        B: Buf,
+        C: Buf,
+        P: Peer,
+    {
+        log::trace!("applying implicit settings: {:?}", settings);
+        Self::apply_remote(settings, codec, streams)
+    }
+
+    fn apply_remote<T, B, C, P>(
+        settings: &frame::Settings,
+        dst: &mut Codec<T, B>,
+        streams: &mut Streams<C, P>,
+    ) -> Result<(), RecvError>
+    where
+        B: Buf,
+        C: Buf,
+        P: Peer,
+    {
+        if let Some(val) = settings.header_table_size() {
+            dst.set_send_header_table_size(val as usize);
+        }
+
+        if let Some(val) = settings.max_frame_size() {
+            dst.set_max_send_frame_size(val as usize);
+        }
+
+        streams.apply_remote_settings(settings)
     }
 
     pub(crate) fn poll_send<T, B, C, P>(
@@ -117,15 +215,7 @@ impl Settings {
 
             log::trace!("ACK sent; applying settings");
 
-            if let Some(val) = settings.header_table_size() {
-                dst.set_send_header_table_size(val as usize);
-            }
-
-            if let Some(val) = settings.max_frame_size() {
-                dst.set_max_send_frame_size(val as usize);
-            }
-
-            streams.apply_remote_settings(settings)?;
+            Self::apply_remote(settings, dst, streams)?;
         }
 
         self.remote = None;
@@ -149,3 +239,89 @@ impl Settings {
         Poll::Ready(Ok(()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn window(size: u32) -> frame::Settings {
+        let mut settings = frame::Settings::default();
+        settings.set_initial_window_size(Some(size));
+        settings
+    }
+
+    fn user() -> frame::Settings {
+        let mut settings = frame::Settings::default();
+        settings.set_max_concurrent_streams(Some(10));
+        settings
+    }
+
+    fn synced() -> Settings {
+        let mut settings = Settings::new(frame::Settings::default());
+        settings.local_acked();
+        settings
+    }
+
+    #[test]
+    fn test_user_merged_into_unsent_internal() {
+        let mut settings = synced();
+        assert!(settings.send_internal_settings(window(100_000)));
+        settings.send_settings(user()).unwrap();
+
+        match settings.local {
+            Local::ToSend(ref frame) => {
+                assert_eq!(frame.initial_window_size(), Some(100_000));
+                assert_eq!(frame.max_concurrent_streams(), Some(10));
+            }
+            ref local => panic!("unexpected state; local={:?}", local),
+        }
+
+        // The merged SETTINGS are the user's.
+        assert!(settings.send_settings(user()).is_err());
+    }
+
+    #[test]
+    fn test_user_window_wins_over_internal() {
+        let mut settings = synced();
+        assert!(settings.send_internal_settings(window(100_000)));
+        settings.send_settings(window(70_000)).unwrap();
+
+        match settings.local {
+            Local::ToSend(ref frame) => assert_eq!(frame.initial_window_size(), Some(70_000)),
+            ref local => panic!("unexpected state; local={:?}", local),
+        }
+    }
+
+    #[test]
+    fn test_user_queued_behind_internal_in_flight() {
+        let mut settings = synced();
+        assert!(settings.send_internal_settings(window(100_000)));
+        settings.local = Local::WaitingAck(window(100_000));
+
+        settings.send_settings(user()).unwrap();
+        assert!(settings.send_settings(user()).is_err());
+        assert!(!settings.send_internal_settings(window(200_000)));
+
+        settings.local_acked();
+        match settings.local {
+            Local::ToSend(ref frame) => {
+                assert_eq!(frame.initial_window_size(), None);
+                assert_eq!(frame.max_concurrent_streams(), Some(10));
+            }
+            ref local => panic!("unexpected state; local={:?}", local),
+        }
+    }
+
+    #[test]
+    fn test_internal_waits_for_user() {
+        let mut settings = synced();
+        settings.send_settings(user()).unwrap();
+        assert!(!settings.send_internal_settings(window(100_000)));
+
+        settings.local = Local::WaitingAck(user());
+        assert!(!settings.send_internal_settings(window(100_000)));
+
+        settings.local_acked();
+        assert!(settings.send_internal_settings(window(100_000)));
+    }
+}
@@ -1,5 +1,7 @@
 use super::*;
 
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use std::usize;
 
 #[derive(Debug)]
@@ -25,6 +27,23 @@ pub(super) struct Counts {
 
     /// Current number of pending locally reset streams
     num_reset_streams: usize,
+
+    /// Number of streams closed over the life of the connection
+    num_closed_streams: u64,
+
+    /// Number of the closed streams that were reset
+    num_closed_reset_streams: u64,
+
+    /// Maximum number of unanswered streams the remote may reset within a
+    /// duration
+    remote_reset_limit: Option<(usize, Duration)>,
+
+    /// When each unanswered stream was reset by the remote, oldest first,
+    /// for as long as it falls within the limit's duration
+    remote_reset_times: VecDeque<Instant>,
+
+    /// Number of streams reset by the remote over the life of the connection
+    num_remote_reset_streams: u64,
 }
 
 impl Counts {
@@ -38,6 +57,11 @@ impl Counts {
             num_recv_streams: 0,
             max_reset_streams: config.local_reset_max,
             num_reset_streams: 0,
+            num_closed_streams: 0,
+            num_closed_reset_streams: 0,
+            remote_reset_limit: config.remote_reset_limit,
+            remote_reset_times: VecDeque::new(),
+            num_remote_reset_streams: 0,
         }
     }
 
@@ -50,6 +74,53 @@ impl Counts {
         self.num_send_streams != 0 || self.num_recv_streams != 0
     }
 
+    /// Returns the number of streams counted as active
+    pub fn num_active_streams(&self) -> usize {
+        self.num_send_streams + self.num_recv_streams
+    }
+
+    pub fn num_closed_streams(&self) -> u64 {
+        self.num_closed_streams
+    }
+
+    pub fn num_closed_reset_streams(&self) -> u64 {
+        self.num_closed_reset_streams
+    }
+
+    pub fn num_remote_reset_streams(&self) -> u64 {
+        self.num_remote_reset_streams
+    }
+
+    /// Records a stream reset by the remote.
+    pub fn inc_num_remote_reset_streams(&mut self) {
+        self.num_remote_reset_streams += 1;
+    }
+
+    /// Records a stream reset by the remote before it was answered, at `now`.
+    ///
+    /// Returns false if the remote has reset more such streams than allowed
+    /// within the configured duration, counted over a sliding window.
+    pub fn inc_num_unanswered_remote_reset_streams(&mut self, now: Instant) -> bool {
+        let (max, duration) = match self.remote_reset_limit {
+            Some(limit) => limit,
+            None => return true,
+        };
+
+        while let Some(&oldest) = self.remote_reset_times.front() {
+            if now - oldest < duration {
+                break;
+            }
+            self.remote_reset_times.pop_front();
+        }
+
+        if self.remote_reset_times.len() >= max {
+            return false;
+        }
+
+        self.remote_reset_times.push_back(now);
+        true
+    }
+
     /// Returns true if the receive stream concurrency can be incremented
     pub fn can_inc_num_recv_streams(&self) -> bool {
         self.max_recv_streams > self.num_recv_streams
@@ -170,6 +241,11 @@ impl Counts {
     fn dec_num_streams(&mut self, stream: &mut store::Ptr) {
         assert!(stream.is_counted);
 
+        self.num_closed_streams += 1;
+        if stream.state.is_reset() {
+            self.num_closed_reset_streams += 1;
+        }
+
         if self.peer.is_local_init(stream.id) {
             assert!(self.num_send_streams > 0);
             self.num_send_streams -= 1;
@@ -196,3 +272,87 @@ impl Drop for Counts {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::DEFAULT_INITIAL_WINDOW_SIZE;
+
+    fn server_counts(remote_reset_limit: Option<(usize, Duration)>) -> Counts {
+        let config = Config {
+            local_init_window_sz: DEFAULT_INITIAL_WINDOW_SIZE,
+            initial_max_send_streams: 0,
+            local_next_stream_id: 2.into(),
+            local_push_enabled: false,
+            local_extended_connect_protocol_enabled: false,
+            local_reset_duration: Duration::from_secs(DEFAULT_RESET_STREAM_SECS),
+            local_reset_max: DEFAULT_RESET_STREAM_MAX,
+            local_max_buffer_size: DEFAULT_MAX_SEND_BUFFER_SIZE,
+            local_auto_release_capacity: false,
+            local_max_recv_buffer_size: None,
+            remote_init_window_sz: DEFAULT_INITIAL_WINDOW_SIZE,
+            remote_max_initiated: None,
+            remote_reset_limit,
+        };
+
+        Counts::new(peer::Dyn::Server, &config)
+    }
+
+    #[test]
+    fn test_remote_reset_limit() {
+        let now = Instant::now();
+        let mut counts = server_counts(Some((2, Duration::from_secs(60))));
+
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        assert!(!counts.inc_num_unanswered_remote_reset_streams(now));
+
+        // Resets leave the window once they are older than the duration
+        let mut counts = server_counts(Some((1, Duration::from_secs(0))));
+
+        for _ in 0..10 {
+            assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        }
+
+        // No limit
+        let mut counts = server_counts(None);
+
+        for _ in 0..1_000 {
+            assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        }
+    }
+
+    #[test]
+    fn test_remote_reset_window_slides() {
+        let start = Instant::now();
+        let mut counts = server_counts(Some((2, Duration::from_secs(10))));
+
+        assert!(counts.inc_num_unanswered_remote_reset_streams(start));
+
+        let now = start + Duration::from_secs(6);
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        assert!(!counts.inc_num_unanswered_remote_reset_streams(now));
+
+        // Only the first reset has left the window.
+        let now = start + Duration::from_secs(12);
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        assert!(!counts.inc_num_unanswered_remote_reset_streams(now));
+
+        // Refused resets aren't recorded, so the window only holds the one
+        // at 12s once the one at 6s is out.
+        let now = start + Duration::from_secs(16);
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+        assert!(!counts.inc_num_unanswered_remote_reset_streams(now));
+    }
+
+    #[test]
+    fn test_remote_reset_stats() {
+        let now = Instant::now();
+        let mut counts = server_counts(Some((1, Duration::from_secs(60))));
+
+        counts.inc_num_remote_reset_streams();
+        counts.inc_num_remote_reset_streams();
+        assert_eq!(counts.num_remote_reset_streams(), 2);
+        assert!(counts.inc_num_unanswered_remote_reset_streams(now));
+    }
+}
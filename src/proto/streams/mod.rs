@@ -2,6 +2,7 @@ mod buffer;
 mod counts;
 mod flow_control;
 mod prioritize;
+mod priority;
 mod recv;
 mod send;
 mod state;
@@ -18,6 +19,7 @@ use self::buffer::Buffer;
 use self::counts::Counts;
 use self::flow_control::FlowControl;
 use self::prioritize::Prioritize;
+use self::priority::PriorityTree;
 use self::recv::Recv;
 use self::send::Send;
 use self::state::State;
@@ -47,15 +49,30 @@ pub struct Config {
     /// If the local peer is willing to receive push promises
     pub local_push_enabled: bool,
 
+    /// If the local peer is willing to receive extended CONNECT requests
+    pub local_extended_connect_protocol_enabled: bool,
+
     /// How long a locally reset stream should ignore frames
     pub local_reset_duration: Duration,
 
     /// Maximum number of locally reset streams to keep at a time
     pub local_reset_max: usize,
 
+    /// Maximum number of bytes buffered for sending on a stream
+    pub local_max_buffer_size: usize,
+
+    /// If capacity is released when received data is taken by default
+    pub local_auto_release_capacity: bool,
+
+    /// Maximum number of unread bytes buffered on a received stream
+    pub local_max_recv_buffer_size: Option<usize>,
+
     /// Initial window size of remote initiated streams
     pub remote_init_window_sz: WindowSize,
 
     /// Maximum number of remote initiated streams
     pub remote_max_initiated: Option<usize>,
+
+    /// Maximum number of streams the remote may reset within the duration
+    pub remote_reset_limit: Option<(usize, Duration)>,
 }
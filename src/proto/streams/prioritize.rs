@@ -7,10 +7,20 @@ use crate::codec::UserError;
 use crate::codec::UserError::*;
 
 use bytes::buf::ext::{BufExt, Take};
+use fnv::FnvHashMap;
+use std::collections::BTreeMap;
 use std::io;
 use std::task::{Context, Poll, Waker};
 use std::{cmp, fmt, mem};
 
+/// Upper bound on the number of PRIORITY_UPDATE frames kept for streams that
+/// have not been opened yet.
+const MAX_PENDING_PRIORITY_UPDATES: usize = 100;
+
+/// Orders the streams ready to send DATA by urgency: (urgency, incremental,
+/// stream ID for non-incremental streams or arrival order otherwise).
+pub(super) type ReadyRank = (u8, bool, u64);
+
 /// # Warning
 ///
 /// Queued streams are ordered by stream ID, as we need to ensure that
@@ -51,6 +61,27 @@ pub(super) struct Prioritize {
 
     /// What `DATA` frame is currently being sent in the codec.
     in_flight_data_frame: InFlightData,
+
+    /// The stream dependency tree, deciding which stream sends DATA next.
+    tree: PriorityTree,
+
+    /// Set once any stream has been given an RFC 9218 priority. From then
+    /// on, urgency takes precedence over the dependency tree.
+    is_urgency_scheduled: bool,
+
+    /// Priorities received in PRIORITY_UPDATE frames for streams that are
+    /// still idle.
+    pending_priority_updates: FnvHashMap<StreamId, frame::PriorityParams>,
+
+    /// Streams with DATA at the head of their queue, once priorities are in
+    /// use. They are also marked ready in the dependency tree.
+    ready: BTreeMap<ReadyRank, StreamId>,
+
+    /// Arrival order of the next stream added to `ready`.
+    next_ready_seq: u64,
+
+    /// Maximum number of bytes buffered for sending on a stream.
+    max_buffer_size: usize,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -93,9 +124,24 @@ impl Prioritize {
             flow,
             last_opened_id: StreamId::ZERO,
             in_flight_data_frame: InFlightData::Nothing,
+            tree: PriorityTree::new(),
+            is_urgency_scheduled: false,
+            pending_priority_updates: FnvHashMap::default(),
+            ready: BTreeMap::new(),
+            next_ready_seq: 0,
+            max_buffer_size: config.local_max_buffer_size,
         }
     }
 
+    pub fn max_buffer_size(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    /// Returns the connection level window as advertised by the peer
+    pub fn window_size(&self) -> WindowSize {
+        self.flow.window_size()
+    }
+
     /// Queue a frame to be sent to the remote
     pub fn queue_frame<B>(
         &mut self,
@@ -127,6 +173,72 @@ impl Prioritize {
         self.pending_open.push(stream);
     }
 
+    /// Update the priority of a stream in the dependency tree
+    pub fn reprioritize(
+        &mut self,
+        id: StreamId,
+        dependency: &frame::StreamDependency,
+        is_idle: bool,
+    ) {
+        self.tree.reprioritize(id, dependency, is_idle);
+    }
+
+    /// Set the urgency and incremental parameters of a stream
+    pub fn set_priority_params(&mut self, stream: &mut store::Ptr, params: frame::PriorityParams) {
+        log::trace!(
+            "set_priority_params; stream={:?}; params={:?}",
+            stream.id,
+            params
+        );
+
+        stream.priority_params = params;
+        self.is_urgency_scheduled = true;
+
+        // Move the stream to its new rank.
+        if let Some(rank) = stream.ready_rank.take() {
+            self.ready.remove(&rank);
+            self.insert_ready(stream);
+        }
+    }
+
+    pub fn buffer_priority_update(&mut self, id: StreamId, params: frame::PriorityParams) {
+        if self.pending_priority_updates.len() >= MAX_PENDING_PRIORITY_UPDATES
+            && !self.pending_priority_updates.contains_key(&id)
+        {
+            log::trace!(
+                "buffer_priority_update; too many pending, ignoring; stream={:?}",
+                id
+            );
+            return;
+        }
+
+        self.pending_priority_updates.insert(id, params);
+    }
+
+    pub fn take_priority_update(&mut self, id: StreamId) -> Option<frame::PriorityParams> {
+        let params = self.pending_priority_updates.remove(&id);
+
+        // Streams are opened in order, lower IDs will never be opened now.
+        self.pending_priority_updates
+            .retain(|pending, _| *pending > id);
+
+        params
+    }
+
+    /// Returns the IDs of the streams in the dependency tree, if it is due
+    /// for removing stale entries.
+    pub fn priority_sweep_candidates(&self) -> Option<Vec<StreamId>> {
+        if self.tree.needs_sweep() {
+            Some(self.tree.stream_ids())
+        } else {
+            None
+        }
+    }
+
+    pub fn sweep_priorities(&mut self, stale: &[StreamId], num_idle: usize) {
+        self.tree.sweep(stale, num_idle);
+    }
+
     /// Send a data frame
     pub fn send_data<B>(
         &mut self,
@@ -419,7 +531,7 @@ impl Prioritize {
             log::trace!("  assigning; stream={:?}, capacity={}", stream.id, assign,);
 
             // Assign the capacity to the stream
-            stream.assign_capacity(assign);
+            stream.assign_capacity(assign, self.max_buffer_size);
 
             // Claim the capacity from the connection
             self.flow.claim_capacity(assign);
@@ -625,6 +737,14 @@ impl Prioritize {
             let is_pending_reset = stream.is_pending_reset_expiration();
             counts.transition_after(stream, is_pending_reset);
         }
+
+        while let Some(id) = self.ready.values().next().cloned() {
+            let mut stream = store.find_mut(&id).expect("ready stream");
+            self.remove_ready(&mut stream);
+
+            let is_pending_reset = stream.is_pending_reset_expiration();
+            counts.transition_after(stream, is_pending_reset);
+        }
     }
 
     pub fn clear_pending_open(&mut self, store: &mut Store, counts: &mut Counts) {
@@ -647,7 +767,7 @@ impl Prioritize {
         log::trace!("pop_frame");
 
         loop {
-            match self.pending_send.pop(store) {
+            match self.pop_pending_send(buffer, store) {
                 Some(mut stream) => {
                     log::trace!(
                         "pop_frame; stream={:?}; stream.state={:?}",
@@ -725,12 +845,8 @@ impl Prioritize {
 
                             // Update the flow control
                             log::trace!(" -- updating stream flow --");
-                            stream.send_flow.send_data(len);
-
-                            // Decrement the stream's buffered data counter
-                            debug_assert!(stream.buffered_send_data >= len);
-                            stream.buffered_send_data -= len;
-                            stream.requested_send_capacity -= len;
+                            stream.send_data(len, self.max_buffer_size);
+                            stream.sent_data_len += u64::from(len);
 
                             // Assign the capacity back to the connection that
                             // was just consumed from the stream in the previous
@@ -740,6 +856,10 @@ impl Prioritize {
                             log::trace!(" -- updating connection flow --");
                             self.flow.send_data(len);
 
+                            if self.tree.is_prioritized() {
+                                self.tree.charge(stream.id, len as usize);
+                            }
+
                             // Wrap the frame's data payload to ensure that the
                             // correct amount of data gets written.
 
@@ -823,6 +943,78 @@ impl Prioritize {
         }
     }
 
+    /// Pops the next stream to send a frame for.
+    ///
+    /// Until a stream is given a priority, streams are served in the order
+    /// they were queued. After that, streams with anything but a DATA frame
+    /// at the head of their queue still go first and in order, as HEADERS must
+    /// open streams in increasing ID order.
+    ///
+    /// Streams with DATA at the head are moved to the ready set as they are
+    /// popped from the queue. Which of them sends next is then decided by
+    /// urgency once any stream has an RFC 9218 priority, or by the dependency
+    /// tree otherwise. Of the streams with the same urgency, non-incremental
+    /// ones are served one at a time in stream ID order, before the
+    /// incremental ones take turns in the order they were queued.
+    fn pop_pending_send<'a, B>(
+        &mut self,
+        buffer: &Buffer<Frame<B>>,
+        store: &'a mut Store,
+    ) -> Option<store::Ptr<'a>> {
+        if !self.tree.is_prioritized() && !self.is_urgency_scheduled {
+            return self.pending_send.pop(store);
+        }
+
+        while let Some(mut stream) = self.pending_send.pop(store) {
+            match stream.pending_send.peek_front(buffer) {
+                Some(Frame::Data(_)) => self.insert_ready(&mut stream),
+                _ => {
+                    let key = stream.key();
+                    return Some(store.resolve(key));
+                }
+            }
+        }
+
+        let next = if self.is_urgency_scheduled {
+            self.ready.values().next().cloned()
+        } else {
+            self.tree.pick()
+        }?;
+
+        let mut stream = store.find_mut(&next).expect("ready stream");
+        self.remove_ready(&mut stream);
+
+        Some(stream)
+    }
+
+    /// Adds a stream popped from `pending_send` to the ready set.
+    ///
+    /// The stream stays marked as pending send, so that it isn't queued again
+    /// nor released while it waits there.
+    fn insert_ready(&mut self, stream: &mut store::Ptr) {
+        let params = stream.priority_params;
+        let order = if params.is_incremental() {
+            self.next_ready_seq += 1;
+            self.next_ready_seq
+        } else {
+            u64::from(u32::from(stream.id))
+        };
+        let rank = (params.urgency(), params.is_incremental(), order);
+
+        self.ready.insert(rank, stream.id);
+        self.tree.set_ready(stream.id, true);
+        stream.ready_rank = Some(rank);
+        stream.is_pending_send = true;
+    }
+
+    fn remove_ready(&mut self, stream: &mut store::Ptr) {
+        if let Some(rank) = stream.ready_rank.take() {
+            self.ready.remove(&rank);
+            self.tree.set_ready(stream.id, false);
+            stream.is_pending_send = false;
+        }
+    }
+
     fn schedule_pending_open(&mut self, store: &mut Store, counts: &mut Counts) {
         log::trace!("schedule_pending_open");
         // check for any pending open streams
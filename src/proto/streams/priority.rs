@@ -0,0 +1,540 @@
+use super::*;
+
+use crate::frame::StreamDependency;
+
+use fnv::FnvHashMap;
+use std::{cmp, mem};
+
+/// The weight given to streams without explicit priority (RFC 7540 §5.3.5).
+const DEFAULT_WEIGHT: u16 = 16;
+
+/// Upper bound on the number of nodes kept for streams that are still idle.
+///
+/// Peers may send PRIORITY frames for streams they have not opened yet, e.g.
+/// to build "placeholder" groups. Without a bound, this could grow forever.
+const MAX_IDLE_NODES: usize = 100;
+
+/// The tree is never swept for stale nodes before it reaches this size.
+const MIN_SWEEP_LEN: usize = 32;
+
+/// Virtual time charged per octet sent by a stream with a weight of 1.
+const STRIDE: u64 = 256;
+
+/// The stream dependency tree described in RFC 7540 §5.3.
+///
+/// It decides which stream gets to send the next DATA frame. A stream is only
+/// served when none of its ancestors has data ready to send. Siblings share the
+/// bandwidth in proportion to their weight using stride scheduling: every time
+/// a stream sends data, it and its ancestors are charged virtual time inversely
+/// proportional to their weight, and the sibling that consumed the least
+/// virtual time goes next.
+///
+/// Streams without a node implicitly depend on the root with the default
+/// weight, nodes are created lazily when a stream is first given a priority or
+/// has data to schedule.
+///
+/// The tree tracks which streams are ready to send, and how many are in each
+/// subtree, so that picking the next stream only walks down the branches
+/// that have one.
+#[derive(Debug)]
+pub(super) struct PriorityTree {
+    nodes: FnvHashMap<StreamId, Node>,
+
+    /// Number of nodes for streams that have not been opened yet.
+    num_idle: usize,
+
+    /// When the number of nodes reaches this, stale nodes are removed and
+    /// `num_idle` is recounted. It is set to twice the number of nodes left
+    /// by each sweep, so that sweeping is amortized over the nodes added.
+    sweep_at: usize,
+
+    /// Set once any stream has been given an explicit priority. Until then,
+    /// all streams have the same priority and the tree isn't consulted.
+    is_prioritized: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: StreamId,
+
+    /// The effective weight, in the range [1, 256].
+    weight: u16,
+
+    children: Vec<StreamId>,
+
+    /// True if the stream has DATA ready to send.
+    is_ready: bool,
+
+    /// Number of ready streams in the subtree rooted at this node.
+    num_ready: usize,
+
+    /// Virtual time consumed by the subtree rooted at this node.
+    pass: u64,
+
+    /// Virtual time of the last child served. Children joining this node
+    /// start at this point, so that they don't starve their siblings.
+    vtime: u64,
+}
+
+impl PriorityTree {
+    pub fn new() -> Self {
+        let mut nodes = FnvHashMap::default();
+        nodes.insert(StreamId::ZERO, Node::new(StreamId::ZERO, DEFAULT_WEIGHT, 0));
+
+        PriorityTree {
+            nodes,
+            num_idle: 0,
+            sweep_at: MIN_SWEEP_LEN,
+            is_prioritized: false,
+        }
+    }
+
+    pub fn is_prioritized(&self) -> bool {
+        self.is_prioritized
+    }
+
+    /// Sets the priority of a stream, from a PRIORITY frame, the priority
+    /// fields of a HEADERS frame, or the local user.
+    ///
+    /// `is_idle` is set when the stream has not been opened yet.
+    pub fn reprioritize(&mut self, id: StreamId, dependency: &StreamDependency, is_idle: bool) {
+        if id.is_zero() || dependency.dependency_id() == id {
+            // Rejected at the frame or API layer.
+            return;
+        }
+
+        if !self.nodes.contains_key(&id) {
+            if is_idle {
+                if self.num_idle >= MAX_IDLE_NODES {
+                    // Idle nodes whose stream has since been opened are only
+                    // recounted by the next sweep.
+                    log::trace!(
+                        "reprioritize; too many idle nodes, ignoring; stream={:?}",
+                        id
+                    );
+                    return;
+                }
+                self.num_idle += 1;
+            }
+
+            self.insert(id);
+        }
+
+        self.is_prioritized = true;
+
+        let mut parent = dependency.dependency_id();
+        let mut weight = u16::from(dependency.weight()) + 1;
+        let mut is_exclusive = dependency.is_exclusive();
+
+        if !self.nodes.contains_key(&parent) {
+            // > If a stream is made dependent on a stream that is not
+            // > currently in the tree [...] the dependent stream is given a
+            // > default priority.
+            parent = StreamId::ZERO;
+            weight = DEFAULT_WEIGHT;
+            is_exclusive = false;
+        }
+
+        log::trace!(
+            "reprioritize; stream={:?}; parent={:?}; weight={}; exclusive={}",
+            id,
+            parent,
+            weight,
+            is_exclusive
+        );
+
+        // > If a stream is made dependent on one of its own dependencies, the
+        // > formerly dependent stream is first moved to be dependent on the
+        // > reprioritized stream's previous parent. The moved dependency
+        // > retains its weight.
+        if self.is_ancestor(id, parent) {
+            let former = self.nodes[&id].parent;
+            self.detach(parent);
+            self.attach(parent, former);
+        }
+
+        self.detach(id);
+
+        let adopted = if is_exclusive {
+            let children = mem::take(&mut self.node_mut(parent).children);
+            let num_ready = children
+                .iter()
+                .map(|child| self.nodes[child].num_ready)
+                .sum();
+            self.dec_num_ready(parent, num_ready);
+            children
+        } else {
+            Vec::new()
+        };
+
+        self.node_mut(id).weight = weight;
+        self.attach(id, parent);
+
+        for child in adopted {
+            self.attach(child, id);
+        }
+    }
+
+    /// Sets whether a stream has DATA ready to send.
+    pub fn set_ready(&mut self, id: StreamId, is_ready: bool) {
+        if !self.nodes.contains_key(&id) {
+            if !is_ready {
+                return;
+            }
+
+            self.insert(id);
+        }
+
+        let node = self.node_mut(id);
+
+        if node.is_ready == is_ready {
+            return;
+        }
+
+        node.is_ready = is_ready;
+
+        if is_ready {
+            self.inc_num_ready(id, 1);
+        } else {
+            self.dec_num_ready(id, 1);
+        }
+    }
+
+    /// Picks which of the ready streams should send next.
+    pub fn pick(&self) -> Option<StreamId> {
+        let mut id = StreamId::ZERO;
+
+        loop {
+            let node = &self.nodes[&id];
+
+            if node.num_ready == 0 {
+                return None;
+            }
+
+            if node.is_ready {
+                return Some(id);
+            }
+
+            id = node
+                .children
+                .iter()
+                .filter(|child| self.nodes[child].num_ready > 0)
+                .min_by_key(|child| (self.nodes[child].pass, **child))
+                .cloned()?;
+        }
+    }
+
+    /// Charges `len` octets of DATA sent on `id` to the stream and its
+    /// ancestors.
+    pub fn charge(&mut self, id: StreamId, len: usize) {
+        // Zero-length frames still take a turn.
+        let len = cmp::max(len, 1) as u64;
+        let mut id = id;
+
+        while !id.is_zero() {
+            let (parent, pass) = match self.nodes.get_mut(&id) {
+                Some(node) => {
+                    let pass = node.pass;
+                    node.pass += len * STRIDE / u64::from(node.weight);
+                    (node.parent, pass)
+                }
+                None => return,
+            };
+
+            let parent_node = self.node_mut(parent);
+            parent_node.vtime = cmp::max(parent_node.vtime, pass);
+
+            id = parent;
+        }
+    }
+
+    /// Returns true when the tree has grown enough that stale nodes should
+    /// be looked for.
+    pub fn needs_sweep(&self) -> bool {
+        self.nodes.len() >= self.sweep_at
+    }
+
+    /// Returns the IDs of all streams in the tree.
+    pub fn stream_ids(&self) -> Vec<StreamId> {
+        self.nodes
+            .keys()
+            .filter(|id| !id.is_zero())
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the nodes of streams which are no longer in use, and records
+    /// how many of the remaining nodes belong to idle streams.
+    pub fn sweep(&mut self, stale: &[StreamId], num_idle: usize) {
+        log::trace!("sweep; nodes={}; stale={}", self.nodes.len(), stale.len());
+
+        for id in stale {
+            self.remove(*id);
+        }
+
+        self.num_idle = num_idle;
+        self.sweep_at = cmp::max(self.nodes.len() * 2, MIN_SWEEP_LEN);
+    }
+
+    /// Removes a stream from the tree, its children are given to its parent
+    /// with their weights scaled to share the removed stream's weight.
+    fn remove(&mut self, id: StreamId) {
+        let node = match self.nodes.remove(&id) {
+            Some(node) => node,
+            None => return,
+        };
+
+        self.node_mut(node.parent)
+            .children
+            .retain(|child| *child != id);
+        self.dec_num_ready(node.parent, node.num_ready);
+
+        let total: u32 = node
+            .children
+            .iter()
+            .map(|child| u32::from(self.nodes[child].weight))
+            .sum();
+
+        for child in node.children {
+            let weight = u32::from(self.nodes[&child].weight) * u32::from(node.weight) / total;
+            self.node_mut(child).weight = cmp::max(weight, 1) as u16;
+            self.attach(child, node.parent);
+        }
+    }
+
+    fn insert(&mut self, id: StreamId) {
+        self.nodes
+            .insert(id, Node::new(StreamId::ZERO, DEFAULT_WEIGHT, 0));
+        self.attach(id, StreamId::ZERO);
+    }
+
+    /// Returns true if `ancestor` is an ancestor of `id`.
+    fn is_ancestor(&self, ancestor: StreamId, id: StreamId) -> bool {
+        let mut id = id;
+
+        while !id.is_zero() {
+            id = self.nodes[&id].parent;
+
+            if id == ancestor {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn detach(&mut self, id: StreamId) {
+        let node = &self.nodes[&id];
+        let (parent, num_ready) = (node.parent, node.num_ready);
+        self.node_mut(parent).children.retain(|child| *child != id);
+        self.dec_num_ready(parent, num_ready);
+    }
+
+    fn attach(&mut self, id: StreamId, parent: StreamId) {
+        let parent_node = self.node_mut(parent);
+        parent_node.children.push(id);
+        let vtime = parent_node.vtime;
+
+        let node = self.node_mut(id);
+        node.parent = parent;
+        node.pass = vtime;
+        let num_ready = node.num_ready;
+
+        self.inc_num_ready(parent, num_ready);
+    }
+
+    /// Adds `n` ready streams to the subtrees of `id` and its ancestors.
+    fn inc_num_ready(&mut self, id: StreamId, n: usize) {
+        let mut id = id;
+
+        loop {
+            let node = self.node_mut(id);
+            node.num_ready += n;
+
+            if id.is_zero() {
+                return;
+            }
+
+            id = node.parent;
+        }
+    }
+
+    fn dec_num_ready(&mut self, id: StreamId, n: usize) {
+        let mut id = id;
+
+        loop {
+            let node = self.node_mut(id);
+            node.num_ready -= n;
+
+            if id.is_zero() {
+                return;
+            }
+
+            id = node.parent;
+        }
+    }
+
+    fn node_mut(&mut self, id: StreamId) -> &mut Node {
+        self.nodes.get_mut(&id).expect("priority node")
+    }
+}
+
+impl Node {
+    fn new(parent: StreamId, weight: u16, pass: u64) -> Self {
+        Node {
+            parent,
+            weight,
+            children: Vec::new(),
+            is_ready: false,
+            num_ready: 0,
+            pass,
+            vtime: pass,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dep(id: u32, weight: u8, is_exclusive: bool) -> StreamDependency {
+        StreamDependency::new(StreamId::from(id), weight, is_exclusive)
+    }
+
+    fn parent(tree: &PriorityTree, id: u32) -> StreamId {
+        tree.nodes[&StreamId::from(id)].parent
+    }
+
+    #[test]
+    fn test_exclusive_adopts_siblings() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(1.into(), &dep(0, 15, false), false);
+        tree.reprioritize(3.into(), &dep(0, 15, false), false);
+        tree.reprioritize(5.into(), &dep(0, 15, true), false);
+
+        assert_eq!(parent(&tree, 5), StreamId::ZERO);
+        assert_eq!(parent(&tree, 1), StreamId::from(5));
+        assert_eq!(parent(&tree, 3), StreamId::from(5));
+    }
+
+    #[test]
+    fn test_depend_on_descendant() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(1.into(), &dep(0, 15, false), false);
+        tree.reprioritize(3.into(), &dep(1, 15, false), false);
+        tree.reprioritize(1.into(), &dep(3, 15, false), false);
+
+        assert_eq!(parent(&tree, 3), StreamId::ZERO);
+        assert_eq!(parent(&tree, 1), StreamId::from(3));
+    }
+
+    #[test]
+    fn test_parent_blocks_children() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(3.into(), &dep(1, 15, false), false);
+        tree.reprioritize(1.into(), &dep(0, 15, false), false);
+        tree.reprioritize(3.into(), &dep(1, 15, false), false);
+
+        tree.set_ready(1.into(), true);
+        tree.set_ready(3.into(), true);
+        assert_eq!(tree.pick(), Some(StreamId::from(1)));
+
+        tree.set_ready(1.into(), false);
+        assert_eq!(tree.pick(), Some(StreamId::from(3)));
+
+        tree.set_ready(3.into(), false);
+        assert_eq!(tree.pick(), None);
+    }
+
+    #[test]
+    fn test_ready_counts_follow_moves() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(1.into(), &dep(0, 15, false), false);
+        tree.reprioritize(3.into(), &dep(1, 15, false), false);
+        tree.set_ready(3.into(), true);
+        tree.set_ready(5.into(), true);
+        assert_eq!(tree.nodes[&StreamId::ZERO].num_ready, 2);
+
+        // 7 adopts 1 and 5, and 1 then moves under its own child 3.
+        tree.reprioritize(7.into(), &dep(0, 15, true), false);
+        tree.reprioritize(1.into(), &dep(3, 15, false), false);
+        assert_eq!(tree.nodes[&StreamId::ZERO].num_ready, 2);
+        assert_eq!(tree.nodes[&StreamId::from(7)].num_ready, 2);
+        assert_eq!(tree.nodes[&StreamId::from(1)].num_ready, 0);
+
+        tree.sweep(&[StreamId::from(7)], 0);
+        assert_eq!(tree.nodes[&StreamId::ZERO].num_ready, 2);
+
+        tree.set_ready(3.into(), false);
+        assert_eq!(tree.pick(), Some(StreamId::from(5)));
+        tree.set_ready(5.into(), false);
+        assert_eq!(tree.pick(), None);
+    }
+
+    #[test]
+    fn test_weighted_share() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(1.into(), &dep(0, 255, false), false);
+        tree.reprioritize(3.into(), &dep(0, 63, false), false);
+
+        tree.set_ready(1.into(), true);
+        tree.set_ready(3.into(), true);
+
+        let mut sent = FnvHashMap::default();
+        for _ in 0..500 {
+            let id = tree.pick().unwrap();
+            tree.charge(id, 100);
+            *sent.entry(id).or_insert(0) += 1;
+        }
+
+        assert_eq!(sent[&StreamId::from(1)], 400);
+        assert_eq!(sent[&StreamId::from(3)], 100);
+    }
+
+    #[test]
+    fn test_remove_redistributes_weight() {
+        let mut tree = PriorityTree::new();
+        tree.reprioritize(1.into(), &dep(0, 31, false), false);
+        tree.reprioritize(3.into(), &dep(1, 15, false), false);
+        tree.reprioritize(5.into(), &dep(1, 47, false), false);
+
+        tree.sweep(&[StreamId::from(1)], 0);
+
+        assert_eq!(parent(&tree, 3), StreamId::ZERO);
+        assert_eq!(tree.nodes[&StreamId::from(3)].weight, 8);
+        assert_eq!(tree.nodes[&StreamId::from(5)].weight, 24);
+    }
+
+    #[test]
+    fn test_idle_nodes_are_bounded() {
+        let mut tree = PriorityTree::new();
+
+        for i in 0..(MAX_IDLE_NODES as u32 + 10) {
+            tree.reprioritize((2 * i + 1).into(), &dep(0, 15, false), true);
+        }
+
+        assert_eq!(tree.stream_ids().len(), MAX_IDLE_NODES);
+    }
+
+    #[test]
+    fn test_idle_nodes_dont_force_sweeps() {
+        let mut tree = PriorityTree::new();
+
+        for i in 0..(MAX_IDLE_NODES as u32) {
+            tree.reprioritize((2 * i + 1).into(), &dep(0, 15, false), true);
+        }
+        assert!(tree.needs_sweep());
+
+        // Nothing is stale, so the next sweep waits for the tree to grow.
+        tree.sweep(&[], MAX_IDLE_NODES);
+        assert!(!tree.needs_sweep());
+
+        tree.reprioritize(1001.into(), &dep(0, 15, false), true);
+        assert!(!tree.needs_sweep());
+
+        for i in 0..=(MAX_IDLE_NODES as u32) {
+            tree.set_ready((2 * i + 2001).into(), true);
+        }
+        assert!(tree.needs_sweep());
+    }
+}
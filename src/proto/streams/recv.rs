@@ -6,6 +6,7 @@ use std::task::Context;
 
 use http::{HeaderMap, Request, Response};
 
+use std::cmp;
 use std::io;
 use std::task::{Poll, Waker};
 use std::time::{Duration, Instant};
@@ -56,11 +57,22 @@ pub(super) struct Recv {
 
     /// If push promises are allowed to be recevied.
     is_push_enabled: bool,
+
+    /// If extended CONNECT requests are allowed to be received.
+    is_extended_connect_protocol_enabled: bool,
+
+    /// If capacity is released when received data is taken, unless
+    /// overridden by the stream.
+    auto_release_capacity: bool,
+
+    /// Maximum number of unread bytes buffered on a stream.
+    max_buffer_size: Option<usize>,
 }
 
 #[derive(Debug)]
 pub(super) enum Event {
     Headers(peer::PollMessage),
+    Informational(Response<()>),
     Data(Bytes),
     Trailers(HeaderMap),
 }
@@ -109,6 +121,9 @@ impl Recv {
             buffer: Buffer::new(),
             refused: None,
             is_push_enabled: config.local_push_enabled,
+            is_extended_connect_protocol_enabled: config.local_extended_connect_protocol_enabled,
+            auto_release_capacity: config.local_auto_release_capacity,
+            max_buffer_size: config.local_max_recv_buffer_size,
         }
     }
 
@@ -117,6 +132,11 @@ impl Recv {
         self.init_window_sz
     }
 
+    /// Returns the connection level window as known by the peer
+    pub fn window_size(&self) -> WindowSize {
+        self.flow.window_size()
+    }
+
     /// Returns the ID of the last processed stream
     pub fn last_processed_id(&self) -> StreamId {
         self.last_processed_id
@@ -160,6 +180,12 @@ impl Recv {
         stream: &mut store::Ptr,
         counts: &mut Counts,
     ) -> Result<(), RecvHeaderBlockError<Option<frame::Headers>>> {
+        if frame.is_informational() && !counts.peer().is_server() {
+            return self
+                .recv_informational(frame, stream, counts)
+                .map_err(Into::into);
+        }
+
         log::trace!("opening stream; init_window={}", self.init_window_sz);
         let is_initial = stream.state.recv_open(frame.is_end_stream())?;
 
@@ -226,6 +252,19 @@ impl Recv {
 
         let stream_id = frame.stream_id();
         let (pseudo, fields) = frame.into_parts();
+
+        if pseudo.protocol.is_some()
+            && counts.peer().is_server()
+            && !self.is_extended_connect_protocol_enabled
+        {
+            proto_err!(stream: "recv_headers: :protocol without extended CONNECT enabled; stream={:?}", stream.id);
+            return Err(RecvError::Stream {
+                id: stream.id,
+                reason: Reason::PROTOCOL_ERROR,
+            }
+            .into());
+        }
+
         let message = counts
             .peer()
             .convert_poll_message(pseudo, fields, stream_id)?;
@@ -245,6 +284,61 @@ impl Recv {
         Ok(())
     }
 
+    /// Queues an informational (1xx) response. The stream keeps waiting for
+    /// the final response.
+    fn recv_informational(
+        &mut self,
+        frame: frame::Headers,
+        stream: &mut store::Ptr,
+        counts: &mut Counts,
+    ) -> Result<(), RecvError> {
+        use super::peer::PollMessage::*;
+
+        if frame.is_end_stream() {
+            proto_err!(stream: "recv_informational: END_STREAM on informational response; stream={:?}", stream.id);
+            return Err(RecvError::Stream {
+                id: stream.id,
+                reason: Reason::PROTOCOL_ERROR,
+            });
+        }
+
+        if frame.is_over_size() {
+            // Nothing depends on it, so the client may as well discard it.
+            log::debug!(
+                "recv_informational: frame is over size, ignoring; stream={:?}",
+                stream.id
+            );
+            return Ok(());
+        }
+
+        let stream_id = frame.stream_id();
+        let (pseudo, fields) = frame.into_parts();
+
+        if pseudo.status == Some(::http::StatusCode::SWITCHING_PROTOCOLS) {
+            // HTTP/2 has no upgrade mechanism
+            proto_err!(stream: "recv_informational: 101 response; stream={:?}", stream.id);
+            return Err(RecvError::Stream {
+                id: stream.id,
+                reason: Reason::PROTOCOL_ERROR,
+            });
+        }
+
+        let response = match counts
+            .peer()
+            .convert_poll_message(pseudo, fields, stream_id)?
+        {
+            Client(response) => response,
+            Server(_) => unreachable!("informational response received by server"),
+        };
+
+        stream
+            .pending_recv
+            .push_back(&mut self.buffer, Event::Informational(response));
+        stream.notify_recv();
+
+        Ok(())
+    }
+
     /// Called by the server to get the request
     ///
     /// TODO: Should this fn return `Result`?
@@ -299,15 +393,42 @@ impl Recv {
 
         // If the buffer is not empty, then the first frame must be a HEADERS
         // frame or the user violated the contract.
+        loop {
+            match stream.pending_recv.pop_front(&mut self.buffer) {
+                Some(Event::Headers(Client(response))) => return Poll::Ready(Ok(response)),
+                // Informational responses the user did not ask for
+                Some(Event::Informational(_)) => continue,
+                Some(_) => panic!("poll_response called after response returned"),
+                None => {
+                    stream.state.ensure_recv_open()?;
+
+                    stream.recv_task = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    /// Called by the client to get the informational responses received
+    /// before the final response
+    pub fn poll_informational(
+        &mut self,
+        cx: &Context,
+        stream: &mut store::Ptr,
+    ) -> Poll<Option<Result<Response<()>, proto::Error>>> {
         match stream.pending_recv.pop_front(&mut self.buffer) {
-            Some(Event::Headers(Client(response))) => Poll::Ready(Ok(response)),
-            Some(_) => panic!("poll_response called after response returned"),
-            None => {
-                stream.state.ensure_recv_open()?;
+            Some(Event::Informational(response)) => Poll::Ready(Some(Ok(response))),
+            Some(event) => {
+                // The final response, or what follows it.
+                stream.pending_recv.push_front(&mut self.buffer, event);
 
-                stream.recv_task = Some(cx.waker().clone());
-                Poll::Pending
+                // Just in case `poll_response` was waiting, see `poll_data`.
+                stream.notify_recv();
+
+                Poll::Ready(None)
             }
+            None if !stream.state.is_recv_headers() => Poll::Ready(None),
+            None => self.schedule_recv(cx, stream),
         }
     }
 
@@ -393,6 +514,65 @@ impl Recv {
         Ok(())
     }
 
+    /// Sets whether the capacity of the stream is released when received data
+    /// is taken.
+    ///
+    /// When enabling it, the capacity of data already taken is released.
+    pub fn set_auto_release_capacity(
+        &mut self,
+        enabled: bool,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
+        stream.auto_release_capacity = Some(enabled);
+
+        if !enabled {
+            return;
+        }
+
+        let taken = stream
+            .in_flight_recv_data
+            .saturating_sub(stream.buffered_recv_data);
+
+        if taken > 0 {
+            self.release_capacity(taken, stream, task)
+                .expect("taken data is in flight");
+        }
+    }
+
+    pub fn is_auto_release_capacity(&self, stream: &Stream) -> bool {
+        stream
+            .auto_release_capacity
+            .unwrap_or(self.auto_release_capacity)
+    }
+
+    /// Returns the size of the WINDOW_UPDATE to send for the stream, if any.
+    ///
+    /// The window isn't grown past the maximum buffer size, less the data
+    /// still waiting to be taken.
+    fn window_update_size(&self, stream: &Stream) -> Option<WindowSize> {
+        let incr = stream.recv_flow.unclaimed_capacity()?;
+
+        let max = match self.max_buffer_size {
+            Some(max) => max,
+            None => return Some(incr),
+        };
+
+        let used = stream.buffered_recv_data as usize + stream.recv_flow.window_size() as usize;
+        let allowed = max.saturating_sub(used);
+
+        if allowed == 0 {
+            log::trace!(
+                "window update held back; stream={:?}; buffered={}",
+                stream.id,
+                stream.buffered_recv_data
+            );
+            return None;
+        }
+
+        Some(cmp::min(incr as usize, allowed) as WindowSize)
+    }
+
     /// Release any unclaimed capacity for a closed stream.
     pub fn release_closed_capacity(&mut self, stream: &mut store::Ptr, task: &mut Option<Waker>) {
         debug_assert_eq!(stream.ref_count, 0);
@@ -455,11 +635,23 @@ impl Recv {
         }
     }
 
+    /// Like `set_target_connection_window`, but never shrinks the window.
+    pub fn grow_target_connection_window(&mut self, target: WindowSize, task: &mut Option<Waker>) {
+        let current = (self.flow.available() + self.in_flight_data).checked_size();
+        if target > current {
+            self.set_target_connection_window(target, task);
+        }
+    }
+
     pub(crate) fn apply_local_settings(
         &mut self,
         settings: &frame::Settings,
         store: &mut Store,
     ) -> Result<(), RecvError> {
+        if let Some(val) = settings.is_extended_connect_protocol_enabled() {
+            self.is_extended_connect_protocol_enabled = val;
+        }
+
         let target = if let Some(val) = settings.initial_window_size() {
             val
         } else {
@@ -619,6 +811,8 @@ impl Recv {
 
         // Track the data as in-flight
         stream.in_flight_recv_data += sz;
+        stream.buffered_recv_data += sz;
+        stream.recv_data_len += u64::from(sz);
 
         let event = Event::Data(frame.into_payload());
 
@@ -778,6 +972,8 @@ impl Recv {
         while let Some(_) = stream.pending_recv.pop_front(&mut self.buffer) {
             // drop it
         }
+
+        stream.buffered_recv_data = 0;
     }
 
     /// Get the max ID of streams we can receive.
@@ -995,7 +1191,7 @@ impl Recv {
                 }
 
                 // TODO: de-dup
-                if let Some(incr) = stream.recv_flow.unclaimed_capacity() {
+                if let Some(incr) = self.window_update_size(stream) {
                     // Create the WINDOW_UPDATE frame
                     let frame = frame::WindowUpdate::new(stream.id, incr);
 
@@ -1020,11 +1216,15 @@ impl Recv {
     pub fn poll_data(
         &mut self,
         cx: &Context,
-        stream: &mut Stream,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
     ) -> Poll<Option<Result<Bytes, proto::Error>>> {
         // TODO: Return error when the stream is reset
         match stream.pending_recv.pop_front(&mut self.buffer) {
-            Some(Event::Data(payload)) => Poll::Ready(Some(Ok(payload))),
+            Some(Event::Data(payload)) => {
+                self.take_data(payload.len() as WindowSize, stream, task);
+                Poll::Ready(Some(Ok(payload)))
+            }
             Some(event) => {
                 // Frame is trailer
                 stream.pending_recv.push_front(&mut self.buffer, event);
@@ -1046,6 +1246,29 @@ impl Recv {
         }
     }
 
+    /// Accounts for data taken by the user.
+    fn take_data(&mut self, sz: WindowSize, stream: &mut store::Ptr, task: &mut Option<Waker>) {
+        stream.buffered_recv_data -= sz;
+
+        if self.is_auto_release_capacity(stream) {
+            // The user may have released some of it already.
+            let sz = cmp::min(sz, stream.in_flight_recv_data);
+
+            if sz > 0 {
+                self.release_capacity(sz, stream, task)
+                    .expect("taken data is in flight");
+            }
+        } else if self.max_buffer_size.is_some() && stream.recv_flow.unclaimed_capacity().is_some()
+        {
+            // A window update may have been held back by the buffered data.
+            self.pending_window_updates.push(stream);
+
+            if let Some(task) = task.take() {
+                task.wake();
+            }
+        }
+    }
+
     pub fn poll_trailers(
         &mut self,
         cx: &Context,
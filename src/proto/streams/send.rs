@@ -34,6 +34,12 @@ pub(super) struct Send {
     prioritize: Prioritize,
 
     is_push_enabled: bool,
+
+    /// If the remote peer has enabled the extended CONNECT protocol
+    is_extended_connect_protocol_enabled: bool,
+
+    /// If the remote peer has said it does not use RFC 7540 priorities
+    is_rfc7540_priorities_disabled: bool,
 }
 
 /// A value to detect which public API has called `poll_reset`.
@@ -52,6 +58,8 @@ impl Send {
             next_stream_id: Ok(config.local_next_stream_id),
             prioritize: Prioritize::new(config),
             is_push_enabled: true,
+            is_extended_connect_protocol_enabled: false,
+            is_rfc7540_priorities_disabled: false,
         }
     }
 
@@ -60,6 +68,83 @@ impl Send {
         self.init_window_sz
     }
 
+    /// Update the priority of a stream in the dependency tree
+    pub fn reprioritize(
+        &mut self,
+        id: StreamId,
+        dependency: &frame::StreamDependency,
+        is_idle: bool,
+    ) {
+        self.prioritize.reprioritize(id, dependency, is_idle);
+    }
+
+    /// Reprioritize a stream locally and let the remote know
+    pub fn send_priority<B>(
+        &mut self,
+        frame: frame::Priority,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
+        log::trace!("send_priority; frame={:?}", frame);
+
+        self.prioritize
+            .reprioritize(stream.id, frame.dependency(), false);
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+    }
+
+    /// Set the RFC 9218 priority of a stream
+    pub fn set_priority_params(&mut self, stream: &mut store::Ptr, params: frame::PriorityParams) {
+        self.prioritize.set_priority_params(stream, params);
+    }
+
+    /// Reprioritize a stream locally, and ask the remote to do the same for
+    /// the response
+    pub fn send_priority_update<B>(
+        &mut self,
+        params: frame::PriorityParams,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
+        let frame = frame::PriorityUpdate::new(stream.id, params);
+
+        log::trace!("send_priority_update; frame={:?}", frame);
+
+        self.prioritize.set_priority_params(stream, params);
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+    }
+
+    /// Keep the priority of a stream which isn't open yet
+    pub fn buffer_priority_update(&mut self, id: StreamId, params: frame::PriorityParams) {
+        self.prioritize.buffer_priority_update(id, params);
+    }
+
+    /// Take the priority received for a stream before it was opened
+    pub fn take_priority_update(&mut self, id: StreamId) -> Option<frame::PriorityParams> {
+        self.prioritize.take_priority_update(id)
+    }
+
+    pub fn priority_sweep_candidates(&self) -> Option<Vec<StreamId>> {
+        self.prioritize.priority_sweep_candidates()
+    }
+
+    pub fn sweep_priorities(&mut self, stale: &[StreamId], num_idle: usize) {
+        self.prioritize.sweep_priorities(stale, num_idle);
+    }
+
+    /// Returns true if the remote peer does not use RFC 7540 priorities
+    pub fn is_rfc7540_priorities_disabled(&self) -> bool {
+        self.is_rfc7540_priorities_disabled
+    }
+
+    /// Returns true if the remote peer accepts extended CONNECT requests
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.is_extended_connect_protocol_enabled
+    }
+
     pub fn open(&mut self) -> Result<StreamId, UserError> {
         let stream_id = self.ensure_next_stream_id()?;
         self.next_stream_id = stream_id.next_id();
@@ -104,6 +189,10 @@ impl Send {
             self.init_window_sz
         );
 
+        if !self.is_push_enabled {
+            return Err(UserError::PeerDisabledServerPush);
+        }
+
         Self::check_headers(frame.fields())?;
 
         // Queue the frame for sending
@@ -157,6 +246,73 @@ impl Send {
         Ok(())
     }
 
+    /// Opens a stream whose request headers were sent before the connection
+    /// was upgraded to HTTP/2.0, so there is no frame to queue.
+    pub fn open_upgraded(
+        &mut self,
+        stream: &mut store::Ptr,
+        counts: &mut Counts,
+    ) -> Result<(), UserError> {
+        if !counts.can_inc_num_send_streams() {
+            return Err(UserError::Rejected);
+        }
+
+        stream.state.send_open(true)?;
+        counts.inc_num_send_streams(stream);
+
+        Ok(())
+    }
+
+    /// Queues an informational response, which leaves the stream state as is.
+    pub fn send_informational_headers<B>(
+        &mut self,
+        frame: frame::Headers,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        log::trace!("send_informational_headers; frame={:?}", frame);
+
+        Self::check_headers(frame.fields())?;
+
+        if frame.has_too_big_field() {
+            return Err(UserError::HeaderTooBig);
+        }
+
+        if !stream.state.is_send_headers() {
+            return Err(UserError::UnexpectedFrameType);
+        }
+
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+
+        Ok(())
+    }
+
+    pub fn send_alt_svc<B>(
+        &mut self,
+        frame: frame::AltSvc,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        log::trace!("send_alt_svc; frame={:?}", frame);
+
+        if stream.state.is_send_closed() {
+            return Err(UserError::UnexpectedFrameType);
+        }
+
+        // The maximum frame size of the peer is never below the default one.
+        if frame.payload_len() > frame::DEFAULT_MAX_FRAME_SIZE as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+
+        Ok(())
+    }
+
     /// Send an explicit RST_STREAM frame
     pub fn send_reset<B>(
         &mut self,
@@ -327,16 +483,37 @@ impl Send {
         Poll::Ready(Some(Ok(self.capacity(stream))))
     }
 
+    /// Returns the connection level window as advertised by the peer
+    pub fn window_size(&self) -> WindowSize {
+        self.prioritize.window_size()
+    }
+
     /// Current available stream send capacity
     pub fn capacity(&self, stream: &mut store::Ptr) -> WindowSize {
-        let available = stream.send_flow.available().as_size();
-        let buffered = stream.buffered_send_data;
+        stream.capacity(self.prioritize.max_buffer_size())
+    }
 
-        if available <= buffered {
-            0
-        } else {
-            available - buffered
+    /// Returns `Ready` once more data may be buffered on the stream, which is
+    /// when less than the maximum send buffer size is buffered.
+    pub fn poll_send_buffer_ready(
+        &self,
+        cx: &Context,
+        stream: &mut store::Ptr,
+    ) -> Poll<Result<(), UserError>> {
+        if !stream.state.is_send_streaming() {
+            if stream.state.is_closed() {
+                return Poll::Ready(Err(UserError::InactiveStreamId));
+            } else {
+                return Poll::Ready(Err(UserError::UnexpectedFrameType));
+            }
         }
+
+        if stream.is_send_buffer_ready(self.prioritize.max_buffer_size()) {
+            return Poll::Ready(Ok(()));
+        }
+
+        stream.wait_send(cx);
+        Poll::Pending
     }
 
     pub fn poll_reset(
@@ -503,6 +680,14 @@ impl Send {
             self.is_push_enabled = val
         }
 
+        if let Some(val) = settings.is_extended_connect_protocol_enabled() {
+            self.is_extended_connect_protocol_enabled = val
+        }
+
+        if let Some(val) = settings.is_no_rfc7540_priorities() {
+            self.is_rfc7540_priorities_disabled = val
+        }
+
         Ok(())
     }
 
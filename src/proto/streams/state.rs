@@ -76,6 +76,8 @@ enum Cause {
     EndStream,
     Proto(Reason),
     LocallyReset(Reason),
+    /// Above the last stream identifier of a GOAWAY frame received.
+    GoAway(Reason),
     Io,
 
     /// This indicates to the connection that a reset frame must be sent out
@@ -278,6 +280,8 @@ impl State {
                 log::trace!("recv_err; err={:?}", err);
                 self.inner = Closed(match *err {
                     Proto(reason) => Cause::LocallyReset(reason),
+                    GoAway(reason) => Cause::GoAway(reason),
+                    Refused => Cause::Proto(Reason::REFUSED_STREAM),
                     Io(..) => Cause::Io,
                 });
             }
@@ -338,6 +342,7 @@ impl State {
     pub fn is_local_reset(&self) -> bool {
         match self.inner {
             Closed(Cause::LocallyReset(_)) => true,
+            Closed(Cause::GoAway(_)) => true,
             Closed(Cause::Scheduled(..)) => true,
             _ => false,
         }
@@ -376,6 +381,19 @@ impl State {
         }
     }
 
+    /// Returns true if the local end has yet to send its headers, which is
+    /// when informational responses may be sent.
+    pub fn is_send_headers(&self) -> bool {
+        match self.inner {
+            Open {
+                local: AwaitingHeaders,
+                ..
+            } => true,
+            HalfClosedRemote(AwaitingHeaders) => true,
+            _ => false,
+        }
+    }
+
     pub fn is_recv_streaming(&self) -> bool {
         match self.inner {
             Open {
@@ -417,9 +435,11 @@ impl State {
     pub fn ensure_recv_open(&self) -> Result<bool, proto::Error> {
         // TODO: Is this correct?
         match self.inner {
+            Closed(Cause::Proto(Reason::REFUSED_STREAM)) => Err(proto::Error::Refused),
             Closed(Cause::Proto(reason))
             | Closed(Cause::LocallyReset(reason))
             | Closed(Cause::Scheduled(reason)) => Err(proto::Error::Proto(reason)),
+            Closed(Cause::GoAway(reason)) => Err(proto::Error::GoAway(reason)),
             Closed(Cause::Io) => Err(proto::Error::Io(io::ErrorKind::BrokenPipe.into())),
             Closed(Cause::EndStream) | HalfClosedRemote(..) | ReservedLocal => Ok(false),
             _ => Ok(true),
@@ -431,6 +451,7 @@ impl State {
         match self.inner {
             Closed(Cause::Proto(reason))
             | Closed(Cause::LocallyReset(reason))
+            | Closed(Cause::GoAway(reason))
             | Closed(Cause::Scheduled(reason)) => Ok(Some(reason)),
             Closed(Cause::Io) => Err(proto::Error::Io(io::ErrorKind::BrokenPipe.into()).into()),
             Open {
@@ -1,3 +1,4 @@
+use super::prioritize::ReadyRank;
 use super::*;
 
 use std::task::{Context, Waker};
@@ -72,6 +73,16 @@ pub(super) struct Stream {
     /// Set to true when a push is pending for this stream
     pub is_pending_push: bool,
 
+    /// The RFC 9218 priority, deciding when DATA is sent on this stream
+    pub priority_params: frame::PriorityParams,
+
+    /// Set while the stream waits in the ready set of `Prioritize`, to the
+    /// rank it was inserted with
+    pub ready_rank: Option<ReadyRank>,
+
+    /// Number of DATA payload bytes sent
+    pub sent_data_len: u64,
+
     // ===== Fields related to receiving =====
     /// Next node in the accept linked list
     pub next_pending_accept: Option<store::Key>,
@@ -84,6 +95,15 @@ pub(super) struct Stream {
 
     pub in_flight_recv_data: WindowSize,
 
+    /// Number of received bytes buffered until taken by the user
+    pub buffered_recv_data: WindowSize,
+
+    /// Overrides whether capacity is released when received data is taken
+    pub auto_release_capacity: Option<bool>,
+
+    /// Number of DATA payload bytes received
+    pub recv_data_len: u64,
+
     /// Next node in the linked list of streams waiting to send window updates.
     pub next_window_update: Option<store::Key>,
 
@@ -169,12 +189,18 @@ impl Stream {
             is_pending_open: false,
             next_open: None,
             is_pending_push: false,
+            priority_params: frame::PriorityParams::default(),
+            ready_rank: None,
+            sent_data_len: 0,
 
             // ===== Fields related to receiving =====
             next_pending_accept: None,
             is_pending_accept: false,
             recv_flow,
             in_flight_recv_data: 0,
+            buffered_recv_data: 0,
+            auto_release_capacity: None,
+            recv_data_len: 0,
             next_window_update: None,
             is_pending_window_update: false,
             reset_at: None,
@@ -260,9 +286,24 @@ impl Stream {
         self.ref_count == 0 && !self.state.is_closed()
     }
 
-    pub fn assign_capacity(&mut self, capacity: WindowSize) {
+    /// Returns the send capacity that can be used, which is the assigned
+    /// capacity not yet used by buffered data, capped by the maximum send
+    /// buffer size.
+    pub fn capacity(&self, max_buffer_size: usize) -> WindowSize {
+        let available = self.send_flow.available().as_size() as usize;
+        let buffered = self.buffered_send_data as usize;
+
+        available.min(max_buffer_size).saturating_sub(buffered) as WindowSize
+    }
+
+    /// Returns true if more data may be buffered for sending.
+    pub fn is_send_buffer_ready(&self, max_buffer_size: usize) -> bool {
+        (self.buffered_send_data as usize) < max_buffer_size
+    }
+
+    pub fn assign_capacity(&mut self, capacity: WindowSize, max_buffer_size: usize) {
+        let prev_capacity = self.capacity(max_buffer_size);
         debug_assert!(capacity > 0);
-        self.send_capacity_inc = true;
         self.send_flow.assign_capacity(capacity);
 
         log::trace!(
@@ -272,13 +313,39 @@ impl Stream {
             self.id
         );
 
-        // Only notify if the capacity exceeds the amount of buffered data
-        if self.send_flow.available() > self.buffered_send_data {
-            log::trace!("  notifying task");
-            self.notify_send();
+        // Only notify if the usable capacity grew
+        if prev_capacity < self.capacity(max_buffer_size) {
+            self.notify_capacity();
+        }
+    }
+
+    /// Accounts for data leaving the send buffer to be written.
+    pub fn send_data(&mut self, len: WindowSize, max_buffer_size: usize) {
+        let prev_capacity = self.capacity(max_buffer_size);
+        let was_buffer_ready = self.is_send_buffer_ready(max_buffer_size);
+
+        self.send_flow.send_data(len);
+
+        // Decrement the stream's buffered data counter
+        debug_assert!(self.buffered_send_data >= len);
+        self.buffered_send_data -= len;
+        self.requested_send_capacity -= len;
+
+        // The capacity was capped by the buffered data, or a producer waits
+        // for the buffer to drain.
+        if prev_capacity < self.capacity(max_buffer_size)
+            || (!was_buffer_ready && self.is_send_buffer_ready(max_buffer_size))
+        {
+            self.notify_capacity();
         }
     }
 
+    fn notify_capacity(&mut self) {
+        self.send_capacity_inc = true;
+        log::trace!("  notifying task");
+        self.notify_send();
+    }
+
     /// Returns `Err` when the decrement cannot be completed due to overflow.
     pub fn dec_content_length(&mut self, len: usize) -> Result<(), ()> {
         match self.content_length {
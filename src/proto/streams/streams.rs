@@ -2,19 +2,35 @@ use super::recv::RecvHeaderBlockError;
 use super::store::{self, Entry, Resolve, Store};
 use super::{Buffer, Config, Counts, Prioritized, Recv, Send, Stream, StreamId};
 use crate::codec::{Codec, RecvError, SendError, UserError};
+use crate::ext::Protocol;
 use crate::frame::{self, Frame, Reason};
 use crate::proto::{peer, Open, Peer, WindowSize};
+use crate::remote_settings::RemoteSettings;
+use crate::stats::{ConnectionStats, SharedStats, StreamStats};
 use crate::{client, proto, server};
 
 use bytes::{Buf, Bytes};
-use http::{HeaderMap, Request, Response};
+use http::{HeaderMap, Request, Response, Uri};
 use std::task::{Context, Poll, Waker};
 use futures_io::AsyncWrite;
 
 use crate::PollExt;
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use std::{fmt, io};
 
+/// Maximum number of origins kept from the ORIGIN frames received, the
+/// others are ignored.
+const MAX_ORIGINS: usize = 256;
+
+/// Maximum number of tasks waiting for the connection to stop accepting new
+/// streams.
+const MAX_CLOSED_TASKS: usize = 32;
+
+/// Maximum number of tasks waiting for the settings of the peer to change.
+const MAX_REMOTE_SETTINGS_TASKS: usize = 32;
+
 #[derive(Debug)]
 pub(crate) struct Streams<B, P>
 where
@@ -67,6 +83,9 @@ struct Inner {
 
     /// The number of stream refs to this shared state.
     refs: usize,
+
+    /// Counters of the codec, for reporting connection stats.
+    stats: Arc<SharedStats>,
 }
 
 #[derive(Debug)]
@@ -82,6 +101,26 @@ struct Actions {
 
     /// If the connection errors, a copy is kept for any StreamRefs.
     conn_error: Option<proto::Error>,
+
+    /// The last GOAWAY frame received.
+    go_away: Option<frame::GoAway>,
+
+    /// Tasks waiting for the connection to stop accepting new streams.
+    closed_tasks: Vec<Waker>,
+
+    /// Set once a client started a graceful shutdown, new requests are then
+    /// refused.
+    going_away: bool,
+
+    /// The origins advertised by the server in ORIGIN frames, once the first
+    /// one is received.
+    origin_set: Option<HashSet<Uri>>,
+
+    /// The settings of the peer.
+    remote_settings: RemoteSettings,
+
+    /// Tasks waiting for the settings of the peer to change.
+    remote_settings_tasks: Vec<Waker>,
 }
 
 /// Contains the buffer of frames to be written to the wire.
@@ -97,7 +136,7 @@ where
     B: Buf,
     P: Peer,
 {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, stats: Arc<SharedStats>) -> Self {
         let peer = P::r#dyn();
 
         Streams {
@@ -108,9 +147,16 @@ where
                     send: Send::new(&config),
                     task: None,
                     conn_error: None,
+                    go_away: None,
+                    closed_tasks: Vec::new(),
+                    going_away: false,
+                    origin_set: None,
+                    remote_settings: RemoteSettings::default(),
+                    remote_settings_tasks: Vec::new(),
                 },
                 store: Store::new(),
                 refs: 1,
+                stats,
             })),
             send_buffer: Arc::new(SendBuffer::new()),
             _p: ::std::marker::PhantomData,
@@ -126,6 +172,16 @@ where
             .set_target_connection_window(size, &mut me.actions.task)
     }
 
+    /// Grows the connection window to `size`, unless it is larger already.
+    pub fn grow_target_connection_window_size(&mut self, size: WindowSize) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        me.actions
+            .recv
+            .grow_target_connection_window(size, &mut me.actions.task)
+    }
+
     /// Process inbound headers
     pub fn recv_headers(&mut self, frame: frame::Headers) -> Result<(), RecvError> {
         let id = frame.stream_id();
@@ -134,7 +190,7 @@ where
 
         // The GOAWAY process has begun. All streams with a greater ID than
         // specified as part of GOAWAY should be ignored.
-        if id > me.actions.recv.max_stream_id() {
+        if me.actions.is_ignored_after_go_away::<P>(id) {
             log::trace!(
                 "id ({:?}) > max_stream_id ({:?}), ignoring HEADERS",
                 id,
@@ -181,7 +237,7 @@ where
             }
         };
 
-        let stream = me.store.resolve(key);
+        let mut stream = me.store.resolve(key);
 
         if stream.state.is_local_reset() {
             // Locally reset streams must ignore frames "for some time".
@@ -191,6 +247,27 @@ where
             return Ok(());
         }
 
+        if let Some(dependency) = frame.stream_dependency() {
+            if !me.actions.send.is_rfc7540_priorities_disabled() {
+                me.actions.send.reprioritize(id, dependency, false);
+            }
+        }
+
+        if P::is_server() && stream.state.is_recv_headers() {
+            // A PRIORITY_UPDATE received before the request overrides the
+            // `priority` header field.
+            let params = me.actions.send.take_priority_update(id).or_else(|| {
+                frame
+                    .fields()
+                    .get("priority")
+                    .map(|value| frame::PriorityParams::parse(value.as_bytes()))
+            });
+
+            if let Some(params) = params {
+                me.actions.send.set_priority_params(&mut stream, params);
+            }
+        }
+
         let actions = &mut me.actions;
         let mut send_buffer = self.send_buffer.inner.lock().unwrap();
         let send_buffer = &mut *send_buffer;
@@ -258,7 +335,7 @@ where
             None => {
                 // The GOAWAY process has begun. All streams with a greater ID
                 // than specified as part of GOAWAY should be ignored.
-                if id > me.actions.recv.max_stream_id() {
+                if me.actions.is_ignored_after_go_away::<P>(id) {
                     log::trace!(
                         "id ({:?}) > max_stream_id ({:?}), ignoring DATA",
                         id,
@@ -321,7 +398,7 @@ where
 
         // The GOAWAY process has begun. All streams with a greater ID than
         // specified as part of GOAWAY should be ignored.
-        if id > me.actions.recv.max_stream_id() {
+        if me.actions.is_ignored_after_go_away::<P>(id) {
             log::trace!(
                 "id ({:?}) > max_stream_id ({:?}), ignoring RST_STREAM",
                 id,
@@ -347,12 +424,29 @@ where
 
         let actions = &mut me.actions;
 
+        // Opening and immediately resetting streams costs the remote close to
+        // nothing, while the local end may already have started the work.
+        // Cancelling a stream that was already answered is normal though.
+        let is_unanswered = !me.counts.peer().is_local_init(id) && stream.state.is_send_headers();
+
         me.counts.transition(stream, |counts, stream| {
             actions.recv.recv_reset(frame, stream);
             actions.send.recv_err(send_buffer, stream, counts);
             assert!(stream.state.is_closed());
-            Ok(())
-        })
+        });
+
+        me.counts.inc_num_remote_reset_streams();
+
+        let now = Instant::now();
+
+        if is_unanswered && !me.counts.inc_num_unanswered_remote_reset_streams(now) {
+            log::debug!(
+                "connection error ENHANCE_YOUR_CALM -- too many streams reset by the remote"
+            );
+            return Err(RecvError::Connection(Reason::ENHANCE_YOUR_CALM));
+        }
+
+        Ok(())
     }
 
     /// Handle a received error and return the ID of the last processed stream.
@@ -377,7 +471,13 @@ where
             })
             .unwrap();
 
-        actions.conn_error = Some(err.shallow_clone());
+        // Requests sent after a GOAWAY remain unprocessed, whatever happens
+        // to the connection next.
+        if actions.go_away.is_none() {
+            actions.conn_error = Some(err.shallow_clone());
+        }
+
+        actions.notify_closed();
 
         last_processed_id
     }
@@ -395,11 +495,13 @@ where
 
         actions.send.recv_go_away(last_stream_id)?;
 
-        let err = frame.reason().into();
+        let err = proto::Error::GoAway(frame.reason());
 
+        // The identifier only covers the streams we initiated, those of the
+        // peer are left to complete.
         me.store
             .for_each(|stream| {
-                if stream.id > last_stream_id {
+                if stream.id > last_stream_id && P::is_local_init(stream.id) {
                     counts.transition(stream, |counts, stream| {
                         actions.recv.recv_err(&err, &mut *stream);
                         actions.send.recv_err(send_buffer, stream, counts);
@@ -412,6 +514,97 @@ where
             .unwrap();
 
         actions.conn_error = Some(err);
+        actions.go_away = Some(frame.clone());
+        actions.notify_closed();
+
+        Ok(())
+    }
+
+    pub fn recv_origin(&mut self, frame: frame::Origin) {
+        if P::is_server() {
+            // Only servers send ORIGIN frames, a server ignores them.
+            log::trace!("recv_origin; ignoring ORIGIN frame as server");
+            return;
+        }
+
+        let mut me = self.inner.lock().unwrap();
+        let origin_set = me.actions.origin_set.get_or_insert_with(HashSet::new);
+
+        // Each frame adds to the origins of the previous ones.
+        for origin in frame.into_origins() {
+            match origin.parse::<Uri>() {
+                Ok(ref uri) if uri.scheme().is_none() || uri.authority().is_none() => {
+                    log::debug!("recv_origin; ignoring invalid origin={:?}", origin);
+                }
+                Ok(uri) => {
+                    if origin_set.len() >= MAX_ORIGINS {
+                        log::debug!("recv_origin; too many origins, ignoring origin={:?}", uri);
+                        continue;
+                    }
+
+                    origin_set.insert(uri);
+                }
+                Err(_) => {
+                    log::debug!("recv_origin; ignoring invalid origin={:?}", origin);
+                }
+            }
+        }
+    }
+
+    pub fn recv_priority(&mut self, frame: &frame::Priority) -> Result<(), RecvError> {
+        let id = frame.stream_id();
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        if me.actions.send.is_rfc7540_priorities_disabled() {
+            log::trace!("recv_priority; RFC 7540 priorities disabled by remote, ignoring");
+            return Ok(());
+        }
+
+        let is_idle = if me.store.contains_id(&id) {
+            false
+        } else if me.actions.may_have_forgotten_stream::<P>(id) {
+            // Streams that are closed and forgotten keep no priority.
+            log::trace!("recv_priority; ignoring closed stream={:?}", id);
+            return Ok(());
+        } else {
+            true
+        };
+
+        me.actions
+            .send
+            .reprioritize(id, frame.dependency(), is_idle);
+        me.actions.sweep_priorities(me.counts.peer(), &me.store);
+
+        Ok(())
+    }
+
+    pub fn recv_priority_update(&mut self, frame: &frame::PriorityUpdate) -> Result<(), RecvError> {
+        let id = frame.prioritized_id();
+
+        if !P::is_server() {
+            // Servers MUST NOT send PRIORITY_UPDATE frames.
+            proto_err!(conn: "recv_priority_update: received PRIORITY_UPDATE as client");
+            return Err(RecvError::Connection(Reason::PROTOCOL_ERROR));
+        }
+
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        match me.store.find_mut(&id) {
+            Some(mut stream) => {
+                me.actions
+                    .send
+                    .set_priority_params(&mut stream, frame.params());
+            }
+            None => {
+                if id.is_client_initiated() && !me.actions.recv.may_have_created_stream(id) {
+                    me.actions.send.buffer_priority_update(id, frame.params());
+                } else {
+                    log::trace!("recv_priority_update; ignoring closed stream={:?}", id);
+                }
+            }
+        }
 
         Ok(())
     }
@@ -469,10 +662,10 @@ where
             Some(stream) => {
                 // The GOAWAY process has begun. All streams with a greater ID
                 // than specified as part of GOAWAY should be ignored.
-                if id > me.actions.recv.max_stream_id() {
+                if me.actions.is_ignored_after_go_away::<P>(promised_id) {
                     log::trace!(
                         "id ({:?}) > max_stream_id ({:?}), ignoring PUSH_PROMISE",
-                        id,
+                        promised_id,
                         me.actions.recv.max_stream_id()
                     );
                     return Ok(());
@@ -609,6 +802,8 @@ where
         let mut send_buffer = self.send_buffer.inner.lock().unwrap();
         let send_buffer = &mut *send_buffer;
 
+        me.actions.sweep_priorities(me.counts.peer(), &me.store);
+
         // Send WINDOW_UPDATE frames first
         //
         // TODO: It would probably be better to interleave updates w/ data
@@ -639,6 +834,11 @@ where
 
         me.counts.apply_remote_settings(frame);
 
+        me.actions.remote_settings.apply(frame);
+        for task in me.actions.remote_settings_tasks.drain(..) {
+            task.wake();
+        }
+
         me.actions.send.apply_remote_settings(
             frame,
             send_buffer,
@@ -676,6 +876,7 @@ where
         let send_buffer = &mut *send_buffer;
 
         me.actions.ensure_no_conn_error()?;
+        me.actions.ensure_not_going_away()?;
         me.actions.send.ensure_next_stream_id()?;
 
         // The `pending` argument is provided by the `Client`, and holds
@@ -695,6 +896,14 @@ where
             return Err(UserError::UnexpectedFrameType.into());
         }
 
+        // An extended CONNECT may only be sent once the peer has advertised
+        // SETTINGS_ENABLE_CONNECT_PROTOCOL.
+        if request.extensions().get::<Protocol>().is_some()
+            && !me.actions.send.is_extended_connect_protocol_enabled()
+        {
+            return Err(UserError::PeerDisabledConnectProtocol.into());
+        }
+
         let stream_id = me.actions.send.open()?;
 
         let mut stream = Stream::new(
@@ -710,6 +919,10 @@ where
         // Convert the message
         let headers = client::Peer::convert_send_message(stream_id, request, end_of_stream)?;
 
+        if let Some(dependency) = headers.stream_dependency() {
+            me.actions.send.reprioritize(stream_id, dependency, false);
+        }
+
         let mut stream = me.store.insert(stream.id, stream);
 
         let sent = me.actions.send.send_headers(
@@ -767,6 +980,29 @@ where
         let me = &mut *me;
         let actions = &mut me.actions;
         actions.recv.go_away(last_processed_id);
+
+        if !P::is_server() {
+            actions.going_away = true;
+            actions.notify_closed();
+        }
+    }
+
+    /// Returns the streams that are still open, in either direction.
+    pub fn active_stream_ids(&self) -> Vec<StreamId> {
+        let mut me = self.inner.lock().unwrap();
+        let mut ids = vec![];
+
+        me.store
+            .for_each(|stream| {
+                if !stream.state.is_closed() {
+                    ids.push(stream.id);
+                }
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+
+        ids.sort();
+        ids
     }
 }
 
@@ -774,6 +1010,47 @@ impl<B> Streams<B, client::Peer>
 where
     B: Buf,
 {
+    /// Opens stream 1 for a request that was sent over HTTP/1.1 with an
+    /// `Upgrade: h2c` header. The stream is half-closed (local), awaiting the
+    /// response.
+    pub fn send_upgrade_request(
+        &mut self,
+        request: &Request<()>,
+    ) -> Result<StreamRef<B>, SendError> {
+        use super::stream::ContentLength;
+        use http::Method;
+
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let stream_id = me.actions.send.open()?;
+
+        if stream_id != StreamId::from(1) {
+            return Err(UserError::Rejected.into());
+        }
+
+        let mut stream = Stream::new(
+            stream_id,
+            me.actions.send.init_window_sz(),
+            me.actions.recv.init_window_sz(),
+        );
+
+        if *request.method() == Method::HEAD {
+            stream.content_length = ContentLength::Head;
+        }
+
+        let mut stream = me.store.insert(stream.id, stream);
+
+        me.actions.send.open_upgraded(&mut stream, &mut me.counts)?;
+
+        me.refs += 1;
+
+        Ok(StreamRef {
+            opaque: OpaqueStreamRef::new(self.inner.clone(), &mut stream),
+            send_buffer: self.send_buffer.clone(),
+        })
+    }
+
     pub fn poll_pending_open(
         &mut self,
         cx: &Context,
@@ -783,6 +1060,7 @@ where
         let me = &mut *me;
 
         me.actions.ensure_no_conn_error()?;
+        me.actions.ensure_not_going_away()?;
         me.actions.send.ensure_next_stream_id()?;
 
         if let Some(pending) = pending {
@@ -795,12 +1073,100 @@ where
         }
         Poll::Ready(Ok(()))
     }
+
+    /// Returns true once no new stream may be opened, because a GOAWAY frame
+    /// was sent or received, or the connection is closed.
+    pub fn is_draining(&self) -> bool {
+        let me = self.inner.lock().unwrap();
+        me.actions.conn_error.is_some() || me.actions.going_away
+    }
+
+    pub fn poll_closed(&mut self, cx: &Context) -> Poll<()> {
+        let mut me = self.inner.lock().unwrap();
+
+        if me.actions.conn_error.is_some() || me.actions.going_away {
+            return Poll::Ready(());
+        }
+
+        let tasks = &mut me.actions.closed_tasks;
+
+        if !tasks.iter().any(|task| task.will_wake(cx.waker())) {
+            if tasks.len() >= MAX_CLOSED_TASKS {
+                // Rather than growing, wake all the tasks early. Those still
+                // waiting register again, the others are forgotten.
+                for task in tasks.drain(..) {
+                    task.wake();
+                }
+            }
+
+            tasks.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+
+    /// Returns the reason and debug data of the last GOAWAY frame received.
+    pub fn go_away_received(&self) -> Option<(Reason, Bytes)> {
+        let me = self.inner.lock().unwrap();
+        me.actions
+            .go_away
+            .as_ref()
+            .map(|frame| (frame.reason(), frame.debug_data().clone()))
+    }
 }
 
 impl<B, P> Streams<B, P>
 where
     P: Peer,
 {
+    pub fn origin_set(&self) -> Option<Vec<Uri>> {
+        let me = self.inner.lock().unwrap();
+        me.actions
+            .origin_set
+            .as_ref()
+            .map(|set| set.iter().cloned().collect())
+    }
+
+    pub fn remote_settings(&self) -> RemoteSettings {
+        let me = self.inner.lock().unwrap();
+        me.actions.remote_settings.clone()
+    }
+
+    /// Returns the settings of the peer once they differ from the generation
+    /// `seen`, or `None` once the connection is closed.
+    pub fn poll_remote_settings(
+        &mut self,
+        cx: &Context,
+        seen: u64,
+    ) -> Poll<Option<RemoteSettings>> {
+        let mut me = self.inner.lock().unwrap();
+        let actions = &mut me.actions;
+
+        if actions.remote_settings.generation() != seen {
+            return Poll::Ready(Some(actions.remote_settings.clone()));
+        }
+
+        if actions.conn_error.is_some() {
+            return Poll::Ready(None);
+        }
+
+        let tasks = &mut actions.remote_settings_tasks;
+
+        if !tasks.iter().any(|task| task.will_wake(cx.waker())) {
+            if tasks.len() >= MAX_REMOTE_SETTINGS_TASKS {
+                // Rather than growing, wake all the tasks early. Those still
+                // waiting register again, the others are forgotten.
+                for task in tasks.drain(..) {
+                    task.wake();
+                }
+            }
+
+            tasks.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+
     /// This function is safe to call multiple times.
     ///
     /// A `Result` is returned to avoid panicking if the mutex is poisoned.
@@ -833,6 +1199,7 @@ where
             .expect("recv_eof");
 
         actions.clear_queues(clear_pending_accept, &mut me.store, counts);
+        actions.notify_closed();
         Ok(())
     }
 
@@ -842,11 +1209,29 @@ where
         me.store.num_active_streams()
     }
 
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        let me = self.inner.lock().unwrap();
+        me.actions.send.is_extended_connect_protocol_enabled()
+    }
+
     pub fn has_streams(&self) -> bool {
         let me = self.inner.lock().unwrap();
         me.counts.has_streams()
     }
 
+    pub fn stats(&self) -> ConnectionStats {
+        let me = self.inner.lock().unwrap();
+
+        let mut stats = me.stats.snapshot();
+        stats.open_streams = me.counts.num_active_streams();
+        stats.closed_streams = me.counts.num_closed_streams();
+        stats.reset_streams = me.counts.num_closed_reset_streams();
+        stats.remote_reset_streams = me.counts.num_remote_reset_streams();
+        stats.send_window = me.actions.send.window_size();
+        stats.recv_window = me.actions.recv.window_size();
+        stats
+    }
+
     pub fn has_streams_or_other_references(&self) -> bool {
         let me = self.inner.lock().unwrap();
         me.counts.has_streams() || me.refs > 1
@@ -942,6 +1327,51 @@ impl<B> StreamRef<B> {
             .send_reset(stream, reason, &mut me.counts, send_buffer);
     }
 
+    pub fn set_priority(&mut self, dependency: frame::StreamDependency) -> Result<(), UserError> {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+
+        if dependency.dependency_id() == stream.id {
+            return Err(UserError::InvalidStreamDependency);
+        }
+
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        let frame = frame::Priority::new(stream.id, dependency);
+
+        me.actions
+            .send
+            .send_priority(frame, send_buffer, &mut stream, &mut me.actions.task);
+
+        Ok(())
+    }
+
+    pub fn update_priority(&mut self, params: frame::PriorityParams) {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+
+        if me.counts.peer().is_server() || stream.state.is_closed() {
+            // Only the response is scheduled, there's no one to tell.
+            me.actions.send.set_priority_params(&mut stream, params);
+            return;
+        }
+
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        me.actions.send.send_priority_update(
+            params,
+            send_buffer,
+            &mut stream,
+            &mut me.actions.task,
+        );
+    }
+
     pub fn send_response(
         &mut self,
         response: Response<()>,
@@ -964,6 +1394,39 @@ impl<B> StreamRef<B> {
         })
     }
 
+    pub fn send_informational(&mut self, response: Response<()>) -> Result<(), UserError> {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+        let actions = &mut me.actions;
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        let frame = server::Peer::convert_send_message(stream.id, response, false);
+
+        actions
+            .send
+            .send_informational_headers(frame, send_buffer, &mut stream, &mut actions.task)
+    }
+
+    pub fn send_alt_svc(&mut self, value: Bytes) -> Result<(), UserError> {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+        let actions = &mut me.actions;
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        // The origin of a request stream is the one of the request.
+        let frame = frame::AltSvc::new(stream.id, Bytes::new(), value);
+
+        actions
+            .send
+            .send_alt_svc(frame, send_buffer, &mut stream, &mut actions.task)
+    }
+
     pub fn send_push_promise(&mut self, request: Request<()>) -> Result<StreamRef<B>, UserError> {
         let mut me = self.opaque.inner.lock().unwrap();
         let me = &mut *me;
@@ -1036,6 +1499,16 @@ impl<B> StreamRef<B> {
         me.store.resolve(self.opaque.key).is_pending_open
     }
 
+    /// Returns true if the local end of the stream was ended with
+    /// `END_STREAM`, rather than by a reset.
+    #[cfg(feature = "stream")]
+    pub fn is_send_end_stream(&self) -> bool {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let state = &me.store.resolve(self.opaque.key).state;
+
+        state.is_send_closed() && !state.is_reset()
+    }
+
     /// Request capacity to send data
     pub fn reserve_capacity(&mut self, capacity: WindowSize) {
         let mut me = self.opaque.inner.lock().unwrap();
@@ -1068,6 +1541,16 @@ impl<B> StreamRef<B> {
         me.actions.send.poll_capacity(cx, &mut stream)
     }
 
+    /// Request to be notified when more data may be buffered on the stream.
+    pub fn poll_send_buffer_ready(&mut self, cx: &Context) -> Poll<Result<(), UserError>> {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+
+        me.actions.send.poll_send_buffer_ready(cx, &mut stream)
+    }
+
     /// Request to be notified for if a `RST_STREAM` is received for this stream.
     pub(crate) fn poll_reset(
         &mut self,
@@ -1095,6 +1578,10 @@ impl<B> StreamRef<B> {
     pub fn stream_id(&self) -> StreamId {
         self.opaque.stream_id()
     }
+
+    pub fn stats(&self) -> StreamStats {
+        self.opaque.stats()
+    }
 }
 
 impl<B> Clone for StreamRef<B> {
@@ -1125,6 +1612,19 @@ impl OpaqueStreamRef {
 
         me.actions.recv.poll_response(cx, &mut stream)
     }
+
+    /// Called by a client to check for received informational responses.
+    pub fn poll_informational(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Option<Result<Response<()>, proto::Error>>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions.recv.poll_informational(cx, &mut stream)
+    }
     /// Called by a client to check for a pushed request.
     pub fn poll_pushed(
         &mut self,
@@ -1160,7 +1660,9 @@ impl OpaqueStreamRef {
 
         let mut stream = me.store.resolve(self.key);
 
-        me.actions.recv.poll_data(cx, &mut stream)
+        me.actions
+            .recv
+            .poll_data(cx, &mut stream, &mut me.actions.task)
     }
 
     pub fn poll_trailers(&mut self, cx: &Context) -> Poll<Option<Result<HeaderMap, proto::Error>>> {
@@ -1201,6 +1703,26 @@ impl OpaqueStreamRef {
             .release_capacity(capacity, &mut stream, &mut me.actions.task)
     }
 
+    pub fn set_auto_release_capacity(&mut self, enabled: bool) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions
+            .recv
+            .set_auto_release_capacity(enabled, &mut stream, &mut me.actions.task)
+    }
+
+    pub fn is_auto_release_capacity(&self) -> bool {
+        let me = self.inner.lock().unwrap();
+        let me = &*me;
+
+        me.actions
+            .recv
+            .is_auto_release_capacity(&me.store[self.key])
+    }
+
     pub(crate) fn clear_recv_buffer(&mut self) {
         let mut me = self.inner.lock().unwrap();
         let me = &mut *me;
@@ -1213,6 +1735,18 @@ impl OpaqueStreamRef {
     pub fn stream_id(&self) -> StreamId {
         self.inner.lock().unwrap().store[self.key].id
     }
+
+    pub fn stats(&self) -> StreamStats {
+        let me = self.inner.lock().unwrap();
+        let stream = &me.store[self.key];
+
+        StreamStats {
+            bytes_sent: stream.sent_data_len,
+            bytes_received: stream.recv_data_len,
+            send_window: stream.send_flow.window_size(),
+            recv_window: stream.recv_flow.window_size(),
+        }
+    }
 }
 
 impl fmt::Debug for OpaqueStreamRef {
@@ -1363,6 +1897,8 @@ impl Actions {
             // Reset the stream.
             self.send
                 .send_reset(reason, buffer, stream, counts, &mut self.task);
+            // A response future or body may be waiting on the stream.
+            stream.notify_recv();
             Ok(())
         } else {
             res
@@ -1377,6 +1913,43 @@ impl Actions {
         }
     }
 
+    /// Removes the dependency tree entries of streams that are gone.
+    fn sweep_priorities(&mut self, peer: peer::Dyn, store: &Store) {
+        let ids = match self.send.priority_sweep_candidates() {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        let mut stale = Vec::new();
+        let mut num_idle = 0;
+
+        for id in ids {
+            let may_have_created = if peer.is_local_init(id) {
+                self.send.may_have_created_stream(id)
+            } else {
+                self.recv.may_have_created_stream(id)
+            };
+
+            if !may_have_created {
+                num_idle += 1;
+            } else if !store.contains_id(&id) {
+                stale.push(id);
+            }
+        }
+
+        self.send.sweep_priorities(&stale, num_idle);
+    }
+
+    fn notify_closed(&mut self) {
+        for task in self.closed_tasks.drain(..) {
+            task.wake();
+        }
+
+        for task in self.remote_settings_tasks.drain(..) {
+            task.wake();
+        }
+    }
+
     fn ensure_no_conn_error(&self) -> Result<(), proto::Error> {
         if let Some(ref err) = self.conn_error {
             Err(err.shallow_clone())
@@ -1385,6 +1958,20 @@ impl Actions {
         }
     }
 
+    /// Returns true if frames on the stream must be ignored, because it was
+    /// opened by the peer after the last stream identifier of our GOAWAY.
+    fn is_ignored_after_go_away<P: Peer>(&self, id: StreamId) -> bool {
+        id > self.recv.max_stream_id() && !P::is_local_init(id)
+    }
+
+    fn ensure_not_going_away(&self) -> Result<(), UserError> {
+        if self.going_away {
+            Err(UserError::GoingAway)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Check if we possibly could have processed and since forgotten this stream.
     ///
     /// If we send a RST_STREAM for a stream, we will eventually "forget" about
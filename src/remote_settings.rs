@@ -0,0 +1,198 @@
+use crate::frame;
+
+/// The settings of the peer, as last received in a SETTINGS frame.
+///
+/// Returned by the `remote_settings` functions of [`client::Connection`],
+/// [`client::SendRequest`] and [`server::Connection`]. Settings the peer
+/// didn't send have their default value, and a limit it didn't send is
+/// `None`, meaning unlimited.
+///
+/// [`client::Connection`]: client/struct.Connection.html
+/// [`client::SendRequest`]: client/struct.SendRequest.html
+/// [`server::Connection`]: server/struct.Connection.html
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RemoteSettings {
+    header_table_size: u32,
+    enable_push: bool,
+    max_concurrent_streams: Option<u32>,
+    initial_window_size: u32,
+    max_frame_size: u32,
+    max_header_list_size: Option<u32>,
+    enable_connect_protocol: bool,
+    custom: Vec<(u16, u32)>,
+    /// Number of SETTINGS frames applied, for handles to notice changes.
+    generation: u64,
+}
+
+impl RemoteSettings {
+    /// Returns the `SETTINGS_HEADER_TABLE_SIZE` of the peer.
+    pub fn header_table_size(&self) -> u32 {
+        self.header_table_size
+    }
+
+    /// Returns the `SETTINGS_ENABLE_PUSH` of the peer. This is only
+    /// meaningful when the peer is a client.
+    pub fn is_push_enabled(&self) -> bool {
+        self.enable_push
+    }
+
+    /// Returns the `SETTINGS_MAX_CONCURRENT_STREAMS` of the peer, which is
+    /// the number of streams it lets us open at once.
+    pub fn max_concurrent_streams(&self) -> Option<u32> {
+        self.max_concurrent_streams
+    }
+
+    /// Returns the `SETTINGS_INITIAL_WINDOW_SIZE` of the peer.
+    pub fn initial_window_size(&self) -> u32 {
+        self.initial_window_size
+    }
+
+    /// Returns the `SETTINGS_MAX_FRAME_SIZE` of the peer.
+    pub fn max_frame_size(&self) -> u32 {
+        self.max_frame_size
+    }
+
+    /// Returns the `SETTINGS_MAX_HEADER_LIST_SIZE` of the peer.
+    pub fn max_header_list_size(&self) -> Option<u32> {
+        self.max_header_list_size
+    }
+
+    /// Returns the `SETTINGS_ENABLE_CONNECT_PROTOCOL` of the peer.
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.enable_connect_protocol
+    }
+
+    /// Returns the settings of the peer that this crate doesn't implement,
+    /// as (identifier, value) pairs sorted by identifier.
+    pub fn custom(&self) -> &[(u16, u32)] {
+        &self.custom
+    }
+
+    /// Returns true once the first SETTINGS frame of the peer was received.
+    pub fn is_received(&self) -> bool {
+        self.generation > 0
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Updates the settings with those of a SETTINGS frame received.
+    pub(crate) fn apply(&mut self, frame: &frame::Settings) {
+        if let Some(val) = frame.header_table_size() {
+            self.header_table_size = val;
+        }
+
+        if let Some(val) = frame.is_push_enabled() {
+            self.enable_push = val;
+        }
+
+        if let Some(val) = frame.max_concurrent_streams() {
+            self.max_concurrent_streams = Some(val);
+        }
+
+        if let Some(val) = frame.initial_window_size() {
+            self.initial_window_size = val;
+        }
+
+        if let Some(val) = frame.max_frame_size() {
+            self.max_frame_size = val;
+        }
+
+        if let Some(val) = frame.max_header_list_size() {
+            self.max_header_list_size = Some(val);
+        }
+
+        if let Some(val) = frame.is_extended_connect_protocol_enabled() {
+            self.enable_connect_protocol = val;
+        }
+
+        if !frame.unknown_settings().is_empty() {
+            self.custom = merge_sorted(&self.custom, frame.unknown_settings());
+        }
+
+        self.generation += 1;
+    }
+}
+
+/// Merges two lists of settings sorted by identifier, the values of `new`
+/// replacing those of `old`.
+fn merge_sorted(old: &[(u16, u32)], new: &[(u16, u32)]) -> Vec<(u16, u32)> {
+    let mut merged = Vec::with_capacity(old.len() + new.len());
+    let mut old = old.iter().peekable();
+    let mut new = new.iter().peekable();
+
+    loop {
+        let next = match (old.peek(), new.peek()) {
+            (Some(a), Some(b)) if a.0 < b.0 => old.next(),
+            (Some(a), Some(b)) if a.0 == b.0 => {
+                old.next();
+                new.next()
+            }
+            (_, Some(_)) => new.next(),
+            (Some(_), None) => old.next(),
+            (None, None) => break,
+        };
+        merged.extend(next);
+    }
+
+    merged
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        RemoteSettings {
+            header_table_size: frame::DEFAULT_SETTINGS_HEADER_TABLE_SIZE as u32,
+            enable_push: true,
+            max_concurrent_streams: None,
+            initial_window_size: frame::DEFAULT_INITIAL_WINDOW_SIZE,
+            max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
+            max_header_list_size: None,
+            enable_connect_protocol: false,
+            custom: Vec::new(),
+            generation: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut settings = RemoteSettings::default();
+        assert!(!settings.is_received());
+        assert_eq!(settings.initial_window_size(), 65_535);
+
+        let mut frame = frame::Settings::default();
+        frame.set_max_concurrent_streams(Some(100));
+        frame.set_unknown_setting(0xf0, 1);
+        settings.apply(&frame);
+
+        let mut frame = frame::Settings::default();
+        frame.set_initial_window_size(Some(1 << 20));
+        frame.set_unknown_setting(0xf0, 2);
+        settings.apply(&frame);
+
+        assert!(settings.is_received());
+        assert_eq!(settings.max_concurrent_streams(), Some(100));
+        assert_eq!(settings.initial_window_size(), 1 << 20);
+        assert_eq!(settings.max_frame_size(), 16_384);
+        assert_eq!(settings.custom(), &[(0xf0, 2)]);
+        assert_eq!(settings.generation(), 2);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let old = [(1, 1), (3, 3), (5, 5)];
+        let new = [(0, 10), (3, 30), (6, 60)];
+
+        assert_eq!(
+            merge_sorted(&old, &new),
+            vec![(0, 10), (1, 1), (3, 30), (5, 5), (6, 60)]
+        );
+        assert_eq!(merge_sorted(&[], &new), new.to_vec());
+        assert_eq!(merge_sorted(&old, &[]), old.to_vec());
+    }
+}
@@ -116,14 +116,21 @@
 //! [`TcpListener`]: https://docs.rs/tokio-core/0.1/tokio_core/net/struct.TcpListener.html
 
 use crate::codec::{Codec, RecvError, UserError};
+use crate::ext::ExtensionFrame;
 use crate::frame::{self, Pseudo, PushPromiseHeaderError, Reason, Settings, StreamId};
+use crate::observer::{FrameObserver, ObserverHandle};
 use crate::proto::{self, Config, Prioritized};
-use crate::{FlowControl, PingPong, RecvStream, SendStream};
+use crate::remote_settings::RemoteSettings;
+use crate::shutdown::GracefulShutdown;
+use crate::stats::ConnectionStats;
+use crate::timer::{Timer, TimerHandle};
+use crate::{FlowControl, PingPong, RecvStream, SendStream, Tunnel};
 
 use bytes::{Buf, Bytes};
 use futures_io::{AsyncRead, AsyncWrite};
-use http::{HeaderMap, Method, Request, Response};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri};
 use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -149,6 +156,39 @@ pub struct Handshake<T, B: Buf = Bytes> {
     builder: Builder,
     /// The current state of the handshake.
     state: Handshaking<T, B>,
+    /// The settings and request of a connection upgraded from HTTP/1.1, to
+    /// apply and open as stream 1 once the connection is created.
+    upgrade: Option<(Settings, frame::Headers)>,
+    /// True if the client preface was already read by `Sniff`.
+    preface_read: bool,
+}
+
+/// Future detecting whether a connection starts with the HTTP/2.0 client
+/// preface.
+///
+/// Returned by [`Builder::sniff`], see its documentation for more details.
+///
+/// [`Builder::sniff`]: struct.Builder.html#method.sniff
+#[must_use = "futures do nothing unless polled"]
+pub struct Sniff<T, B: Buf = Bytes> {
+    builder: Builder,
+    io: Option<T>,
+    buf: [u8; 24],
+    pos: usize,
+    _phantom: PhantomData<B>,
+}
+
+/// The outcome of [`Sniff`].
+///
+/// [`Sniff`]: struct.Sniff.html
+pub enum Sniffed<T, B: Buf = Bytes> {
+    /// The client preface was received, the handshake continues with sending
+    /// the initial settings frame.
+    Http2(Box<Handshake<T, B>>),
+    /// Something else was received. The bytes read so far, which may be
+    /// empty if the connection was closed, are returned along with the I/O
+    /// handle so that another protocol can be served, usually HTTP/1.1.
+    Other(T, Bytes),
 }
 
 /// Accepts inbound HTTP/2.0 streams on a connection.
@@ -190,6 +230,9 @@ pub struct Handshake<T, B: Buf = Bytes> {
 #[must_use = "streams do nothing unless polled"]
 pub struct Connection<T, B: Buf> {
     connection: proto::Connection<T, Peer, B>,
+    /// Generation of the remote settings last returned by
+    /// `poll_remote_settings`.
+    remote_settings_seen: u64,
 }
 
 /// Builds server connections with custom configuration values.
@@ -235,11 +278,51 @@ pub struct Builder {
     /// Maximum number of locally reset streams to keep at a time.
     reset_stream_max: usize,
 
+    /// Maximum number of bytes buffered for sending on a stream.
+    max_send_buffer_size: usize,
+
+    /// If capacity is released when received data is taken.
+    auto_release_capacity: bool,
+
+    /// Maximum number of unread bytes buffered on a received stream.
+    max_recv_buffer_size: Option<usize>,
+
+    /// Maximum number of unanswered streams the client may reset within
+    /// `remote_reset_stream_duration`.
+    remote_reset_stream_max: usize,
+
+    /// Duration over which remotely reset streams are counted.
+    remote_reset_stream_duration: Duration,
+
+    /// Budgets for frames the peer could flood the connection with.
+    flood: proto::FloodConfig,
+
     /// Initial `Settings` frame to send as part of the handshake.
     settings: Settings,
 
     /// Initial target window size for new connections.
     initial_target_connection_window_size: Option<u32>,
+
+    /// Maximum window size when windows are sized adaptively.
+    max_adaptive_window_size: Option<u32>,
+
+    /// Timer used for keep-alive.
+    timer: Option<TimerHandle>,
+
+    /// Interval of keep-alive PINGs, if enabled.
+    keep_alive_interval: Option<Duration>,
+
+    /// How long to wait for a keep-alive PING to be acknowledged.
+    keep_alive_timeout: Duration,
+
+    /// Whether keep-alive PINGs are sent when there are no streams.
+    keep_alive_while_idle: bool,
+
+    /// Observer of the frames sent and received.
+    observer: Option<ObserverHandle>,
+
+    /// Frame types to receive as extension frames.
+    extension_types: Vec<u8>,
 }
 
 /// Send a response back to the client
@@ -370,6 +453,14 @@ where
             codec.set_max_recv_header_list_size(max as usize);
         }
 
+        if let Some(ref observer) = builder.observer {
+            codec.set_observer(observer.clone());
+        }
+
+        if !builder.extension_types.is_empty() {
+            codec.set_extension_types(builder.extension_types.clone());
+        }
+
         // Send initial settings frame.
         codec
             .buffer(builder.settings.clone().into())
@@ -378,7 +469,12 @@ where
         // Create the handshake future.
         let state = Handshaking::from(codec);
 
-        Handshake { builder, state }
+        Handshake {
+            builder,
+            state,
+            upgrade: None,
+            preface_read: false,
+        }
     }
 
     /// Accept the next incoming request on this connection.
@@ -449,13 +545,76 @@ where
     /// # Errors
     ///
     /// Returns an error if a previous call is still pending acknowledgement
-    /// from the remote endpoint.
+    /// from the remote endpoint. The SETTINGS sent by the connection for the
+    /// [adaptive window] don't count, they are merged with these or sent
+    /// before them, and this window size overrides theirs.
+    ///
+    /// [adaptive window]: struct.Builder.html#method.adaptive_window
     pub fn set_initial_window_size(&mut self, size: u32) -> Result<(), crate::Error> {
         assert!(size <= proto::MAX_WINDOW_SIZE);
         self.connection.set_initial_window_size(size)?;
         Ok(())
     }
 
+    /// Sends a new SETTINGS frame with settings this crate doesn't
+    /// implement, as (identifier, value) pairs.
+    ///
+    /// The `SETTINGS` will be sent to the remote, which acknowledges them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a previous SETTINGS frame is still pending
+    /// acknowledgement from the remote endpoint. The SETTINGS sent by the
+    /// connection for the [adaptive window] don't count, they are merged with
+    /// these or sent before them.
+    ///
+    /// Also returns an error if an identifier is the one of a setting
+    /// implemented by the connection, in which case nothing is sent.
+    ///
+    /// [adaptive window]: struct.Builder.html#method.adaptive_window
+    pub fn send_custom_settings(&mut self, settings: &[(u16, u32)]) -> Result<(), crate::Error> {
+        self.connection.send_custom_settings(settings)?;
+        Ok(())
+    }
+
+    /// Returns the settings received from the remote that this crate
+    /// doesn't implement, as (identifier, value) pairs with their latest
+    /// value.
+    ///
+    /// These are also available as [`RemoteSettings::custom`].
+    ///
+    /// [`RemoteSettings::custom`]: ../struct.RemoteSettings.html#method.custom
+    pub fn remote_custom_settings(&self) -> Vec<(u16, u32)> {
+        self.connection.remote_settings().custom().to_vec()
+    }
+
+    /// Returns the settings of the client, as last received.
+    ///
+    /// Until the first SETTINGS frame of the client is received, these are
+    /// the defaults of the specification.
+    pub fn remote_settings(&self) -> RemoteSettings {
+        self.connection.remote_settings()
+    }
+
+    /// Polls for a change of the settings of the client.
+    ///
+    /// Returns the settings once they differ from those last returned by
+    /// this function, so the first call returns once the initial SETTINGS
+    /// frame of the client is received. Returns `None` once the connection
+    /// is closed. The connection must still be polled for the settings to
+    /// be received.
+    pub fn poll_remote_settings(&mut self, cx: &mut Context) -> Poll<Option<RemoteSettings>> {
+        let settings = ready!(self
+            .connection
+            .poll_remote_settings(cx, self.remote_settings_seen));
+
+        if let Some(ref settings) = settings {
+            self.remote_settings_seen = settings.generation();
+        }
+
+        Poll::Ready(settings)
+    }
+
     /// Returns `Ready` when the underlying connection has closed.
     ///
     /// If any new inbound streams are received during a call to `poll_closed`,
@@ -509,6 +668,36 @@ where
         self.connection.go_away_gracefully();
     }
 
+    /// Starts a graceful shutdown process that turns abrupt once `timeout`
+    /// has passed.
+    ///
+    /// This behaves like [`graceful_shutdown`] until the deadline. If streams
+    /// are still active then, a GOAWAY frame with `NO_ERROR` is sent, and
+    /// the connection is closed without waiting for them, as with
+    /// [`abrupt_shutdown`].
+    ///
+    /// The returned [`GracefulShutdown`] resolves once the shutdown is over,
+    /// with the streams that were cut off, if any. The connection must still
+    /// be polled for the shutdown to make progress. Calling this again
+    /// returns a handle to the same shutdown, and the first deadline stays.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the connection was built without a [`timer`].
+    ///
+    /// [`graceful_shutdown`]: #method.graceful_shutdown
+    /// [`abrupt_shutdown`]: #method.abrupt_shutdown
+    /// [`GracefulShutdown`]: ../struct.GracefulShutdown.html
+    /// [`timer`]: struct.Builder.html#method.timer
+    pub fn graceful_shutdown_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<GracefulShutdown, crate::Error> {
+        self.connection
+            .go_away_gracefully_with_timeout(timeout)
+            .map_err(Into::into)
+    }
+
     /// Takes a `PingPong` instance from the connection.
     ///
     /// # Note
@@ -517,6 +706,85 @@ where
     pub fn ping_pong(&mut self) -> Option<PingPong> {
         self.connection.take_user_pings().map(PingPong::new)
     }
+
+    /// Queues an extension frame to send to the peer.
+    ///
+    /// The frame is written the next time the connection is polled. Its type
+    /// must not be one implemented by the connection, and its payload must
+    /// fit in the maximum frame size advertised by the peer.
+    pub fn send_extension_frame(
+        &mut self,
+        frame_type: u8,
+        flags: u8,
+        stream_id: crate::StreamId,
+        payload: Bytes,
+    ) -> Result<(), crate::Error> {
+        let frame = frame::Extension::new(frame_type, flags, stream_id.to_internal(), payload);
+        self.connection.send_extension_frame(frame)?;
+        Ok(())
+    }
+
+    /// Polls for the next extension frame received.
+    ///
+    /// Only the frames of the types registered with
+    /// [`Builder::extension_frame_type`] are received. Returns `None` once the
+    /// connection is closed and all the frames were taken. The connection
+    /// must still be polled for frames to be received.
+    ///
+    /// [`Builder::extension_frame_type`]: struct.Builder.html#method.extension_frame_type
+    pub fn poll_extension_frame(&mut self, cx: &mut Context) -> Poll<Option<ExtensionFrame>> {
+        self.connection
+            .poll_extension_frame(cx)
+            .map(|frame| frame.map(ExtensionFrame::from_internal))
+    }
+
+    /// Advertises the origins this server is authoritative for, in an ORIGIN
+    /// frame (RFC 8336).
+    ///
+    /// Clients may then reuse the connection for requests to these origins.
+    /// Each URI must have a scheme and an authority, only these are sent.
+    /// Every call sends a new frame, adding to the origins of the previous
+    /// ones. The frame is written the next time the connection is polled.
+    pub fn send_origin_set(&mut self, origins: Vec<Uri>) -> Result<(), crate::Error> {
+        let serialized = origins
+            .iter()
+            .map(serialize_origin)
+            .collect::<Result<_, _>>()?;
+
+        let frame = frame::Origin::new(serialized);
+        self.connection.send_origin(frame)?;
+        Ok(())
+    }
+
+    /// Advertises alternative services for an origin, in an ALTSVC frame sent
+    /// on stream 0 (RFC 7838 §4).
+    ///
+    /// The value has the syntax of the `Alt-Svc` header field, such as
+    /// `h3=":443"; ma=3600`, or `clear`. The origin must have a scheme and an
+    /// authority. The frame is written the next time the connection is
+    /// polled. Use [`SendResponse::send_alt_svc`] to advertise them for the
+    /// origin of a request instead.
+    ///
+    /// [`SendResponse::send_alt_svc`]: struct.SendResponse.html#method.send_alt_svc
+    pub fn send_alt_svc(&mut self, origin: &Uri, value: &HeaderValue) -> Result<(), crate::Error> {
+        let origin = serialize_origin(origin)?;
+        let value = Bytes::copy_from_slice(value.as_bytes());
+
+        let frame = frame::AltSvc::new(StreamId::ZERO, origin.into(), value);
+        self.connection.send_alt_svc(frame)?;
+        Ok(())
+    }
+
+    /// Returns a snapshot of the statistics of the connection.
+    ///
+    /// This includes the bytes and frames sent and received, the stream
+    /// counts, the connection level windows and the last PING round-trip
+    /// time. See [`ConnectionStats`] for details.
+    ///
+    /// [`ConnectionStats`]: ../struct.ConnectionStats.html
+    pub fn stats(&self) -> ConnectionStats {
+        self.connection.stats()
+    }
 }
 
 #[cfg(feature = "stream")]
@@ -544,6 +812,15 @@ where
     }
 }
 
+/// Serializes an origin as carried by ORIGIN and ALTSVC frames, which is
+/// `scheme://authority`.
+fn serialize_origin(origin: &Uri) -> Result<String, UserError> {
+    match (origin.scheme(), origin.authority()) {
+        (Some(scheme), Some(authority)) => Ok(format!("{}://{}", scheme, authority)),
+        _ => Err(UserError::MissingUriSchemeAndAuthority),
+    }
+}
+
 // ===== impl Builder =====
 
 impl Builder {
@@ -576,8 +853,23 @@ impl Builder {
         Builder {
             reset_stream_duration: Duration::from_secs(proto::DEFAULT_RESET_STREAM_SECS),
             reset_stream_max: proto::DEFAULT_RESET_STREAM_MAX,
+            max_send_buffer_size: proto::DEFAULT_MAX_SEND_BUFFER_SIZE,
+            auto_release_capacity: false,
+            max_recv_buffer_size: None,
+            remote_reset_stream_max: proto::DEFAULT_REMOTE_RESET_STREAM_MAX,
+            remote_reset_stream_duration: Duration::from_secs(
+                proto::DEFAULT_REMOTE_RESET_STREAM_SECS,
+            ),
+            flood: proto::FloodConfig::default(),
             settings: Settings::default(),
             initial_target_connection_window_size: None,
+            max_adaptive_window_size: None,
+            timer: None,
+            keep_alive_interval: None,
+            keep_alive_timeout: Duration::from_secs(proto::DEFAULT_KEEP_ALIVE_TIMEOUT_SECS),
+            keep_alive_while_idle: false,
+            observer: None,
+            extension_types: Vec::new(),
         }
     }
 
@@ -649,6 +941,56 @@ impl Builder {
         self
     }
 
+    /// Enables adaptive flow control for received data, growing the windows
+    /// up to `max_window_size`.
+    ///
+    /// The bandwidth-delay product of the connection is estimated by sending
+    /// a PING when DATA is received and counting the bytes received until it
+    /// is acknowledged. When the windows are found to limit the throughput,
+    /// the connection window and the initial stream window are grown to fit
+    /// the estimate. The stream window is updated with a SETTINGS frame,
+    /// which never makes the SETTINGS sent through [`Connection`] fail.
+    ///
+    /// The windows configured with [`initial_window_size`] and
+    /// [`initial_connection_window_size`] are used as a starting point, they
+    /// are never shrunk.
+    ///
+    /// Adaptive flow control is disabled by default.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_window_size` exceeds the maximum window
+    /// size of 2^31-1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .adaptive_window(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`initial_connection_window_size`]: #method.initial_connection_window_size
+    /// [`Connection`]: struct.Connection.html
+    pub fn adaptive_window(&mut self, max_window_size: u32) -> &mut Self {
+        assert!(max_window_size <= proto::MAX_WINDOW_SIZE);
+        self.max_adaptive_window_size = Some(max_window_size);
+        self
+    }
+
     /// Indicates the size (in octets) of the largest HTTP/2.0 frame payload that the
     /// configured server is able to accept.
     ///
@@ -721,6 +1063,45 @@ impl Builder {
         self
     }
 
+    /// Sets the size of the HPACK dynamic table used to decode received
+    /// headers.
+    ///
+    /// This is sent to the peer as `SETTINGS_HEADER_TABLE_SIZE`, the largest
+    /// table its encoder may use. A smaller table saves memory, a larger one
+    /// compresses repeated headers better. Setting it to 0 disables the
+    /// dynamic table.
+    ///
+    /// The new size is applied once the peer acknowledges the settings. If it
+    /// is smaller than the table currently in use, the peer must then shrink
+    /// its table at the start of the next header block, or the connection is
+    /// closed with a protocol error.
+    ///
+    /// The default value is 4,096.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .header_table_size(0)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn header_table_size(&mut self, size: u32) -> &mut Self {
+        self.settings.set_header_table_size(Some(size));
+        self
+    }
+
     /// Sets the maximum number of concurrent streams.
     ///
     /// The maximum concurrent streams setting only controls the maximum number
@@ -815,6 +1196,116 @@ impl Builder {
         self
     }
 
+    /// Sets the maximum number of bytes buffered for sending on each stream.
+    ///
+    /// Data passed to `SendStream::send_data` is buffered until the peer's
+    /// flow control window and the connection allow it to be written. This
+    /// setting bounds that buffer: the capacity assigned to a stream never
+    /// lets more than `max` bytes be buffered, and
+    /// `SendStream::poll_send_buffer_ready` only becomes ready once the
+    /// buffered data drops below `max`.
+    ///
+    /// The default value is 400KB.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max` is larger than `u32::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_send_buffer_size(64 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        assert!(max <= u32::MAX as usize);
+        self.max_send_buffer_size = max;
+        self
+    }
+
+    /// Sets whether received data releases its capacity when taken.
+    ///
+    /// When enabled, the capacity of each data frame is released back to the
+    /// peer as soon as `RecvStream` hands it to the caller, so calling
+    /// `FlowControl::release_capacity` is not needed. When disabled, which is
+    /// the default, the caller must release capacity itself, or the stream and
+    /// connection windows eventually stall.
+    ///
+    /// This can be overridden per stream with
+    /// `FlowControl::set_auto_release_capacity`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .auto_release_capacity(true)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn auto_release_capacity(&mut self, enabled: bool) -> &mut Self {
+        self.auto_release_capacity = enabled;
+        self
+    }
+
+    /// Sets the maximum number of unread bytes buffered on each received
+    /// stream.
+    ///
+    /// Received data is buffered until taken from the `RecvStream`. With a
+    /// maximum set, `WINDOW_UPDATE` frames for a stream are held back while
+    /// they would let the buffered data grow past `max`, whether or not the
+    /// capacity was released. The peer may still send up to the initial
+    /// window size before any data is taken.
+    ///
+    /// By default there is no maximum other than the window size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_recv_buffer_size(256 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_recv_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_recv_buffer_size = Some(max);
+        self
+    }
+
     /// Sets the maximum number of concurrent locally reset streams.
     ///
     /// When a stream is explicitly reset by either calling
@@ -862,26 +1353,26 @@ impl Builder {
         self
     }
 
-    /// Creates a new configured HTTP/2.0 server backed by `io`.
+    /// Sets the maximum number of streams the client may reset within any
+    /// [`remote_reset_stream_duration`], before they are answered.
     ///
-    /// It is expected that `io` already be in an appropriate state to commence
-    /// the [HTTP/2.0 handshake]. See [Handshake] for more details.
+    /// A client can open a stream and immediately reset it with RST_STREAM.
+    /// This costs the client close to nothing, while the server may already
+    /// have started processing the request, which allows for cheap denial of
+    /// service attacks (known as "rapid reset", CVE-2023-44487).
     ///
-    /// Returns a future which resolves to the [`Connection`] instance once the
-    /// HTTP/2.0 handshake has been completed.
+    /// Only streams reset before the server sent response headers count
+    /// towards this limit. Cancelling a stream whose response is already on
+    /// its way is normal client behavior and is not limited.
     ///
-    /// This function also allows the caller to configure the send payload data
-    /// type. See [Outbound data type] for more details.
+    /// When the client resets more streams than allowed, the connection is
+    /// closed with a GOAWAY frame carrying `ENHANCE_YOUR_CALM`. The number of
+    /// streams reset by the client is available from [`Connection::stats`].
     ///
-    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
-    /// [Handshake]: ../index.html#handshake
-    /// [`Connection`]: struct.Connection.html
-    /// [Outbound data type]: ../index.html#outbound-data-type.
+    /// The default value is 200.
     ///
     /// # Examples
     ///
-    /// Basic usage:
-    ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::server::*;
@@ -892,6 +1383,7 @@ impl Builder {
     /// // `server_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let server_fut = Builder::new()
+    ///     .max_remote_reset_streams(100)
     ///     .handshake(my_io);
     /// # server_fut
     /// # }
@@ -899,37 +1391,673 @@ impl Builder {
     /// # pub fn main() {}
     /// ```
     ///
-    /// Configures the send-payload data type. In this case, the outbound data
-    /// type will be `&'static [u8]`.
+    /// [`remote_reset_stream_duration`]: #method.remote_reset_stream_duration
+    /// [`Connection::stats`]: struct.Connection.html#method.stats
+    pub fn max_remote_reset_streams(&mut self, max: usize) -> &mut Self {
+        self.remote_reset_stream_max = max;
+        self
+    }
+
+    /// Sets the duration over which the streams reset by the client are
+    /// counted.
+    ///
+    /// The duration is a sliding window: a reset stops counting once it is
+    /// older than the duration.
+    ///
+    /// See [`max_remote_reset_streams`] for details.
+    ///
+    /// The default value is 10 seconds.
+    ///
+    /// # Examples
     ///
     /// ```
     /// # use futures_io::{AsyncRead, AsyncWrite};
     /// # use h2::server::*;
+    /// # use std::time::Duration;
     /// #
     /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
-    /// # -> Handshake<T, &'static [u8]>
+    /// # -> Handshake<T>
     /// # {
     /// // `server_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
-    /// let server_fut: Handshake<_, &'static [u8]> = Builder::new()
+    /// let server_fut = Builder::new()
+    ///     .remote_reset_stream_duration(Duration::from_secs(1))
     ///     .handshake(my_io);
     /// # server_fut
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
-    pub fn handshake<T, B>(&self, io: T) -> Handshake<T, B>
-    where
-        T: AsyncRead + AsyncWrite + Unpin,
-        B: Buf + 'static,
-    {
-        Connection::handshake2(io, self.clone())
+    ///
+    /// [`max_remote_reset_streams`]: #method.max_remote_reset_streams
+    pub fn remote_reset_stream_duration(&mut self, dur: Duration) -> &mut Self {
+        self.remote_reset_stream_duration = dur;
+        self
     }
-}
 
-impl Default for Builder {
-    fn default() -> Builder {
-        Builder::new()
+    /// Sets the maximum number of PING and SETTINGS acknowledgements that may
+    /// be waiting to be written to the socket.
+    ///
+    /// Every PING and SETTINGS frame received has to be acknowledged. A peer
+    /// sending them faster than it reads the acknowledgements makes these
+    /// pile up (CVE-2019-9512 and CVE-2019-9515). Once more than `max` are
+    /// pending, the connection is closed with a GOAWAY frame carrying
+    /// `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 100.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_pending_control_replies(200)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_pending_control_replies(&mut self, max: usize) -> &mut Self {
+        self.flood.max_pending_replies = max;
+        self
+    }
+
+    /// Sets the maximum number of empty DATA frames the peer may send in a
+    /// row.
+    ///
+    /// A DATA frame with neither payload nor the END_STREAM flag carries
+    /// nothing, yet has to be processed like any other (CVE-2019-9518). When
+    /// the peer sends more than `max` of them without a DATA frame with
+    /// payload in between, the connection is closed with a GOAWAY frame
+    /// carrying `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 10.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_empty_data_frames(20)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_empty_data_frames(&mut self, max: usize) -> &mut Self {
+        self.flood.max_empty_data_frames = max;
+        self
+    }
+
+    /// Sets the budget of WINDOW_UPDATE and PRIORITY frames the peer may send.
+    ///
+    /// The peer may send `max` WINDOW_UPDATE, PRIORITY and PRIORITY_UPDATE
+    /// frames up front, and `max` more for every stream opened and every DATA
+    /// frame sent on the connection. Beyond that, these frames only make the
+    /// connection churn its flow control and priority state, and the
+    /// connection is closed with a GOAWAY frame carrying `ENHANCE_YOUR_CALM`.
+    ///
+    /// The default value is 10.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_churn_frames(20)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_churn_frames(&mut self, max: usize) -> &mut Self {
+        self.flood.max_churn_frames = max;
+        self
+    }
+
+    /// Sets the timer used by the connection.
+    ///
+    /// A timer is required by the keep-alive options and by
+    /// [`Connection::graceful_shutdown_with_timeout`], which need to wait
+    /// without depending on a specific runtime. See [`Timer`] for how to
+    /// provide one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # fn my_timer() -> impl h2::Timer {
+    /// #     struct T;
+    /// #     impl h2::Timer for T {
+    /// #         fn sleep(&self, _: Duration) -> h2::Sleep { Box::pin(async {}) }
+    /// #     }
+    /// #     T
+    /// # }
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .timer(my_timer())
+    ///     .keep_alive_interval(Duration::from_secs(30))
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Timer`]: ../trait.Timer.html
+    /// [`Connection::graceful_shutdown_with_timeout`]: struct.Connection.html#method.graceful_shutdown_with_timeout
+    pub fn timer<T: Timer + 'static>(&mut self, timer: T) -> &mut Self {
+        self.timer = Some(TimerHandle::new(timer));
+        self
+    }
+
+    /// Enables keep-alive PINGs, sent whenever nothing has been received from
+    /// the peer for `interval`.
+    ///
+    /// If the peer doesn't acknowledge a PING within the
+    /// [`keep_alive_timeout`], the connection is closed with a GOAWAY and
+    /// fails with a timeout error.
+    ///
+    /// This requires a [`timer`] to be set.
+    ///
+    /// Keep-alive is disabled by default.
+    ///
+    /// # Errors
+    ///
+    /// The handshake fails if this is set without a [`timer`].
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_timeout`]: #method.keep_alive_timeout
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_interval(&mut self, interval: Duration) -> &mut Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long to wait for a keep-alive PING to be acknowledged before
+    /// closing the connection.
+    ///
+    /// This has no effect unless [`keep_alive_interval`] is set.
+    ///
+    /// The default value is 20 seconds.
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_interval`]: #method.keep_alive_interval
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.keep_alive_timeout = timeout;
+        self
+    }
+
+    /// Sets whether keep-alive PINGs are sent while the connection has no
+    /// open streams.
+    ///
+    /// If disabled, an idle connection is left alone and can be closed
+    /// silently by the peer or an intermediary.
+    ///
+    /// This has no effect unless [`keep_alive_interval`] is set.
+    ///
+    /// The default value is `false`.
+    ///
+    /// See [`timer`] for an example.
+    ///
+    /// [`keep_alive_interval`]: #method.keep_alive_interval
+    /// [`timer`]: #method.timer
+    pub fn keep_alive_while_idle(&mut self, enabled: bool) -> &mut Self {
+        self.keep_alive_while_idle = enabled;
+        self
+    }
+
+    /// Sets an observer to tell about every frame sent and received.
+    ///
+    /// The observer is given a description of each frame, including the type
+    /// specific fields and the payload length but not the payload itself.
+    /// See [`FrameObserver`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use h2::{FrameInfo, FrameObserver};
+    /// #
+    /// #[derive(Debug)]
+    /// struct Tracer;
+    ///
+    /// impl FrameObserver for Tracer {
+    ///     fn frame_received(&self, frame: &FrameInfo) {
+    ///         println!("<- {:?}", frame);
+    ///     }
+    ///
+    ///     fn frame_sent(&self, frame: &FrameInfo) {
+    ///         println!("-> {:?}", frame);
+    ///     }
+    /// }
+    ///
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .frame_observer(Tracer)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`FrameObserver`]: ../trait.FrameObserver.html
+    pub fn frame_observer<O: FrameObserver + 'static>(&mut self, observer: O) -> &mut Self {
+        self.observer = Some(ObserverHandle::new(observer));
+        self
+    }
+
+    /// Registers a frame type to receive as an extension frame.
+    ///
+    /// Frames of an unknown type are ignored by default, as required by the
+    /// specification. Those of a registered type are instead made available
+    /// with [`Connection::poll_extension_frame`], once the maximum frame size
+    /// has been checked. This can be called multiple times, to register
+    /// several types.
+    ///
+    /// The frames received are buffered until they are taken. The peer is
+    /// sent a GOAWAY with `ENHANCE_YOUR_CALM` if more than 1 MiB of them,
+    /// frame headers included, pile up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame_type` is the type of a frame implemented by the
+    /// connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .extension_frame_type(0xb)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Connection::poll_extension_frame`]: struct.Connection.html#method.poll_extension_frame
+    pub fn extension_frame_type(&mut self, frame_type: u8) -> &mut Self {
+        assert!(
+            frame::Kind::new(frame_type) == frame::Kind::Unknown,
+            "frame type {} is implemented by the connection",
+            frame_type
+        );
+
+        if !self.extension_types.contains(&frame_type) {
+            self.extension_types.push(frame_type);
+        }
+        self
+    }
+
+    /// Adds a setting this crate doesn't implement to the initial SETTINGS
+    /// frame.
+    ///
+    /// This is how many extensions are negotiated. Calling this again with
+    /// the same identifier replaces the value. The settings received from
+    /// the peer that this crate doesn't implement are available with
+    /// [`Connection::remote_custom_settings`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is the identifier of a setting implemented by the
+    /// connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .custom_setting(0xf0f0, 1)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Connection::remote_custom_settings`]: struct.Connection.html#method.remote_custom_settings
+    pub fn custom_setting(&mut self, id: u16, value: u32) -> &mut Self {
+        self.settings.set_unknown_setting(id, value);
+        self
+    }
+
+    /// Enables the [extended CONNECT protocol].
+    ///
+    /// This advertises `SETTINGS_ENABLE_CONNECT_PROTOCOL` in the initial
+    /// SETTINGS frame, allowing clients to send CONNECT requests with a
+    /// `:protocol` pseudo-header, for instance to bootstrap WebSockets. The
+    /// protocol of such a request is available as an [`ext::Protocol`] in
+    /// the request extensions.
+    ///
+    /// If this is not enabled, a request carrying `:protocol` is rejected
+    /// with a stream error.
+    ///
+    /// Default value: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .enable_connect_protocol()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [extended CONNECT protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [`ext::Protocol`]: ../ext/struct.Protocol.html
+    pub fn enable_connect_protocol(&mut self) -> &mut Self {
+        self.settings.set_enable_connect_protocol(Some(1));
+        self
+    }
+
+    /// Tells the client that the server does not use the stream priorities
+    /// of RFC 7540.
+    ///
+    /// This sends `SETTINGS_NO_RFC7540_PRIORITIES`, as defined by the
+    /// [extensible priority scheme], so that clients know to signal priority
+    /// with the `priority` header field and `PRIORITY_UPDATE` frames instead.
+    /// Those are always followed when sending responses.
+    ///
+    /// Default value: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .disable_rfc7540_priorities()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [extensible priority scheme]: https://www.rfc-editor.org/rfc/rfc9218.html
+    pub fn disable_rfc7540_priorities(&mut self) -> &mut Self {
+        self.settings.set_no_rfc7540_priorities(Some(1));
+        self
+    }
+
+    /// Creates a new configured HTTP/2.0 server backed by `io`.
+    ///
+    /// It is expected that `io` already be in an appropriate state to commence
+    /// the [HTTP/2.0 handshake]. See [Handshake] for more details.
+    ///
+    /// Returns a future which resolves to the [`Connection`] instance once the
+    /// HTTP/2.0 handshake has been completed.
+    ///
+    /// This function also allows the caller to configure the send payload data
+    /// type. See [Outbound data type] for more details.
+    ///
+    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
+    /// [Handshake]: ../index.html#handshake
+    /// [`Connection`]: struct.Connection.html
+    /// [Outbound data type]: ../index.html#outbound-data-type.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// Configures the send-payload data type. In this case, the outbound data
+    /// type will be `&'static [u8]`.
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T, &'static [u8]>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut: Handshake<_, &'static [u8]> = Builder::new()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake<T, B>(&self, io: T) -> Handshake<T, B>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        Connection::handshake2(io, self.clone())
+    }
+
+    /// Creates a new configured HTTP/2.0 server on a connection upgraded from
+    /// HTTP/1.1.
+    ///
+    /// `request` is the HTTP/1.1 request carrying the `Upgrade: h2c` header
+    /// and the client's settings in the `HTTP2-Settings` header, which the
+    /// caller answered with `101 Switching Protocols`. `io` is the
+    /// connection, positioned right after that response.
+    ///
+    /// The client's settings are applied as if received in a SETTINGS frame,
+    /// and `request` is the first request returned by
+    /// [`Connection::accept`], on stream 1 in the half-closed (remote) state.
+    /// The request body, if any, was received over HTTP/1.1 so the
+    /// [`RecvStream`] is empty. The connection specific headers are removed,
+    /// and the `Host` header provides the authority of the URI if it has
+    /// none.
+    ///
+    /// An error is returned if the request doesn't have exactly one valid
+    /// `HTTP2-Settings` header, or if the `Host` header it relies on isn't a
+    /// valid authority. The handshake then proceeds like [`handshake`].
+    ///
+    /// [`Connection::accept`]: struct.Connection.html#method.accept
+    /// [`RecvStream`]: ../struct.RecvStream.html
+    /// [`handshake`]: #method.handshake
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use http::Request;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T, request: Request<()>)
+    /// # -> Result<Handshake<T>, h2::Error>
+    /// # {
+    /// // `request` was received over HTTP/1.1 and answered with a
+    /// // `101 Switching Protocols` response.
+    /// let server_fut = Builder::new()
+    ///     .handshake_upgraded(my_io, request)?;
+    /// # Ok(server_fut)
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake_upgraded<T, B>(
+        &self,
+        io: T,
+        request: Request<()>,
+    ) -> Result<Handshake<T, B>, crate::Error>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        let settings = {
+            let mut values = request.headers().get_all("http2-settings").iter();
+
+            match (values.next(), values.next()) {
+                (Some(value), None) => Settings::load_http2_settings(value.as_bytes())
+                    .map_err(|_| UserError::InvalidUpgradeRequest)?,
+                _ => return Err(UserError::InvalidUpgradeRequest.into()),
+            }
+        };
+
+        let headers = Peer::convert_upgrade_request(request)?;
+
+        let mut handshake = Connection::handshake2(io, self.clone());
+        handshake.upgrade = Some((settings, headers));
+
+        Ok(handshake)
+    }
+
+    /// Detects whether the connection is HTTP/2.0 with prior knowledge before
+    /// starting the handshake, so that a single listener can also serve
+    /// HTTP/1.1.
+    ///
+    /// Returns a future reading from `io`, without writing to it, until
+    /// either the 24 octets of the client preface are received or the bytes
+    /// received so far differ from it. It resolves to
+    /// [`Sniffed::Http2`] with the handshake in progress, configured by this
+    /// builder, or to [`Sniffed::Other`] with `io` and the bytes read.
+    ///
+    /// [`Sniffed::Http2`]: enum.Sniffed.html#variant.Http2
+    /// [`Sniffed::Other`]: enum.Sniffed.html#variant.Other
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<(), h2::Error>
+    /// # {
+    /// match Builder::new().sniff(my_io).await? {
+    ///     Sniffed::Http2(handshake) => {
+    ///         let connection: Connection<_, Bytes> = handshake.await?;
+    ///         // Accept HTTP/2.0 streams on `connection`.
+    ///     }
+    ///     Sniffed::Other(io, read) => {
+    ///         // Serve HTTP/1.1 on `io`, starting with the bytes in `read`.
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn sniff<T, B>(&self, io: T) -> Sniff<T, B>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        Sniff {
+            builder: self.clone(),
+            io: Some(io),
+            buf: [0; 24],
+            pos: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn keep_alive_config(&self) -> Result<Option<proto::KeepAliveConfig>, UserError> {
+        let interval = match self.keep_alive_interval {
+            Some(interval) => interval,
+            None => return Ok(None),
+        };
+        let timer = self.timer.clone().ok_or(UserError::MissingTimer)?;
+
+        Ok(Some(proto::KeepAliveConfig {
+            interval,
+            timeout: self.keep_alive_timeout,
+            while_idle: self.keep_alive_while_idle,
+            timer,
+        }))
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder::new()
     }
 }
 
@@ -963,6 +2091,60 @@ impl<B: Buf> SendResponse<B> {
             .map_err(Into::into)
     }
 
+    /// Send an informational (1xx) response to a client request.
+    ///
+    /// Informational responses, such as `100 Continue` or `103 Early Hints`,
+    /// precede the final response, which is still to be sent with
+    /// [`send_response`]. This function may be called any number of times
+    /// before that.
+    ///
+    /// The status of `response` must be in the 1xx range. `101 Switching
+    /// Protocols` is not allowed, as HTTP/2.0 has no upgrade mechanism.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::server::SendResponse;
+    /// # use bytes::Bytes;
+    /// # use http::{Response, StatusCode};
+    /// #
+    /// # fn doc(respond: &mut SendResponse<Bytes>) -> Result<(), h2::Error> {
+    /// let hints = Response::builder()
+    ///     .status(StatusCode::from_u16(103).unwrap())
+    ///     .header("link", "</style.css>; rel=preload; as=style")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// respond.send_informational(hints)?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`send_response`]: #method.send_response
+    pub fn send_informational(&mut self, response: Response<()>) -> Result<(), crate::Error> {
+        let status = response.status();
+
+        if !status.is_informational() || status == StatusCode::SWITCHING_PROTOCOLS {
+            return Err(UserError::InvalidInformationalStatusCode.into());
+        }
+
+        self.inner.send_informational(response).map_err(Into::into)
+    }
+
+    /// Advertises alternative services for the origin of the request, in an
+    /// ALTSVC frame sent on this stream (RFC 7838 §4).
+    ///
+    /// The value has the syntax of the `Alt-Svc` header field, such as
+    /// `h3=":443"; ma=3600`, or `clear`. Unlike the header, the frame reaches
+    /// the client before the response is complete. This returns an error
+    /// once the response was fully sent.
+    pub fn send_alt_svc(&mut self, value: &HeaderValue) -> Result<(), crate::Error> {
+        let value = Bytes::copy_from_slice(value.as_bytes());
+        self.inner.send_alt_svc(value).map_err(Into::into)
+    }
+
     /// Push a request and response to the client
     ///
     /// On success, a [`SendResponse`] instance is returned.
@@ -1025,6 +2207,55 @@ impl<B: Buf> SendResponse<B> {
     }
 }
 
+impl SendResponse<Bytes> {
+    /// Accepts a CONNECT request by sending a 2xx response, and returns the
+    /// [`Tunnel`] to the client.
+    ///
+    /// `request` is the request this `SendResponse` was returned with.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `request` is not a CONNECT or `response` is not 2xx, in
+    /// addition to the errors of [`send_response`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use futures_io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use http::*;
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(mut connection: Connection<T, bytes::Bytes>)
+    /// # {
+    /// while let Some(result) = connection.accept().await {
+    ///     let (request, mut respond) = result.unwrap();
+    ///
+    ///     if request.method() == Method::CONNECT {
+    ///         let response = Response::new(());
+    ///         let tunnel = respond.accept_tunnel(request, response).unwrap();
+    ///         // Use `tunnel` as an `AsyncRead + AsyncWrite`.
+    ///     }
+    /// }
+    /// # }
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [`Tunnel`]: ../struct.Tunnel.html
+    /// [`send_response`]: #method.send_response
+    pub fn accept_tunnel(
+        &mut self,
+        request: Request<RecvStream>,
+        response: Response<()>,
+    ) -> Result<Tunnel, crate::Error> {
+        if request.method() != Method::CONNECT || !response.status().is_success() {
+            return Err(UserError::InvalidTunnel.into());
+        }
+
+        let send = self.send_response(response, false)?;
+
+        Ok(Tunnel::new(send, request.into_body()))
+    }
+}
+
 // ===== impl SendPushedResponse =====
 
 impl<B: Buf> SendPushedResponse<B> {
@@ -1169,6 +2400,77 @@ where
     }
 }
 
+// ===== impl Sniff =====
+
+impl<T, B> Future for Sniff<T, B>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    B: Buf + 'static,
+{
+    type Output = Result<Sniffed<T, B>, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = &mut *self;
+
+        while me.pos < PREFACE.len() {
+            let io = me.io.as_mut().expect("Sniff polled after completion");
+            let n = ready!(Pin::new(io).poll_read(cx, &mut me.buf[me.pos..]))
+                .map_err(crate::Error::from_io)?;
+
+            let end = me.pos + n;
+
+            if n == 0 || PREFACE[me.pos..end] != me.buf[me.pos..end] {
+                log::debug!("sniff; not HTTP/2.0; read={}", end);
+                let read = Bytes::copy_from_slice(&me.buf[..end]);
+                return Poll::Ready(Ok(Sniffed::Other(me.io.take().unwrap(), read)));
+            }
+
+            me.pos = end;
+        }
+
+        log::debug!("sniff; received client preface");
+
+        let io = me.io.take().expect("Sniff polled after completion");
+        let mut handshake = Connection::handshake2(io, me.builder.clone());
+        handshake.preface_read = true;
+
+        Poll::Ready(Ok(Sniffed::Http2(Box::new(handshake))))
+    }
+}
+
+// `B` is only a marker for the handshake to create.
+impl<T: Unpin, B: Buf> Unpin for Sniff<T, B> {}
+
+impl<T, B> fmt::Debug for Sniff<T, B>
+where
+    T: fmt::Debug,
+    B: Buf,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Sniff")
+            .field("io", &self.io)
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
+
+impl<T, B> fmt::Debug for Sniffed<T, B>
+where
+    T: fmt::Debug,
+    B: Buf,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Sniffed::Http2(_) => write!(fmt, "Sniffed::Http2(_)"),
+            Sniffed::Other(ref io, ref read) => fmt
+                .debug_tuple("Sniffed::Other")
+                .field(io)
+                .field(read)
+                .finish(),
+        }
+    }
+}
+
 // ===== impl Handshake =====
 
 impl<T, B: Buf> Future for Handshake<T, B>
@@ -1196,7 +2498,11 @@ where
                     flushed
                 }
             };
-            Handshaking::from(ReadPreface::new(codec))
+            let mut read = ReadPreface::new(codec);
+            if self.preface_read {
+                read.pos = PREFACE.len();
+            }
+            Handshaking::from(read)
         } else {
             // Otherwise, we haven't actually advanced the state, but we have
             // to replace it with itself, because we have to return a value.
@@ -1225,15 +2531,32 @@ where
                     initial_max_send_streams: 0,
                     reset_stream_duration: self.builder.reset_stream_duration,
                     reset_stream_max: self.builder.reset_stream_max,
+                    max_send_buffer_size: self.builder.max_send_buffer_size,
+                    auto_release_capacity: self.builder.auto_release_capacity,
+                    max_recv_buffer_size: self.builder.max_recv_buffer_size,
+                    remote_reset_limit: Some((
+                        self.builder.remote_reset_stream_max,
+                        self.builder.remote_reset_stream_duration,
+                    )),
                     settings: self.builder.settings.clone(),
+                    keep_alive: self.builder.keep_alive_config()?,
+                    max_adaptive_window_size: self.builder.max_adaptive_window_size,
+                    flood: self.builder.flood.clone(),
+                    timer: self.builder.timer.clone(),
                 },
             );
 
             log::trace!("Handshake::poll(); connection established!");
-            let mut c = Connection { connection };
+            let mut c = Connection {
+                connection,
+                remote_settings_seen: 0,
+            };
             if let Some(sz) = self.builder.initial_target_connection_window_size {
                 c.set_target_window_size(sz);
             }
+            if let Some((settings, headers)) = self.upgrade.take() {
+                c.connection.recv_upgrade(&settings, headers)?;
+            }
             Ok(c)
         })
     }
@@ -1250,6 +2573,61 @@ where
 }
 
 impl Peer {
+    /// Converts an HTTP/1.1 request that was upgraded to HTTP/2.0 into the
+    /// HEADERS frame that would have opened stream 1.
+    pub fn convert_upgrade_request(request: Request<()>) -> Result<frame::Headers, crate::Error> {
+        use http::header;
+
+        let (mut parts, ()) = request.into_parts();
+
+        // 8.1.2.2. Connection-Specific Header Fields
+        let named: Vec<header::HeaderName> = parts
+            .headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|name| name.trim().parse().ok())
+            .collect();
+
+        for name in named {
+            parts.headers.remove(name);
+        }
+
+        for name in &[
+            "connection",
+            "upgrade",
+            "http2-settings",
+            "keep-alive",
+            "proxy-connection",
+            "transfer-encoding",
+        ] {
+            parts.headers.remove(*name);
+        }
+
+        if parts.uri.authority().is_none() {
+            if let Some(host) = parts.headers.get(header::HOST) {
+                let path = parts
+                    .uri
+                    .path_and_query()
+                    .map(|path| path.as_str())
+                    .unwrap_or("/");
+
+                let host = host
+                    .to_str()
+                    .map_err(|_| UserError::InvalidUpgradeRequest)?;
+
+                parts.uri = format!("http://{}{}", host, path)
+                    .parse()
+                    .map_err(|_| UserError::InvalidUpgradeRequest)?;
+            }
+        }
+
+        let request = Request::from_parts(parts, ());
+
+        crate::client::Peer::convert_send_message(1.into(), request, true).map_err(Into::into)
+    }
+
     pub fn convert_send_message(
         id: StreamId,
         response: Response<()>,
@@ -1312,7 +2690,7 @@ impl Peer {
             _,
         ) = request.into_parts();
 
-        let pseudo = Pseudo::request(method, uri);
+        let pseudo = Pseudo::request(method, uri, None);
 
         Ok(frame::PushPromise::new(
             stream_id,
@@ -1365,6 +2743,16 @@ impl proto::Peer for Peer {
             malformed!("malformed headers: missing method");
         }
 
+        // An extended CONNECT (RFC 8441) carries a :protocol, and then
+        // requires :scheme and :path like any other request.
+        let has_protocol = pseudo.protocol.is_some();
+        if let Some(protocol) = pseudo.protocol {
+            if !is_connect {
+                malformed!("malformed headers: :protocol on non-CONNECT request");
+            }
+            b = b.extension(protocol);
+        }
+
         // Specifying :status for a request is a protocol error
         if pseudo.status.is_some() {
             log::trace!("malformed headers: :status field on request; PROTOCOL_ERROR");
@@ -1389,7 +2777,7 @@ impl proto::Peer for Peer {
 
         // A :scheme is required, except CONNECT.
         if let Some(scheme) = pseudo.scheme {
-            if is_connect {
+            if is_connect && !has_protocol {
                 malformed!(":scheme in CONNECT");
             }
             let maybe_scheme = scheme.parse();
@@ -1407,12 +2795,12 @@ impl proto::Peer for Peer {
             if parts.authority.is_some() {
                 parts.scheme = Some(scheme);
             }
-        } else if !is_connect {
+        } else if !is_connect || has_protocol {
             malformed!("malformed headers: missing scheme");
         }
 
         if let Some(path) = pseudo.path {
-            if is_connect {
+            if is_connect && !has_protocol {
                 malformed!(":path in CONNECT");
             }
 
@@ -1425,6 +2813,8 @@ impl proto::Peer for Peer {
             parts.path_and_query = Some(maybe_path.or_else(|why| {
                 malformed!("malformed headers: malformed path ({:?}): {}", path, why,)
             })?);
+        } else if has_protocol {
+            malformed!("malformed headers: missing path in extended CONNECT");
         }
 
         b = b.uri(parts);
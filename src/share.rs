@@ -1,15 +1,18 @@
 use crate::codec::UserError;
-use crate::frame::Reason;
+use crate::frame::{self, Reason};
 use crate::proto::{self, WindowSize};
+use crate::stats::StreamStats;
 
 use bytes::{Buf, Bytes};
+use futures_io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "stream")]
+use futures_sink::Sink;
 use http::HeaderMap;
 
 use crate::PollExt;
-use std::fmt;
-#[cfg(feature = "stream")]
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::{cmp, fmt, io};
 
 /// Sends the body stream and trailers to the remote peer.
 ///
@@ -54,10 +57,11 @@ use std::task::{Context, Poll};
 /// window capacity, the library will buffer the data until capacity becomes
 /// available, at which point the buffer will be flushed to the connection.
 ///
-/// **NOTE**: There is no bound on the amount of data that the library will
-/// buffer. If you are sending large amounts of data, you really should hook
-/// into the flow control lifecycle. Otherwise, you risk using up significant
-/// amounts of memory.
+/// The capacity assigned to a stream is capped by the maximum send buffer
+/// size of the connection, but `send_data` itself does not enforce it. If you
+/// are sending large amounts of data, you really should hook into the flow
+/// control lifecycle, or wait on [`poll_send_buffer_ready`] before sending
+/// more. Otherwise, you risk using up significant amounts of memory.
 ///
 /// To hook into the flow control lifecycle, the caller signals to the library
 /// that it intends to send data by calling [`reserve_capacity`], specifying the
@@ -88,6 +92,7 @@ use std::task::{Context, Poll};
 /// [specification]: http://httpwg.org/specs/rfc7540.html#FlowControl
 /// [`reserve_capacity`]: #method.reserve_capacity
 /// [`poll_capacity`]: #method.poll_capacity
+/// [`poll_send_buffer_ready`]: #method.poll_send_buffer_ready
 /// [`send_data`]: #method.send_data
 /// [`send_request`]: client/struct.SendRequest.html#method.send_request
 /// [`send_response`]: server/struct.SendResponse.html#method.send_response
@@ -112,6 +117,31 @@ pub struct SendStream<B: Buf> {
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct StreamId(u32);
 
+/// The priority of a stream, as described in [Section 5.3] of RFC 7540.
+///
+/// Each stream can depend on another stream, or on the connection as a whole.
+/// Streams that depend on another stream are only sent data when the stream
+/// they depend on cannot make progress. Streams depending on the same parent
+/// share the available bandwidth in proportion to their weight, which is in
+/// the range 1 to 256.
+///
+/// An exclusive dependency makes the stream the sole dependency of its parent,
+/// with the other dependencies of the parent moved to depend on the stream.
+///
+/// A priority can be given to a request by inserting it in the extensions of
+/// the `http::Request` passed to [`SendRequest::send_request`], or be changed
+/// later on with [`SendStream::set_priority`].
+///
+/// [Section 5.3]: https://tools.ietf.org/html/rfc7540#section-5.3
+/// [`SendRequest::send_request`]: client/struct.SendRequest.html#method.send_request
+/// [`SendStream::set_priority`]: struct.SendStream.html#method.set_priority
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StreamPriority {
+    dependency: Option<StreamId>,
+    weight: u16,
+    is_exclusive: bool,
+}
+
 /// Receives the body stream and trailers from the remote peer.
 ///
 /// A `RecvStream` is provided by [`client::ResponseFuture`] and
@@ -142,7 +172,9 @@ pub struct RecvStream {
 /// A handle to release window capacity to a remote stream.
 ///
 /// This type allows the caller to manage inbound data [flow control]. The
-/// caller is expected to call [`release_capacity`] after dropping data frames.
+/// caller is expected to call [`release_capacity`] after dropping data frames,
+/// unless capacity is released automatically (see
+/// [`set_auto_release_capacity`]).
 ///
 /// # Overview
 ///
@@ -191,11 +223,78 @@ pub struct RecvStream {
 ///
 /// [flow control]: ../index.html#flow-control
 /// [`release_capacity`]: struct.FlowControl.html#method.release_capacity
+/// [`set_auto_release_capacity`]: struct.FlowControl.html#method.set_auto_release_capacity
 #[derive(Clone, Debug)]
 pub struct FlowControl {
     inner: proto::OpaqueStreamRef,
 }
 
+/// Writes the body of a stream as an [`AsyncWrite`].
+///
+/// Created with [`SendStream::into_writer`]. Each write waits for send
+/// capacity, reserving it as needed, and sends as much of the buffer as the
+/// capacity allows. Closing the writer ends the stream with `END_STREAM`.
+///
+/// Flushing doesn't wait for the data to be written to the connection, which
+/// happens as the connection is polled.
+///
+/// [`AsyncWrite`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncWrite.html
+/// [`SendStream::into_writer`]: struct.SendStream.html#method.into_writer
+#[derive(Debug)]
+pub struct SendStreamWriter {
+    inner: SendStream<Bytes>,
+    is_closed: bool,
+}
+
+/// Reads the body of a stream as an [`AsyncRead`].
+///
+/// Created with [`RecvStream::into_reader`], which enables automatic capacity
+/// release on the stream, so the peer may keep sending while it is read.
+/// Reading returns 0 bytes at the end of the stream, after which trailers may
+/// be received through [`get_mut`].
+///
+/// [`AsyncRead`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncRead.html
+/// [`RecvStream::into_reader`]: struct.RecvStream.html#method.into_reader
+/// [`get_mut`]: #method.get_mut
+#[derive(Debug)]
+pub struct RecvStreamReader {
+    inner: RecvStream,
+    /// Data received but not yet read.
+    buf: Bytes,
+}
+
+/// A tunnel over the stream of a CONNECT request, as an [`AsyncRead`] and
+/// [`AsyncWrite`] of bytes.
+///
+/// Returned by [`client::SendRequest::send_tunnel`] and
+/// [`server::SendResponse::accept_tunnel`], or created from the stream pair
+/// of a CONNECT with [`Tunnel::new`]. Writes go through a
+/// [`SendStreamWriter`] and reads through a [`RecvStreamReader`], so capacity
+/// is managed by the tunnel.
+///
+/// Closing the tunnel for writing sends `END_STREAM`, while data may still
+/// be read until the peer ends its side. A reset of the stream fails reads
+/// with an `io::Error` of kind `ConnectionReset`, which wraps the [`Error`]
+/// with the reason. Writes fail the same way, except that a write racing
+/// with the reset, using capacity assigned before it, may fail with an
+/// `io::Error` of kind `Other` instead, which wraps the [`Error`] of sending
+/// on a closed stream. Dropping the tunnel before closing it resets the
+/// stream.
+///
+/// [`AsyncRead`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncRead.html
+/// [`AsyncWrite`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncWrite.html
+/// [`client::SendRequest::send_tunnel`]: client/struct.SendRequest.html#method.send_tunnel
+/// [`server::SendResponse::accept_tunnel`]: server/struct.SendResponse.html#method.accept_tunnel
+/// [`Tunnel::new`]: #method.new
+/// [`SendStreamWriter`]: struct.SendStreamWriter.html
+/// [`RecvStreamReader`]: struct.RecvStreamReader.html
+/// [`Error`]: struct.Error.html
+#[derive(Debug)]
+pub struct Tunnel {
+    writer: SendStreamWriter,
+    reader: RecvStreamReader,
+}
+
 /// A handle to send and receive PING frames with the peer.
 // NOT Clone on purpose
 pub struct PingPong {
@@ -309,6 +408,10 @@ impl<B: Buf> SendStream<B> {
     /// amount of assigned capacity at that point in time. It is also possible
     /// that `n` is lower than the previous call if, since then, the caller has
     /// sent data.
+    ///
+    /// The assigned capacity is capped by the maximum send buffer size of the
+    /// connection, see `max_send_buffer_size` on the client and server
+    /// builders.
     pub fn poll_capacity(&mut self, cx: &mut Context) -> Poll<Option<Result<usize, crate::Error>>> {
         self.inner
             .poll_capacity(cx)
@@ -316,6 +419,20 @@ impl<B: Buf> SendStream<B> {
             .map_err_(Into::into)
     }
 
+    /// Polls to be notified when more data may be buffered on the stream.
+    ///
+    /// Data passed to `send_data` stays buffered until it can be written to
+    /// the connection. This returns `Ready(Ok(()))` while less than the
+    /// maximum send buffer size is buffered, and otherwise waits until
+    /// buffered data has been written. This lets a producer apply
+    /// backpressure without managing capacity itself.
+    ///
+    /// An error is returned if the stream can no longer send data, for example
+    /// because it was reset.
+    pub fn poll_send_buffer_ready(&mut self, cx: &mut Context) -> Poll<Result<(), crate::Error>> {
+        self.inner.poll_send_buffer_ready(cx).map_err(Into::into)
+    }
+
     /// Sends a single data frame to the remote peer.
     ///
     /// This function may be called repeatedly as long as `end_of_stream` is set
@@ -380,6 +497,235 @@ impl<B: Buf> SendStream<B> {
     pub fn stream_id(&self) -> StreamId {
         StreamId::from_internal(self.inner.stream_id())
     }
+
+    /// Changes the priority of the stream.
+    ///
+    /// The stream is moved in the local dependency tree, which decides the
+    /// order in which data is sent, and a `PRIORITY` frame is sent to let
+    /// the remote know.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream is made to depend on itself.
+    pub fn set_priority(&mut self, priority: StreamPriority) -> Result<(), crate::Error> {
+        self.inner
+            .set_priority(priority.into_dependency())
+            .map_err(Into::into)
+    }
+
+    /// Changes the urgency of the stream, as defined by the extensible
+    /// priority scheme of RFC 9218.
+    ///
+    /// `urgency` ranges from 0 to 7, lower values being more urgent, and
+    /// `incremental` tells whether the response is useful before it has been
+    /// received in full. The default is an urgency of 3, not incremental.
+    ///
+    /// Once any stream has been given an urgency, data is sent by urgency
+    /// rather than by the dependency tree of [`set_priority`]. On the client,
+    /// a `PRIORITY_UPDATE` frame is also sent, asking the server to
+    /// reprioritize the response.
+    ///
+    /// # Panics
+    ///
+    /// If `urgency` is greater than 7.
+    ///
+    /// [`set_priority`]: #method.set_priority
+    pub fn set_urgency(&mut self, urgency: u8, incremental: bool) {
+        assert!(urgency <= 7, "urgency must be in 0..=7");
+
+        self.inner
+            .update_priority(frame::PriorityParams::new(urgency, incremental));
+    }
+
+    /// Returns a snapshot of the statistics of this stream.
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn stats(&self) -> StreamStats {
+        self.inner.stats()
+    }
+}
+
+/// Sends each item as a data frame, and closing sends `END_STREAM`.
+///
+/// Readiness follows both the send buffer and the flow control capacity of
+/// the stream. The sink first waits, like [`poll_send_buffer_ready`], for
+/// less than the maximum send buffer size to be buffered. It then requests
+/// capacity for up to a frame of data beyond what is buffered, and is ready
+/// once some of it was assigned. An item larger than the capacity is still
+/// accepted, and the sink is then not ready again until all of it can be
+/// written, so that the data buffered stays bounded by the item sizes.
+/// Flushing doesn't wait for the data to be written to the connection,
+/// which happens as the connection is polled.
+///
+/// Closing releases the capacity requested, and sends an empty data frame,
+/// which is why the items must implement `Default`. Closing a stream that
+/// already ended with `END_STREAM` does nothing.
+///
+/// [`poll_send_buffer_ready`]: struct.SendStream.html#method.poll_send_buffer_ready
+#[cfg(feature = "stream")]
+impl<B: Buf + Default> Sink<B> for SendStream<B> {
+    type Error = crate::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Err(e) = ready!(self.poll_send_buffer_ready(cx)) {
+            return Poll::Ready(Err(closed_error(&mut self, cx, e)));
+        }
+
+        loop {
+            if self.capacity() > 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            self.reserve_capacity(frame::DEFAULT_MAX_FRAME_SIZE as usize);
+
+            match ready!(self.poll_capacity(cx)) {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Poll::Ready(Err(e)),
+                None => {
+                    let e = UserError::InactiveStreamId.into();
+                    return Poll::Ready(Err(closed_error(&mut self, cx, e)));
+                }
+            }
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: B) -> Result<(), Self::Error> {
+        self.send_data(item, false)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        if self.inner.is_send_end_stream() {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Don't hold on to capacity that won't be used.
+        self.reserve_capacity(0);
+        Poll::Ready(self.send_data(B::default(), true))
+    }
+}
+
+impl SendStream<Bytes> {
+    /// Converts the stream into an [`AsyncWrite`] of the body.
+    ///
+    /// See [`SendStreamWriter`] for how capacity and closing are handled.
+    ///
+    /// [`AsyncWrite`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncWrite.html
+    /// [`SendStreamWriter`]: struct.SendStreamWriter.html
+    pub fn into_writer(self) -> SendStreamWriter {
+        SendStreamWriter {
+            inner: self,
+            is_closed: false,
+        }
+    }
+}
+
+// ===== impl SendStreamWriter =====
+
+impl SendStreamWriter {
+    /// Returns a mutable reference to the underlying `SendStream`.
+    pub fn get_mut(&mut self) -> &mut SendStream<Bytes> {
+        &mut self.inner
+    }
+
+    /// Returns the underlying `SendStream`.
+    pub fn into_inner(self) -> SendStream<Bytes> {
+        self.inner
+    }
+
+    /// Returns the error for a stream that can no longer send data.
+    fn closed_error(&mut self, cx: &mut Context) -> io::Error {
+        match self.inner.poll_reset(cx) {
+            Poll::Ready(Ok(reason)) => into_io_error(reason.into()),
+            Poll::Ready(Err(e)) => into_io_error(e),
+            Poll::Pending => io::Error::new(io::ErrorKind::BrokenPipe, "stream closed"),
+        }
+    }
+}
+
+impl AsyncWrite for SendStreamWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        if self.is_closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        self.inner.reserve_capacity(buf.len());
+
+        loop {
+            let capacity = self.inner.capacity();
+
+            if capacity > 0 {
+                let len = cmp::min(capacity, buf.len());
+                let data = Bytes::copy_from_slice(&buf[..len]);
+                self.inner.send_data(data, false).map_err(into_io_error)?;
+                return Poll::Ready(Ok(len));
+            }
+
+            match ready!(self.inner.poll_capacity(cx)) {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Poll::Ready(Err(into_io_error(e))),
+                None => return Poll::Ready(Err(self.closed_error(cx))),
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !self.is_closed {
+            // Don't hold on to capacity that won't be used.
+            self.inner.reserve_capacity(0);
+            self.inner
+                .send_data(Bytes::new(), true)
+                .map_err(into_io_error)?;
+            self.is_closed = true;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Returns the error for a stream that can no longer send data, which is the
+/// reason it was reset with if any, or else `err`.
+#[cfg(feature = "stream")]
+fn closed_error<B: Buf>(
+    stream: &mut SendStream<B>,
+    cx: &mut Context,
+    err: crate::Error,
+) -> crate::Error {
+    match stream.poll_reset(cx) {
+        Poll::Ready(Ok(reason)) => reason.into(),
+        Poll::Ready(Err(e)) => e,
+        Poll::Pending => err,
+    }
+}
+
+fn into_io_error(err: crate::Error) -> io::Error {
+    if err.is_io() {
+        err.into_io().expect("is_io")
+    } else if err.reason().is_some() {
+        io::Error::new(io::ErrorKind::ConnectionReset, err)
+    } else {
+        io::Error::other(err)
+    }
 }
 
 // ===== impl StreamId =====
@@ -388,6 +734,84 @@ impl StreamId {
     pub(crate) fn from_internal(id: crate::frame::StreamId) -> Self {
         StreamId(id.into())
     }
+
+    pub(crate) fn to_internal(&self) -> crate::frame::StreamId {
+        crate::frame::StreamId::from(self.0)
+    }
+
+    /// Returns the identifier of the connection as a whole, stream 0.
+    pub fn zero() -> Self {
+        StreamId(0)
+    }
+
+    /// Returns the identifier as an integer.
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+// ===== impl StreamPriority =====
+
+impl StreamPriority {
+    /// Creates a priority with the given weight, depending on the connection
+    /// as a whole.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` is not in the range 1 to 256.
+    pub fn new(weight: u16) -> Self {
+        assert!((1..=256).contains(&weight), "weight must be in 1..=256");
+
+        StreamPriority {
+            dependency: None,
+            weight,
+            is_exclusive: false,
+        }
+    }
+
+    /// Makes the stream depend on `stream`.
+    pub fn depends_on(mut self, stream: StreamId) -> Self {
+        self.dependency = Some(stream);
+        self
+    }
+
+    /// Makes the dependency exclusive.
+    pub fn exclusive(mut self) -> Self {
+        self.is_exclusive = true;
+        self
+    }
+
+    /// Returns the stream this stream depends on, if any.
+    pub fn dependency(&self) -> Option<&StreamId> {
+        self.dependency.as_ref()
+    }
+
+    /// Returns the weight, in the range 1 to 256.
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Returns true if the dependency is exclusive.
+    pub fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
+
+    pub(crate) fn into_dependency(self) -> frame::StreamDependency {
+        let dependency_id = self
+            .dependency
+            .map(|id| frame::StreamId::from(id.0))
+            .unwrap_or(frame::StreamId::ZERO);
+
+        frame::StreamDependency::new(dependency_id, (self.weight - 1) as u8, self.is_exclusive)
+    }
+}
+
+impl Default for StreamPriority {
+    /// The default priority of a stream: depending on the connection, with a
+    /// weight of 16.
+    fn default() -> Self {
+        StreamPriority::new(16)
+    }
 }
 // ===== impl RecvStream =====
 
@@ -446,6 +870,30 @@ impl RecvStream {
     pub fn stream_id(&self) -> StreamId {
         self.inner.stream_id()
     }
+
+    /// Returns a snapshot of the statistics of this stream.
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn stats(&self) -> StreamStats {
+        self.inner.inner.stats()
+    }
+
+    /// Converts the stream into an [`AsyncRead`] of the body.
+    ///
+    /// See [`RecvStreamReader`] for how capacity is released.
+    ///
+    /// [`AsyncRead`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncRead.html
+    /// [`RecvStreamReader`]: struct.RecvStreamReader.html
+    pub fn into_reader(mut self) -> RecvStreamReader {
+        self.inner.set_auto_release_capacity(true);
+
+        RecvStreamReader {
+            inner: self,
+            buf: Bytes::new(),
+        }
+    }
 }
 
 #[cfg(feature = "stream")]
@@ -476,6 +924,106 @@ impl Drop for RecvStream {
     }
 }
 
+// ===== impl RecvStreamReader =====
+
+impl RecvStreamReader {
+    /// Returns a mutable reference to the underlying `RecvStream`.
+    ///
+    /// Data read from it directly bypasses the reader.
+    pub fn get_mut(&mut self) -> &mut RecvStream {
+        &mut self.inner
+    }
+
+    /// Returns the underlying `RecvStream`, dropping any data received but
+    /// not yet read.
+    pub fn into_inner(self) -> RecvStream {
+        self.inner
+    }
+}
+
+impl AsyncRead for RecvStreamReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        while self.buf.is_empty() {
+            match ready!(self.inner.poll_data(cx)) {
+                Some(Ok(data)) => self.buf = data,
+                Some(Err(e)) => return Poll::Ready(Err(into_io_error(e))),
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+
+        let len = cmp::min(buf.len(), self.buf.len());
+        buf[..len].copy_from_slice(&self.buf[..len]);
+        self.buf.advance(len);
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+// ===== impl Tunnel =====
+
+impl Tunnel {
+    /// Creates a tunnel from the send and receive halves of a CONNECT stream.
+    pub fn new(send: SendStream<Bytes>, recv: RecvStream) -> Self {
+        Tunnel {
+            writer: send.into_writer(),
+            reader: recv.into_reader(),
+        }
+    }
+
+    /// Returns the stream ID of the tunnel.
+    pub fn stream_id(&self) -> StreamId {
+        self.reader.inner.stream_id()
+    }
+
+    /// Resets the stream, closing both directions of the tunnel.
+    ///
+    /// A proxy sends `CONNECT_ERROR` when the connection it tunnels to fails.
+    pub fn send_reset(&mut self, reason: Reason) {
+        self.writer.get_mut().send_reset(reason)
+    }
+
+    /// Splits the tunnel into its writing and reading halves.
+    pub fn into_parts(self) -> (SendStreamWriter, RecvStreamReader) {
+        (self.writer, self.reader)
+    }
+}
+
+impl AsyncRead for Tunnel {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Tunnel {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_close(cx)
+    }
+}
+
 // ===== impl FlowControl =====
 
 impl FlowControl {
@@ -525,6 +1073,21 @@ impl FlowControl {
             .release_capacity(sz as proto::WindowSize)
             .map_err(Into::into)
     }
+
+    /// Sets whether capacity is released automatically for this stream,
+    /// overriding the `auto_release_capacity` setting of the connection.
+    ///
+    /// When enabled, the capacity of each data frame is released as soon as
+    /// `RecvStream` hands it to the caller. Enabling it also releases the
+    /// capacity of the data already taken but not released.
+    pub fn set_auto_release_capacity(&mut self, enabled: bool) {
+        self.inner.set_auto_release_capacity(enabled)
+    }
+
+    /// Returns true if capacity is released automatically for this stream.
+    pub fn is_auto_release_capacity(&self) -> bool {
+        self.inner.is_auto_release_capacity()
+    }
 }
 
 // ===== impl PingPong =====
@@ -0,0 +1,147 @@
+use crate::share::StreamId;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A future resolving once a graceful shutdown with a deadline is over.
+///
+/// Returned by the `graceful_shutdown_with_timeout` functions of
+/// [`client::Connection`] and [`server::Connection`]. The connection must
+/// still be polled for the shutdown to make progress.
+///
+/// [`client::Connection`]: client/struct.Connection.html
+/// [`server::Connection`]: server/struct.Connection.html
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct GracefulShutdown {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// The outcome of a graceful shutdown, as resolved by [`GracefulShutdown`].
+///
+/// [`GracefulShutdown`]: struct.GracefulShutdown.html
+#[derive(Debug, Clone)]
+pub struct ShutdownReport {
+    timed_out: bool,
+    cut_off_streams: Vec<StreamId>,
+}
+
+/// The connection side of a `GracefulShutdown`.
+#[derive(Debug)]
+pub(crate) struct ShutdownNotifier {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    report: Option<ShutdownReport>,
+    /// Tasks polling a `GracefulShutdown`, which is returned again when the
+    /// shutdown is started more than once.
+    tasks: Vec<Waker>,
+}
+
+// ===== impl GracefulShutdown =====
+
+impl Future for GracefulShutdown {
+    type Output = ShutdownReport;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.report {
+            Some(ref report) => Poll::Ready(report.clone()),
+            None => {
+                if !inner.tasks.iter().any(|task| task.will_wake(cx.waker())) {
+                    inner.tasks.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// ===== impl ShutdownReport =====
+
+impl ShutdownReport {
+    /// Returns true if the deadline passed before all the streams completed,
+    /// so that the connection was shut down abruptly.
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Returns the streams that were still active when the deadline passed,
+    /// or when the connection failed or was dropped before the shutdown was
+    /// over.
+    pub fn cut_off_streams(&self) -> &[StreamId] {
+        &self.cut_off_streams
+    }
+}
+
+// ===== impl ShutdownNotifier =====
+
+impl ShutdownNotifier {
+    pub(crate) fn new() -> Self {
+        ShutdownNotifier {
+            inner: Arc::new(Mutex::new(Inner::default())),
+        }
+    }
+
+    pub(crate) fn handle(&self) -> GracefulShutdown {
+        GracefulShutdown {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Resolves the `GracefulShutdown` futures, unless it was already done.
+    pub(crate) fn finish(&self, timed_out: bool, cut_off_streams: Vec<crate::frame::StreamId>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.report.is_some() {
+            return;
+        }
+
+        inner.report = Some(ShutdownReport {
+            timed_out,
+            cut_off_streams: cut_off_streams
+                .into_iter()
+                .map(StreamId::from_internal)
+                .collect(),
+        });
+
+        for task in inner.tasks.drain(..) {
+            task.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures_util::task::noop_waker_ref;
+
+    #[test]
+    fn test_finish_once() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let notifier = ShutdownNotifier::new();
+        let mut first = notifier.handle();
+
+        assert!(Pin::new(&mut first).poll(&mut cx).is_pending());
+
+        notifier.finish(true, vec![1.into(), 3.into()]);
+        notifier.finish(false, vec![]);
+
+        let mut second = notifier.handle();
+        for handle in [&mut first, &mut second].iter_mut() {
+            match Pin::new(&mut **handle).poll(&mut cx) {
+                Poll::Ready(report) => {
+                    assert!(report.is_timed_out());
+                    assert_eq!(report.cut_off_streams().len(), 2);
+                }
+                Poll::Pending => panic!("shutdown not finished"),
+            }
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use crate::frame::{Kind, Reason};
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// A snapshot of the statistics of a connection.
+///
+/// Returned by the `stats` functions of [`client::Connection`],
+/// [`client::SendRequest`] and [`server::Connection`].
+///
+/// The byte and frame counts are those of the frames, including the 9 byte
+/// frame headers. The connection preface is not included.
+///
+/// [`client::Connection`]: client/struct.Connection.html
+/// [`client::SendRequest`]: client/struct.SendRequest.html
+/// [`server::Connection`]: server/struct.Connection.html
+#[derive(Debug, Clone)]
+pub struct ConnectionStats {
+    pub(crate) bytes_sent: u64,
+    pub(crate) bytes_received: u64,
+    pub(crate) frames_sent: FrameCounts,
+    pub(crate) frames_received: FrameCounts,
+    pub(crate) open_streams: usize,
+    pub(crate) closed_streams: u64,
+    pub(crate) reset_streams: u64,
+    pub(crate) remote_reset_streams: u64,
+    pub(crate) send_window: u32,
+    pub(crate) recv_window: u32,
+    pub(crate) hpack_encoder_table_size: usize,
+    pub(crate) hpack_decoder_table_size: usize,
+    pub(crate) rtt: Option<Duration>,
+    pub(crate) go_away_sent: Option<Reason>,
+    pub(crate) go_away_received: Option<Reason>,
+}
+
+/// Numbers of frames sent or received on a connection, by frame type.
+#[derive(Debug, Clone, Default)]
+pub struct FrameCounts {
+    counts: [u64; NUM_COUNTS],
+}
+
+/// A snapshot of the statistics of a stream.
+///
+/// Returned by [`SendStream::stats`] and [`RecvStream::stats`].
+///
+/// [`SendStream::stats`]: struct.SendStream.html#method.stats
+/// [`RecvStream::stats`]: struct.RecvStream.html#method.stats
+#[derive(Debug, Clone)]
+pub struct StreamStats {
+    pub(crate) bytes_sent: u64,
+    pub(crate) bytes_received: u64,
+    pub(crate) send_window: u32,
+    pub(crate) recv_window: u32,
+}
+
+/// Counters shared by the codec and the handles of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct SharedStats {
+    pub(crate) sent: Counters,
+    pub(crate) received: Counters,
+    hpack_encoder_table_size: AtomicUsize,
+    hpack_decoder_table_size: AtomicUsize,
+    /// The last round-trip time, in nanoseconds, or 0 if none.
+    rtt: AtomicU64,
+}
+
+/// Counters of the frames going in one direction.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    bytes: AtomicU64,
+    frames: [AtomicU64; NUM_COUNTS],
+    /// The reason of the last GOAWAY frame, or `NO_GO_AWAY`.
+    go_away: AtomicU64,
+}
+
+const NUM_KINDS: usize = 14;
+
+/// The count of each kind, then that of the extension frames.
+const NUM_COUNTS: usize = NUM_KINDS + 1;
+
+const EXTENSION: usize = NUM_KINDS;
+
+const NO_GO_AWAY: u64 = 0;
+
+// ===== impl ConnectionStats =====
+
+impl ConnectionStats {
+    /// Returns the number of bytes sent.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Returns the number of bytes received.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Returns the numbers of frames sent.
+    pub fn frames_sent(&self) -> &FrameCounts {
+        &self.frames_sent
+    }
+
+    /// Returns the numbers of frames received.
+    pub fn frames_received(&self) -> &FrameCounts {
+        &self.frames_received
+    }
+
+    /// Returns the number of streams currently open.
+    pub fn open_streams(&self) -> usize {
+        self.open_streams
+    }
+
+    /// Returns the number of streams closed so far, including the reset ones.
+    pub fn closed_streams(&self) -> u64 {
+        self.closed_streams
+    }
+
+    /// Returns the number of streams reset so far, by either peer.
+    pub fn reset_streams(&self) -> u64 {
+        self.reset_streams
+    }
+
+    /// Returns the number of streams reset by the peer so far.
+    ///
+    /// On a server, see [`max_remote_reset_streams`] for limiting how fast
+    /// the client may reset streams.
+    ///
+    /// [`max_remote_reset_streams`]: server/struct.Builder.html#method.max_remote_reset_streams
+    pub fn remote_reset_streams(&self) -> u64 {
+        self.remote_reset_streams
+    }
+
+    /// Returns the connection level window for sending data, as advertised
+    /// by the peer.
+    pub fn send_window(&self) -> u32 {
+        self.send_window
+    }
+
+    /// Returns the connection level window for receiving data, as advertised
+    /// to the peer.
+    pub fn recv_window(&self) -> u32 {
+        self.recv_window
+    }
+
+    /// Returns the size of the HPACK dynamic table used to encode headers.
+    pub fn hpack_encoder_table_size(&self) -> usize {
+        self.hpack_encoder_table_size
+    }
+
+    /// Returns the size of the HPACK dynamic table used to decode headers.
+    pub fn hpack_decoder_table_size(&self) -> usize {
+        self.hpack_decoder_table_size
+    }
+
+    /// Returns the round-trip time of the last acknowledged PING, if any.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Returns the reason of the last GOAWAY frame sent, if any.
+    pub fn go_away_sent(&self) -> Option<Reason> {
+        self.go_away_sent
+    }
+
+    /// Returns the reason of the last GOAWAY frame received, if any.
+    pub fn go_away_received(&self) -> Option<Reason> {
+        self.go_away_received
+    }
+}
+
+// ===== impl FrameCounts =====
+
+impl FrameCounts {
+    /// Returns the number of DATA frames.
+    pub fn data(&self) -> u64 {
+        self.get(Kind::Data)
+    }
+
+    /// Returns the number of HEADERS frames.
+    pub fn headers(&self) -> u64 {
+        self.get(Kind::Headers)
+    }
+
+    /// Returns the number of PRIORITY frames.
+    pub fn priority(&self) -> u64 {
+        self.get(Kind::Priority)
+    }
+
+    /// Returns the number of RST_STREAM frames.
+    pub fn reset(&self) -> u64 {
+        self.get(Kind::Reset)
+    }
+
+    /// Returns the number of SETTINGS frames.
+    pub fn settings(&self) -> u64 {
+        self.get(Kind::Settings)
+    }
+
+    /// Returns the number of PUSH_PROMISE frames.
+    pub fn push_promise(&self) -> u64 {
+        self.get(Kind::PushPromise)
+    }
+
+    /// Returns the number of PING frames.
+    pub fn ping(&self) -> u64 {
+        self.get(Kind::Ping)
+    }
+
+    /// Returns the number of GOAWAY frames.
+    pub fn go_away(&self) -> u64 {
+        self.get(Kind::GoAway)
+    }
+
+    /// Returns the number of WINDOW_UPDATE frames.
+    pub fn window_update(&self) -> u64 {
+        self.get(Kind::WindowUpdate)
+    }
+
+    /// Returns the number of CONTINUATION frames.
+    pub fn continuation(&self) -> u64 {
+        self.get(Kind::Continuation)
+    }
+
+    /// Returns the number of PRIORITY_UPDATE frames.
+    pub fn priority_update(&self) -> u64 {
+        self.get(Kind::PriorityUpdate)
+    }
+
+    /// Returns the number of ALTSVC frames.
+    pub fn alt_svc(&self) -> u64 {
+        self.get(Kind::AltSvc)
+    }
+
+    /// Returns the number of ORIGIN frames.
+    pub fn origin(&self) -> u64 {
+        self.get(Kind::Origin)
+    }
+
+    /// Returns the number of extension frames, of the types registered with
+    /// `extension_frame_type`.
+    pub fn extension(&self) -> u64 {
+        self.counts[EXTENSION]
+    }
+
+    /// Returns the number of frames of an unknown type, other than the
+    /// extension frames.
+    pub fn unknown(&self) -> u64 {
+        self.get(Kind::Unknown)
+    }
+
+    /// Returns the number of frames of all types.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn get(&self, kind: Kind) -> u64 {
+        self.counts[index(kind)]
+    }
+}
+
+// ===== impl StreamStats =====
+
+impl StreamStats {
+    /// Returns the number of DATA payload bytes sent on the stream.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Returns the number of DATA payload bytes received on the stream.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Returns the stream level window for sending data, as advertised by
+    /// the peer.
+    pub fn send_window(&self) -> u32 {
+        self.send_window
+    }
+
+    /// Returns the stream level window for receiving data, as advertised to
+    /// the peer.
+    pub fn recv_window(&self) -> u32 {
+        self.recv_window
+    }
+}
+
+// ===== impl SharedStats =====
+
+impl SharedStats {
+    pub(crate) fn set_hpack_encoder_table_size(&self, size: usize) {
+        self.hpack_encoder_table_size.store(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_hpack_decoder_table_size(&self, size: usize) {
+        self.hpack_decoder_table_size.store(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_rtt(&self, rtt: Duration) {
+        let nanos = rtt.as_secs() * 1_000_000_000 + u64::from(rtt.subsec_nanos());
+        // 0 means no RTT
+        self.rtt.store(nanos.max(1), Ordering::Relaxed);
+    }
+
+    /// Returns a snapshot of the counters. The stream related fields are left
+    /// for the caller to fill in.
+    pub(crate) fn snapshot(&self) -> ConnectionStats {
+        let rtt = match self.rtt.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        };
+
+        ConnectionStats {
+            bytes_sent: self.sent.bytes.load(Ordering::Relaxed),
+            bytes_received: self.received.bytes.load(Ordering::Relaxed),
+            frames_sent: self.sent.frame_counts(),
+            frames_received: self.received.frame_counts(),
+            open_streams: 0,
+            closed_streams: 0,
+            reset_streams: 0,
+            remote_reset_streams: 0,
+            send_window: 0,
+            recv_window: 0,
+            hpack_encoder_table_size: self.hpack_encoder_table_size.load(Ordering::Relaxed),
+            hpack_decoder_table_size: self.hpack_decoder_table_size.load(Ordering::Relaxed),
+            rtt,
+            go_away_sent: self.sent.go_away(),
+            go_away_received: self.received.go_away(),
+        }
+    }
+}
+
+// ===== impl Counters =====
+
+impl Counters {
+    pub(crate) fn inc_bytes(&self, len: usize) {
+        self.bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_frames(&self, kind: Kind) {
+        self.frames[index(kind)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_extension_frames(&self) {
+        self.frames[EXTENSION].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn frames(&self, kind: Kind) -> u64 {
+        self.frames[index(kind)].load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_go_away(&self, reason: Reason) {
+        self.go_away
+            .store(u64::from(u32::from(reason)) + 1, Ordering::Relaxed);
+    }
+
+    fn go_away(&self) -> Option<Reason> {
+        match self.go_away.load(Ordering::Relaxed) {
+            NO_GO_AWAY => None,
+            val => Some(Reason::from((val - 1) as u32)),
+        }
+    }
+
+    fn frame_counts(&self) -> FrameCounts {
+        let mut counts = [0; NUM_COUNTS];
+
+        for (count, frames) in counts.iter_mut().zip(self.frames.iter()) {
+            *count = frames.load(Ordering::Relaxed);
+        }
+
+        FrameCounts { counts }
+    }
+}
+
+fn index(kind: Kind) -> usize {
+    // Every kind is listed, so that a new one can't be left out.
+    match kind {
+        Kind::Data => 0,
+        Kind::Headers => 1,
+        Kind::Priority => 2,
+        Kind::Reset => 3,
+        Kind::Settings => 4,
+        Kind::PushPromise => 5,
+        Kind::Ping => 6,
+        Kind::GoAway => 7,
+        Kind::WindowUpdate => 8,
+        Kind::Continuation => 9,
+        Kind::PriorityUpdate => 10,
+        Kind::Unknown => 11,
+        Kind::Origin => 12,
+        Kind::AltSvc => 13,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codec::Codec;
+    use crate::frame;
+
+    use bytes::Bytes;
+    use futures_core::Stream;
+    use futures_util::io::Cursor;
+    use futures_util::task::noop_waker_ref;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    #[test]
+    fn test_snapshot() {
+        let stats = SharedStats::default();
+
+        stats.sent.inc_frames(Kind::Headers);
+        stats.sent.inc_frames(Kind::Continuation);
+        stats.sent.inc_bytes(30);
+        stats.received.inc_frames(Kind::PriorityUpdate);
+        stats.received.inc_frames(Kind::Unknown);
+        stats.received.set_go_away(Reason::NO_ERROR);
+        stats.set_rtt(Duration::from_millis(3));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.bytes_sent(), 30);
+        assert_eq!(snapshot.frames_sent().headers(), 1);
+        assert_eq!(snapshot.frames_sent().continuation(), 1);
+        assert_eq!(snapshot.frames_sent().total(), 2);
+        assert_eq!(snapshot.frames_received().priority_update(), 1);
+        assert_eq!(snapshot.frames_received().unknown(), 1);
+        assert_eq!(snapshot.go_away_sent(), None);
+        assert_eq!(snapshot.go_away_received(), Some(Reason::NO_ERROR));
+        assert_eq!(snapshot.rtt(), Some(Duration::from_millis(3)));
+    }
+
+    #[test]
+    fn test_every_kind_has_its_own_count() {
+        let kinds = [
+            Kind::Data,
+            Kind::Headers,
+            Kind::Priority,
+            Kind::Reset,
+            Kind::Settings,
+            Kind::PushPromise,
+            Kind::Ping,
+            Kind::GoAway,
+            Kind::WindowUpdate,
+            Kind::Continuation,
+            Kind::PriorityUpdate,
+            Kind::AltSvc,
+            Kind::Origin,
+            Kind::Unknown,
+        ];
+        assert_eq!(kinds.len(), NUM_KINDS);
+
+        for (i, &kind) in kinds.iter().enumerate() {
+            let counters = Counters::default();
+            counters.inc_frames(kind);
+            assert_eq!(counters.frames(kind), 1);
+
+            // In the same order as `kinds`.
+            let counts = counters.frame_counts();
+            let by_accessor = [
+                counts.data(),
+                counts.headers(),
+                counts.priority(),
+                counts.reset(),
+                counts.settings(),
+                counts.push_promise(),
+                counts.ping(),
+                counts.go_away(),
+                counts.window_update(),
+                counts.continuation(),
+                counts.priority_update(),
+                counts.alt_svc(),
+                counts.origin(),
+                counts.unknown(),
+            ];
+
+            for (j, &count) in by_accessor.iter().enumerate() {
+                assert_eq!(count, (i == j) as u64, "kind={:?}; accessor={}", kind, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejected_frames_not_counted() {
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(Vec::new()));
+        let too_big = Bytes::from(vec![0; codec.max_send_frame_size() + 1]);
+
+        let data = frame::Data::new(1.into(), too_big);
+        assert!(codec.buffer(data.into()).is_err());
+        assert_eq!(codec.stats().snapshot().frames_sent().total(), 0);
+
+        let origin = frame::Origin::new(vec!["o".repeat(codec.max_send_frame_size())]);
+        assert!(codec.buffer(origin.into()).is_err());
+        assert_eq!(codec.stats().snapshot().frames_sent().total(), 0);
+
+        let value = Bytes::from(vec![b'a'; codec.max_send_frame_size()]);
+        let alt_svc = frame::AltSvc::new(1.into(), Bytes::new(), value);
+        assert!(codec.buffer(alt_svc.into()).is_err());
+        assert_eq!(codec.stats().snapshot().frames_sent().total(), 0);
+
+        let data = frame::Data::new(1.into(), Bytes::from_static(b"hello"));
+        codec.buffer(data.into()).unwrap();
+        assert_eq!(codec.stats().snapshot().frames_sent().data(), 1);
+        assert_eq!(codec.stats().snapshot().frames_sent().total(), 1);
+    }
+
+    #[test]
+    fn test_extension_frames_counted() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(Vec::new()));
+        let max = codec.max_send_frame_size();
+
+        let too_big = Bytes::from(vec![0; max + 1]);
+        let extension = frame::Extension::new(0xb, 0, 0.into(), too_big);
+        assert!(codec.buffer(extension.into()).is_err());
+
+        let extension = frame::Extension::new(0xb, 0, 0.into(), Bytes::from_static(b"hello"));
+        codec.buffer(extension.into()).unwrap();
+        let unknown = frame::Extension::new(0xfa, 0, 0.into(), Bytes::from_static(b"hello"));
+        codec.buffer(unknown.into()).unwrap();
+        assert!(codec.flush(&mut cx).is_ready());
+
+        let sent = codec.stats().snapshot();
+        assert_eq!(sent.frames_sent().extension(), 2);
+        assert_eq!(sent.frames_sent().unknown(), 0);
+        assert_eq!(sent.frames_sent().total(), 2);
+
+        // Only the registered type is an extension frame when received
+        let written = codec.get_mut().get_ref().clone();
+        let mut codec: Codec<_, Bytes> = Codec::new(Cursor::new(written));
+        codec.set_extension_types(vec![0xb]);
+        while let Poll::Ready(Some(res)) = Pin::new(&mut codec).poll_next(&mut cx) {
+            res.unwrap();
+        }
+
+        let received = codec.stats().snapshot();
+        assert_eq!(received.frames_received().extension(), 1);
+        assert_eq!(received.frames_received().unknown(), 1);
+        assert_eq!(received.frames_received().total(), 2);
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A future completing after some time has passed, as returned by
+/// [`Timer::sleep`].
+///
+/// [`Timer::sleep`]: trait.Timer.html#tymethod.sleep
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// Provides timers to a connection.
+///
+/// The connection needs timers for some of its features, such as the
+/// keep-alive PINGs. Rather than depending on a specific runtime, the timer
+/// is supplied by the user, typically by wrapping the `sleep` function of the
+/// runtime in use.
+///
+/// # Examples
+///
+/// ```
+/// # use h2::{Sleep, Timer};
+/// # use std::time::Duration;
+/// #
+/// # async fn runtime_sleep(_: Duration) {}
+/// #
+/// #[derive(Debug)]
+/// struct RuntimeTimer;
+///
+/// impl Timer for RuntimeTimer {
+///     fn sleep(&self, duration: Duration) -> Sleep {
+///         Box::pin(runtime_sleep(duration))
+///     }
+/// }
+/// ```
+pub trait Timer: Send + Sync {
+    /// Returns a future that completes once `duration` has passed.
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// A shared `Timer`, as kept by the builders.
+#[derive(Clone)]
+pub(crate) struct TimerHandle(Arc<dyn Timer>);
+
+impl TimerHandle {
+    pub(crate) fn new<T: Timer + 'static>(timer: T) -> Self {
+        TimerHandle(Arc::new(timer))
+    }
+
+    pub(crate) fn sleep(&self, duration: Duration) -> Sleep {
+        self.0.sleep(duration)
+    }
+}
+
+impl fmt::Debug for TimerHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Timer").finish()
+    }
+}
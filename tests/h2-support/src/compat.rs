@@ -0,0 +1,58 @@
+//! Adapts tokio I/O types to the `futures-io` traits h2 is built on.
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Wraps a tokio `AsyncRead + AsyncWrite` to implement the `futures-io`
+/// traits.
+#[derive(Debug)]
+pub struct Compat<T> {
+    inner: T,
+}
+
+impl<T> Compat<T> {
+    pub fn new(inner: T) -> Self {
+        Compat { inner }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> AsyncRead for Compat<T>
+where
+    T: tokio::io::AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.inner), cx, buf)
+    }
+}
+
+impl<T> AsyncWrite for Compat<T>
+where
+    T: tokio::io::AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
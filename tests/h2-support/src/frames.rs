@@ -4,6 +4,7 @@ use std::fmt;
 use bytes::Bytes;
 use http::{self, HeaderMap};
 
+use h2::ext::Protocol;
 use h2::frame::{self, Frame, StreamId};
 
 pub const SETTINGS: &'static [u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
@@ -109,7 +110,23 @@ impl Mock<frame::Headers> {
         let method = method.try_into().unwrap();
         let uri = uri.try_into().unwrap();
         let (id, _, fields) = self.into_parts();
-        let frame = frame::Headers::new(id, frame::Pseudo::request(method, uri), fields);
+        let frame = frame::Headers::new(id, frame::Pseudo::request(method, uri, None), fields);
+        Mock(frame)
+    }
+
+    pub fn extended_connect<U>(self, uri: U, protocol: &'static str) -> Self
+    where
+        U: TryInto<http::Uri>,
+        U::Error: fmt::Debug,
+    {
+        let uri = uri.try_into().unwrap();
+        let protocol = Protocol::from_static(protocol);
+        let (id, _, fields) = self.into_parts();
+        let frame = frame::Headers::new(
+            id,
+            frame::Pseudo::request(http::Method::CONNECT, uri, Some(protocol)),
+            fields,
+        );
         Mock(frame)
     }
 
@@ -222,8 +239,12 @@ impl Mock<frame::PushPromise> {
         let method = method.try_into().unwrap();
         let uri = uri.try_into().unwrap();
         let (id, promised, _, fields) = self.into_parts();
-        let frame =
-            frame::PushPromise::new(id, promised, frame::Pseudo::request(method, uri), fields);
+        let frame = frame::PushPromise::new(
+            id,
+            promised,
+            frame::Pseudo::request(method, uri, None),
+            fields,
+        );
         Mock(frame)
     }
 
@@ -344,6 +365,11 @@ impl Mock<frame::Settings> {
         self.0.set_enable_push(false);
         self
     }
+
+    pub fn enable_connect_protocol(mut self) -> Self {
+        self.0.set_enable_connect_protocol(Some(1));
+        self
+    }
 }
 
 impl From<Mock<frame::Settings>> for frame::Settings {
@@ -5,8 +5,10 @@ pub mod assert;
 
 pub mod raw;
 
+pub mod compat;
 pub mod frames;
 pub mod mock;
+pub mod mock_io;
 pub mod prelude;
 pub mod trace;
 pub mod util;
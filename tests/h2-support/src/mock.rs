@@ -148,10 +148,11 @@ impl Handle {
         poll_fn(move |cx| {
             while buf.has_remaining() {
                 let res = Pin::new(self.codec.get_mut())
-                    .poll_write_buf(cx, &mut buf)
+                    .poll_write(cx, buf.bytes())
                     .map_err(|e| panic!("write err={:?}", e));
 
-                ready!(res).unwrap();
+                let n = ready!(res).unwrap();
+                buf.advance(n);
             }
 
             Poll::Ready(())
@@ -314,11 +315,11 @@ impl AsyncWrite for Handle {
         Pin::new(self.codec.get_mut()).poll_flush(cx)
     }
 
-    fn poll_shutdown(
+    fn poll_close(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), io::Error>> {
-        Pin::new(self.codec.get_mut()).poll_shutdown(cx)
+        Pin::new(self.codec.get_mut()).poll_close(cx)
     }
 }
 
@@ -406,7 +407,7 @@ impl AsyncWrite for Mock {
         Poll::Ready(Ok(()))
     }
 
-    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
         Poll::Ready(Ok(()))
     }
 }
@@ -474,7 +475,7 @@ impl AsyncWrite for Pipe {
         Poll::Ready(Ok(()))
     }
 
-    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
         Poll::Ready(Ok(()))
     }
 }
@@ -0,0 +1,41 @@
+//! `tokio_test::io` mocks usable as an h2 transport.
+
+use crate::compat::Compat;
+
+use std::time::Duration;
+
+/// A mock I/O handle that plays back the reads and writes it was built with.
+pub type Mock = Compat<tokio_test::io::Mock>;
+
+/// Builds a `Mock`, see `tokio_test::io::Builder`.
+#[derive(Debug)]
+pub struct Builder {
+    inner: tokio_test::io::Builder,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder {
+            inner: tokio_test::io::Builder::new(),
+        }
+    }
+
+    pub fn read(&mut self, buf: &[u8]) -> &mut Self {
+        self.inner.read(buf);
+        self
+    }
+
+    pub fn write(&mut self, buf: &[u8]) -> &mut Self {
+        self.inner.write(buf);
+        self
+    }
+
+    pub fn wait(&mut self, duration: Duration) -> &mut Self {
+        self.inner.wait(duration);
+        self
+    }
+
+    pub fn build(&mut self) -> Mock {
+        Compat::new(self.inner.build())
+    }
+}
@@ -27,12 +27,9 @@ pub use super::{
 pub use super::assert::assert_frame_eq;
 
 // Re-export useful crates
-pub use tokio_test::io as mock_io;
-<<<<<<< HEAD
-pub use {bytes, futures, http, tokio::io as tokio_io, tracing, tracing_subscriber};
-=======
-pub use {bytes, env_logger, futures, http, futures_io};
->>>>>>> 368ffdc... remove tokio deps and use futures AsyncWrite/Read
+pub use super::compat::Compat;
+pub use super::mock_io;
+pub use {bytes, futures, futures_io, http, tracing, tracing_subscriber};
 
 // Re-export primary future types
 pub use futures::{Future, Sink, Stream};
@@ -72,7 +69,7 @@ pub trait MockH2 {
     fn handshake_read_settings(&mut self, settings: &[u8]) -> &mut Self;
 }
 
-impl MockH2 for tokio_test::io::Builder {
+impl MockH2 for mock_io::Builder {
     fn handshake(&mut self) -> &mut Self {
         self.handshake_read_settings(frames::SETTINGS)
     }
@@ -2,9 +2,13 @@ use h2;
 
 use bytes::{BufMut, Bytes};
 use futures::ready;
+use futures_util::task::ArcWake;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 pub fn byte_str(s: &str) -> h2::frame::BytesStr {
     h2::frame::BytesStr::try_from(Bytes::copy_from_slice(s.as_bytes())).unwrap()
@@ -65,3 +69,35 @@ impl Future for WaitForCapacity {
         Poll::Pending
     }
 }
+
+/// Counts how many times the wakers made from it were woken.
+#[derive(Debug, Default)]
+pub struct CountWakes(AtomicUsize);
+
+impl CountWakes {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl ArcWake for CountWakes {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A timer whose sleeps either complete at once, or never.
+#[derive(Debug, Clone, Copy)]
+pub enum MockTimer {
+    Expired,
+    Pending,
+}
+
+impl h2::Timer for MockTimer {
+    fn sleep(&self, _: Duration) -> h2::Sleep {
+        match *self {
+            MockTimer::Expired => Box::pin(futures::future::ready(())),
+            MockTimer::Pending => Box::pin(futures::future::pending()),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+#![deny(warnings)]
+
+use futures::future::{join, poll_fn};
+use futures::StreamExt;
+use h2_support::prelude::*;
+use std::task::Poll;
+
+fn alt_svc(id: u32, origin: &'static str, value: String) -> frame::AltSvc {
+    frame::AltSvc::new(
+        id.into(),
+        Bytes::from_static(origin.as_bytes()),
+        value.into(),
+    )
+}
+
+#[tokio::test]
+async fn recv_alt_svc_never_polled() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        for _ in 0..2_000 {
+            let value = "h2=\"alt.example.com:443\"".to_string();
+            srv.send_frame(alt_svc(0, "https://example.com", value))
+                .await;
+        }
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        // The client never polls for ALTSVC frames, the connection carries
+        // on.
+        let response = h2.drive(client.get("https://example.com/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_alt_svc_keeps_most_recent() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        for i in 0..20 {
+            let value = format!("h2=\"alt{}.example.com:443\"", i);
+            srv.send_frame(alt_svc(0, "https://example.com", value))
+                .await;
+        }
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+        h2.drive(client.get("https://example.com/")).await.unwrap();
+        // Polling for ALTSVC frames doesn't drive the connection, only the
+        // frames already received are taken.
+        let mut hosts = vec![];
+        poll_fn(|cx| {
+            while let Poll::Ready(Some(alt_svc)) = h2.poll_alt_svc(cx) {
+                assert_eq!(alt_svc.origin(), Some("https://example.com"));
+                hosts.push(alt_svc.entries()[0].host().to_string());
+            }
+            Poll::Ready(())
+        })
+        .await;
+        let expected: Vec<_> = (4..20).map(|i| format!("alt{}.example.com", i)).collect();
+        assert_eq!(hosts, expected);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn send_alt_svc_frames() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        let value = "h2=\"alt.example.com:443\"".to_string();
+        client
+            .recv_frame(alt_svc(0, "https://example.com", value.clone()))
+            .await;
+        // The origin of a request stream is the one of the request.
+        client.recv_frame(alt_svc(1, "", value)).await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let value = http::HeaderValue::from_static("h2=\"alt.example.com:443\"");
+        srv.send_alt_svc(&uri::Uri::from_static("https://example.com/path"), &value)
+            .unwrap();
+        stream.send_alt_svc(&value).unwrap();
+        stream.send_response(Response::new(()), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
@@ -3,6 +3,7 @@ use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use h2_support::prelude::*;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::Context;
 
 #[tokio::test]
@@ -1231,3 +1232,156 @@ impl MockH2 for mock_io::Builder {
             .read(SETTINGS_ACK)
     }
 }
+
+#[tokio::test]
+async fn recv_go_away_with_streams_above_last_stream_id() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/1")
+                .eos(),
+        )
+        .await;
+        srv.recv_frame(
+            frames::headers(3)
+                .request("GET", "https://example.com/3")
+                .eos(),
+        )
+        .await;
+
+        // Stream 3 was not processed, stream 1 completes.
+        srv.send_frame(frames::go_away(1)).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        let below = client.get("https://example.com/1");
+        let above = client.get("https://example.com/3");
+        let (below, above) = h2.drive(join(below, above)).await;
+
+        let err = above.unwrap_err();
+        assert!(err.is_unprocessed(), "err={:?}", err);
+
+        let response = below.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn refused_by_peer_is_unprocessed() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::reset(1).refused()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        let response = client.get("https://example.com/");
+        let err = h2.drive(response).await.unwrap_err();
+        assert!(err.is_unprocessed(), "err={:?}", err);
+        assert_eq!(err.reason(), Some(Reason::REFUSED_STREAM));
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn refused_locally_is_not_unprocessed() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_eq!(settings.max_header_list_size(), Some(100));
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::headers(1)
+                .response(200)
+                .field("x-large", "a".repeat(200)),
+        )
+        .await;
+        srv.recv_frame(frames::reset(1).refused()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .max_header_list_size(100)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let response = client.get("https://example.com/");
+        let err = h2.drive(response).await.unwrap_err();
+
+        // The server may well have processed the request.
+        assert!(!err.is_unprocessed(), "err={:?}", err);
+        assert_eq!(err.reason(), Some(Reason::REFUSED_STREAM));
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn closed_wakes_every_handle() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+    let (send_go_away, go_away) = futures::channel::oneshot::channel::<()>();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        go_away.await.unwrap();
+        srv.send_frame(frames::go_away(0)).await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::handshake(io).await.expect("handshake");
+
+        let mut handles = vec![];
+        for _ in 0..2 {
+            let counter = Arc::new(util::CountWakes::default());
+            let waker = futures_util::task::waker(counter.clone());
+            let mut handle = client.clone();
+
+            let mut cx = Context::from_waker(&waker);
+            assert!(handle.poll_closed(&mut cx).is_pending());
+            handles.push((handle, counter));
+        }
+
+        send_go_away.send(()).unwrap();
+        join(async move { h2.await.unwrap() }, client.closed()).await;
+
+        for (_, counter) in &handles {
+            assert_eq!(counter.count(), 1);
+        }
+    };
+
+    join(srv, h2).await;
+}
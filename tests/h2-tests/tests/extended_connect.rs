@@ -0,0 +1,166 @@
+#![deny(warnings)]
+
+use futures::future::join;
+use futures::StreamExt;
+use h2::ext::Protocol;
+use h2_support::prelude::*;
+
+fn extended_connect_request() -> Request<()> {
+    let mut request = Request::connect("https://example.com/chat")
+        .body(())
+        .unwrap();
+    request
+        .extensions_mut()
+        .insert(Protocol::from_static("websocket"));
+    request
+}
+
+#[tokio::test]
+async fn send_extended_connect() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv
+            .assert_client_handshake_with_settings(frames::settings().enable_connect_protocol())
+            .await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1).extended_connect("https://example.com/chat", "websocket"),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        // Yield once to receive the SETTINGS enabling the protocol.
+        h2.drive(util::yield_once()).await;
+        assert!(client.is_extended_connect_protocol_enabled());
+
+        let (response, _stream) = client
+            .send_request(extended_connect_request(), false)
+            .unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_extended_connect() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_eq!(settings.is_extended_connect_protocol_enabled(), Some(true));
+        client
+            .send_frame(
+                frames::headers(1).extended_connect("https://example.com/chat", "websocket"),
+            )
+            .await;
+        client.recv_frame(frames::headers(1).response(200)).await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .enable_connect_protocol()
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(req.method(), Method::CONNECT);
+        assert_eq!(req.uri(), "https://example.com/chat");
+        assert_eq!(
+            req.extensions().get::<Protocol>(),
+            Some(&Protocol::from_static("websocket"))
+        );
+        stream.send_response(Response::new(()), false).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn extended_connect_not_enabled_by_server() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        // Yield once to receive the SETTINGS of the server.
+        h2.drive(util::yield_once()).await;
+        assert!(!client.is_extended_connect_protocol_enabled());
+
+        let err = client
+            .send_request(extended_connect_request(), true)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            h2::Error::from(UserError::PeerDisabledConnectProtocol).to_string()
+        );
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn extended_connect_rejected_when_not_enabled() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .extended_connect("https://example.com/chat", "websocket")
+                    .eos(),
+            )
+            .await;
+        client.recv_frame(frames::reset(1).protocol_error()).await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+
+        // The request is reset without being accepted.
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[test]
+fn plain_connect_has_no_path() {
+    let pseudo = frame::Pseudo::request(
+        Method::CONNECT,
+        uri::Uri::from_static("example.com:443"),
+        None,
+    );
+    assert!(pseudo.scheme.is_none());
+    assert!(pseudo.path.is_none());
+    assert_eq!(pseudo.authority.as_deref(), Some("example.com:443"));
+
+    let pseudo = frame::Pseudo::request(
+        Method::CONNECT,
+        uri::Uri::from_static("https://example.com/chat"),
+        Some(Protocol::from_static("websocket")),
+    );
+    assert_eq!(pseudo.scheme.as_deref(), Some("https"));
+    assert_eq!(pseudo.path.as_deref(), Some("/chat"));
+}
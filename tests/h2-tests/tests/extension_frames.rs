@@ -0,0 +1,101 @@
+#![deny(warnings)]
+
+use futures::future::{join, poll_fn};
+use futures::FutureExt;
+use h2_support::prelude::*;
+
+fn extension(kind: u8, flags: u8, payload: &'static [u8]) -> frame::Extension {
+    frame::Extension::new(kind, flags, StreamId::zero(), Bytes::from_static(payload))
+}
+
+#[tokio::test]
+async fn client_extension_frames() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        srv.codec_mut().set_extension_types(vec![0xb]);
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+
+        srv.recv_frame(extension(0xb, 0x1, b"hi")).await;
+
+        // The client did not register this type, and ignores it.
+        srv.send_frame(extension(0xd, 0, b"no")).await;
+        srv.send_frame(extension(0xb, 0, b"hello")).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (_client, mut h2) = client::Builder::new()
+            .extension_frame_type(0xb)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        // Yield once to finish the handshake before sending extension frames.
+        h2.drive(util::yield_once()).await;
+
+        // The server ignores the types it did not register.
+        h2.send_extension_frame(0xd, 0, h2::StreamId::zero(), Bytes::from_static(b"no"))
+            .unwrap();
+        h2.send_extension_frame(0xb, 0x1, h2::StreamId::zero(), Bytes::from_static(b"hi"))
+            .unwrap();
+
+        let frame = poll_fn(|cx| {
+            let _ = h2.poll_unpin(cx);
+            h2.poll_extension_frame(cx)
+        })
+        .await
+        .unwrap();
+        assert_eq!(frame.frame_type(), 0xb);
+        assert_eq!(frame.flags(), 0);
+        assert_eq!(frame.stream_id().as_u32(), 0);
+        assert_eq!(frame.payload(), &Bytes::from_static(b"hello"));
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn server_extension_frames() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client.codec_mut().set_extension_types(vec![0xb]);
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+
+        // The server did not register this type, and ignores it.
+        client.send_frame(extension(0xd, 0, b"no")).await;
+        client.send_frame(extension(0xb, 0x1, b"hi")).await;
+
+        client.recv_frame(extension(0xb, 0, b"hello")).await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .extension_frame_type(0xb)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let frame = poll_fn(|cx| {
+            let _ = srv.poll_closed(cx);
+            srv.poll_extension_frame(cx)
+        })
+        .await
+        .unwrap();
+        assert_eq!(frame.frame_type(), 0xb);
+        assert_eq!(frame.flags(), 0x1);
+        assert_eq!(frame.stream_id().as_u32(), 0);
+        assert_eq!(frame.payload(), &Bytes::from_static(b"hi"));
+
+        srv.send_extension_frame(0xb, 0, h2::StreamId::zero(), Bytes::from_static(b"hello"))
+            .unwrap();
+        poll_fn(|cx| srv.poll_closed(cx)).await.unwrap();
+    };
+
+    join(client, srv).await;
+}
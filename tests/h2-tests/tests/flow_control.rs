@@ -1564,3 +1564,155 @@ async fn data_padding() {
 
     join(srv, h2).await;
 }
+
+/// Connects a client built by `builder`, whose connection is spawned, to a
+/// server.
+async fn connect_spawned(builder: &client::Builder) -> (client::SendRequest<Bytes>, mock::Handle) {
+    let (io, mut srv) = mock::new();
+
+    let (client, h2) = builder.handshake::<_, Bytes>(io).await.unwrap();
+    tokio::spawn(async move {
+        let _ = h2.await;
+    });
+
+    let settings = srv.assert_client_handshake().await;
+    assert_default_settings!(settings);
+
+    (client, srv)
+}
+
+/// Sends the response HEADERS for a stream, then `len` bytes of its body
+/// without ending it.
+async fn send_response_data(srv: &mut mock::Handle, id: u32, mut len: usize) {
+    srv.send_frame(frames::headers(id).response(200)).await;
+
+    while len > 0 {
+        let n = len.min(frame::DEFAULT_MAX_FRAME_SIZE as usize);
+        srv.send_frame(frames::data(id, vec![0; n])).await;
+        len -= n;
+    }
+}
+
+/// Sends a PING and waits for its ACK, so the client has processed all the
+/// frames sent before it. No WINDOW_UPDATE may be sent for `id` in the
+/// meantime.
+async fn ping_pong_without_window_update(srv: &mut mock::Handle, id: u32) {
+    srv.send_frame(frames::ping([1; 8])).await;
+
+    loop {
+        match srv.next().await {
+            Some(Ok(frame::Frame::Ping(ref v))) if v.is_ack() => return,
+            Some(Ok(frame::Frame::WindowUpdate(ref v))) => {
+                assert_ne!(v.stream_id(), StreamId::from(id))
+            }
+            frame => panic!("expected PING; frame={:?}", frame),
+        }
+    }
+}
+
+/// Sums the WINDOW_UPDATEs received for `id` until they reach `total`.
+async fn recv_window_updates(srv: &mut mock::Handle, id: u32, total: u32) {
+    let mut sum = 0;
+
+    while sum < total {
+        match srv.next().await {
+            Some(Ok(frame::Frame::WindowUpdate(ref v))) => {
+                if v.stream_id() == StreamId::from(id) {
+                    sum += v.size_increment();
+                }
+            }
+            frame => panic!("expected WINDOW_UPDATE; frame={:?}", frame),
+        }
+    }
+
+    assert_eq!(sum, total);
+}
+
+#[tokio::test]
+async fn auto_release_capacity() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) =
+        connect_spawned(client::Builder::new().auto_release_capacity(true)).await;
+
+    let request = Request::get("https://example.com/").body(()).unwrap();
+    let (response, _stream) = client.send_request(request, false).unwrap();
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    send_response_data(&mut srv, 1, 40_000).await;
+
+    let mut body = response.await.unwrap().into_body();
+    assert!(body.flow_control().is_auto_release_capacity());
+
+    let mut taken = 0;
+    while taken < 40_000 {
+        taken += body.data().await.unwrap().unwrap().len();
+    }
+
+    // Taking the data released its capacity.
+    assert_eq!(body.flow_control().used_capacity(), 0);
+    recv_window_updates(&mut srv, 1, 40_000).await;
+}
+
+#[tokio::test]
+async fn auto_release_capacity_per_stream() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) =
+        connect_spawned(client::Builder::new().auto_release_capacity(true)).await;
+
+    let request = Request::get("https://example.com/1").body(()).unwrap();
+    let (manual, _stream) = client.send_request(request, false).unwrap();
+    let request = Request::get("https://example.com/3").body(()).unwrap();
+    let (auto, _stream) = client.send_request(request, false).unwrap();
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/1"))
+        .await;
+    srv.recv_frame(frames::headers(3).request("GET", "https://example.com/3"))
+        .await;
+    send_response_data(&mut srv, 1, 1000).await;
+    send_response_data(&mut srv, 3, 1000).await;
+
+    let mut manual = manual.await.unwrap().into_body();
+    manual.flow_control().set_auto_release_capacity(false);
+    assert!(!manual.flow_control().is_auto_release_capacity());
+
+    let mut auto = auto.await.unwrap().into_body();
+    assert!(auto.flow_control().is_auto_release_capacity());
+
+    assert_eq!(manual.data().await.unwrap().unwrap().len(), 1000);
+    assert_eq!(manual.flow_control().used_capacity(), 1000);
+
+    assert_eq!(auto.data().await.unwrap().unwrap().len(), 1000);
+    assert_eq!(auto.flow_control().used_capacity(), 0);
+
+    // Enabling it again releases the data already taken.
+    manual.flow_control().set_auto_release_capacity(true);
+    assert_eq!(manual.flow_control().used_capacity(), 0);
+}
+
+#[tokio::test]
+async fn recv_buffer_holds_window_update() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) =
+        connect_spawned(client::Builder::new().max_recv_buffer_size(50_000)).await;
+
+    let request = Request::get("https://example.com/").body(()).unwrap();
+    let (response, _stream) = client.send_request(request, false).unwrap();
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    send_response_data(&mut srv, 1, 40_000).await;
+    ping_pong_without_window_update(&mut srv, 1).await;
+
+    let mut body = response.await.unwrap().into_body();
+
+    // Releasing the capacity would let the peer buffer more than the
+    // maximum, so the WINDOW_UPDATE is held back.
+    body.flow_control().release_capacity(40_000).unwrap();
+    ping_pong_without_window_update(&mut srv, 1).await;
+
+    let mut taken = 0;
+    while taken < 40_000 {
+        taken += body.data().await.unwrap().unwrap().len();
+    }
+
+    // Once taken, the window grows up to the maximum.
+    recv_window_updates(&mut srv, 1, 50_000 - (65_535 - 40_000)).await;
+}
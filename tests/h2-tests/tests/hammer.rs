@@ -75,7 +75,7 @@ where
     F: Fn() -> Bytes,
     F: Send + Sync + 'static,
 {
-    let mut conn = server::handshake(socket?).await?;
+    let mut conn = server::handshake(Compat::new(socket?)).await?;
     while let Some(result) = conn.next().await {
         let (_, mut respond) = result?;
         reqs.fetch_add(1, Ordering::Release);
@@ -103,7 +103,7 @@ fn hammer_client_concurrency() {
         let tcp = tcp
             .then(|res| {
                 let tcp = res.unwrap();
-                client::handshake(tcp)
+                client::handshake(Compat::new(tcp))
             })
             .then(move |res| {
                 let rsps = rsps;
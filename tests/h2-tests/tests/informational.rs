@@ -0,0 +1,123 @@
+#![deny(warnings)]
+
+use futures::future::join;
+use futures::StreamExt;
+use h2_support::prelude::*;
+
+fn response(status: u16) -> Response<()> {
+    Response::builder().status(status).body(()).unwrap()
+}
+
+#[tokio::test]
+async fn send_informational_responses() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        client.recv_frame(frames::headers(1).response(100)).await;
+        client.send_frame(frames::data(1, "hello").eos()).await;
+        client
+            .recv_frame(
+                frames::headers(1)
+                    .response(103)
+                    .field("link", "</style.css>"),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let req_fut = async move {
+            assert!(stream.send_informational(response(200)).is_err());
+            assert!(stream.send_informational(response(101)).is_err());
+
+            stream.send_informational(response(100)).unwrap();
+            let body = util::concat(req.into_body()).await.unwrap();
+            assert_eq!(body, "hello");
+
+            let mut hints = response(103);
+            hints
+                .headers_mut()
+                .insert("link", "</style.css>".parse().unwrap());
+            stream.send_informational(hints).unwrap();
+            stream.send_response(response(200), true).unwrap();
+
+            // Nothing can follow the final response.
+            assert!(stream.send_informational(response(100)).is_err());
+            assert!(stream.send_response(response(200), true).is_err());
+        };
+
+        let srv_fut = async move {
+            assert!(srv.next().await.is_none());
+        };
+
+        join(req_fut, srv_fut).await;
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn recv_informational_responses() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        srv.send_frame(frames::headers(1).response(100)).await;
+        srv.recv_frame(frames::data(1, "hello").eos()).await;
+        srv.send_frame(
+            frames::headers(1)
+                .response(103)
+                .field("link", "</style.css>"),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        let request = Request::post("https://example.com/").body(()).unwrap();
+        let (mut response, mut stream) = client.send_request(request, false).unwrap();
+        let mut informational = response.informational_responses();
+
+        // The client waits for 100 Continue before sending the body.
+        let first = h2
+            .drive(informational.informational())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::CONTINUE);
+        stream
+            .send_data(Bytes::from_static(b"hello"), true)
+            .unwrap();
+
+        let second = h2
+            .drive(informational.informational())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.status().as_u16(), 103);
+        assert_eq!(second.headers()["link"], "</style.css>");
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(informational.informational().await.is_none());
+    };
+
+    join(srv, h2).await;
+}
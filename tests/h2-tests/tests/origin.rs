@@ -0,0 +1,123 @@
+#![deny(warnings)]
+
+use futures::future::join;
+use futures::StreamExt;
+use h2_support::prelude::*;
+
+fn origin(origins: &[&str]) -> frame::Origin {
+    frame::Origin::new(origins.iter().map(|origin| origin.to_string()).collect())
+}
+
+/// Sends `frames` of origins, then answers a request so that the client has
+/// received them, and returns the origin set of the client.
+async fn recv_origin_set(frames: Vec<frame::Origin>) -> Vec<uri::Uri> {
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        for frame in frames {
+            srv.send_frame(frame).await;
+        }
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+        assert!(client.origin_set().is_none());
+
+        let response = client.get("https://example.com/");
+        h2.drive(response).await.unwrap();
+
+        client.origin_set().unwrap()
+    };
+
+    join(srv, h2).await.1
+}
+
+#[tokio::test]
+async fn recv_origin_frames() {
+    h2_support::trace_init!();
+
+    let mut set = recv_origin_set(vec![
+        origin(&["https://a.example.com", "https://b.example.com:8443"]),
+        origin(&["https://a.example.com", "https://c.example.com"]),
+    ])
+    .await;
+    set.sort_by_key(|uri| uri.to_string());
+
+    assert_eq!(
+        set,
+        vec![
+            uri::Uri::from_static("https://a.example.com"),
+            uri::Uri::from_static("https://b.example.com:8443"),
+            uri::Uri::from_static("https://c.example.com"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn recv_origin_set_capped() {
+    h2_support::trace_init!();
+
+    let origins: Vec<_> = (0..300)
+        .map(|i| format!("https://{}.example.com", i))
+        .collect();
+    let frame = frame::Origin::new(origins);
+
+    assert_eq!(recv_origin_set(vec![frame]).await.len(), 256);
+}
+
+#[tokio::test]
+async fn send_origin_frames() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        // Only the scheme and the authority are sent.
+        client
+            .recv_frame(origin(&[
+                "https://a.example.com",
+                "https://b.example.com:8443",
+            ]))
+            .await;
+        client.recv_frame(origin(&["https://c.example.com"])).await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        srv.send_origin_set(vec![
+            uri::Uri::from_static("https://a.example.com"),
+            uri::Uri::from_static("https://b.example.com:8443/ignored"),
+        ])
+        .unwrap();
+        srv.send_origin_set(vec![uri::Uri::from_static("https://c.example.com")])
+            .unwrap();
+        stream.send_response(Response::new(()), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
@@ -184,3 +184,45 @@ async fn user_notifies_when_connection_closes() {
         "broken pipe",
     );
 }
+
+#[tokio::test]
+async fn client_keep_alive_requires_timer() {
+    h2_support::trace_init!();
+    let (io, _srv) = mock::new();
+
+    let err = client::Builder::new()
+        .keep_alive_interval(Duration::from_secs(30))
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        h2::Error::from(UserError::MissingTimer).to_string()
+    );
+}
+
+#[tokio::test]
+async fn server_keep_alive_requires_timer() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client.write_preface().await;
+    };
+
+    let srv = async move {
+        let err = server::Builder::new()
+            .keep_alive_interval(Duration::from_secs(30))
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            h2::Error::from(UserError::MissingTimer).to_string()
+        );
+    };
+
+    join(client, srv).await;
+}
@@ -1,9 +1,11 @@
-use futures::future::join;
+use futures::future::{join, poll_fn};
 use futures::{FutureExt, StreamExt};
 use h2_support::prelude::*;
 use h2_support::DEFAULT_WINDOW_SIZE;
 use std::task::Context;
 
+const FRAME: usize = frame::DEFAULT_MAX_FRAME_SIZE as usize;
+
 #[tokio::test]
 async fn single_stream_send_large_body() {
     h2_support::trace_init!();
@@ -408,3 +410,244 @@ async fn send_data_receive_window_update() {
 
     join(mock, h2).await;
 }
+
+fn prioritized(priority: StreamPriority) -> Request<()> {
+    let mut request = Request::post("https://example.com/").body(()).unwrap();
+    request.extensions_mut().insert(priority);
+    request
+}
+
+#[tokio::test]
+async fn send_data_in_priority_order() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        // No stream can send DATA until every body is queued.
+        let settings = srv
+            .assert_client_handshake_with_settings(frames::settings().initial_window_size(0))
+            .await;
+        assert_default_settings!(settings);
+
+        for _ in 0..4 {
+            let frame = srv.next().await.unwrap();
+            let headers = assert_headers!(frame.unwrap());
+            assert!(!headers.is_end_stream());
+        }
+
+        // Open the windows of all streams at once.
+        let total = (17 * FRAME) as u32;
+        srv.send_frame(frames::window_update(0, total)).await;
+        srv.send_frame(frames::settings().initial_window_size(total))
+            .await;
+
+        let mut order = Vec::new();
+        while order.len() < 17 {
+            match srv.next().await.unwrap().unwrap() {
+                frame::Frame::Data(v) => order.push(u32::from(v.stream_id())),
+                frame::Frame::Settings(v) => assert!(v.is_ack()),
+                frame => panic!("unexpected frame; frame={:?}", frame),
+            }
+        }
+
+        // 5 goes first, then 1 and 3 share at four to one, and 7 waits for
+        // 1 to finish.
+        assert_eq!(order, [5, 5, 1, 3, 1, 1, 1, 1, 3, 1, 1, 1, 1, 3, 1, 7, 7]);
+
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.unwrap();
+        // Yield once to receive the SETTINGS of the server.
+        conn.drive(util::yield_once()).await;
+
+        // 5 takes 1 and 3 as its dependencies, 7 depends on 1. 1 gets four
+        // times the bandwidth of 3.
+        let (response1, mut stream1) = client
+            .send_request(prioritized(StreamPriority::new(256)), false)
+            .unwrap();
+        let (_response3, mut stream3) = client
+            .send_request(prioritized(StreamPriority::new(64)), false)
+            .unwrap();
+        let (_response5, mut stream5) = client
+            .send_request(prioritized(StreamPriority::new(16).exclusive()), false)
+            .unwrap();
+        let depends_on_1 = StreamPriority::new(16).depends_on(stream1.stream_id());
+        let (_response7, mut stream7) = client
+            .send_request(prioritized(depends_on_1), false)
+            .unwrap();
+
+        let bodies = vec![
+            (&mut stream1, 10),
+            (&mut stream3, 3),
+            (&mut stream5, 2),
+            (&mut stream7, 2),
+        ];
+        for (stream, frames) in bodies {
+            stream
+                .send_data(Bytes::from(vec![0; frames * FRAME]), true)
+                .unwrap();
+        }
+
+        let response = conn.drive(response1).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn recv_priority_update_from_server() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+
+        // Servers must not send PRIORITY_UPDATE.
+        let params = frame::PriorityParams::new(0, false);
+        srv.send_frame(frame::PriorityUpdate::new(1.into(), params))
+            .await;
+        srv.recv_frame(frames::go_away(0).protocol_error()).await;
+    };
+
+    let h2 = async move {
+        let (_client, h2) = client::handshake(io).await.unwrap();
+        let err = h2.await.unwrap_err();
+        assert_eq!(err.reason(), Some(Reason::PROTOCOL_ERROR));
+    };
+
+    join(srv, h2).await;
+}
+
+/// Answers `num` requests with `frames` full DATA frames each.
+async fn send_data_responses(io: mock::Mock, num: usize, frames: usize) {
+    let mut srv = server::handshake(io).await.expect("handshake");
+
+    let mut streams = Vec::new();
+    for _ in 0..num {
+        let (_req, mut respond) = srv.next().await.unwrap().unwrap();
+        let mut stream = respond.send_response(Response::new(()), false).unwrap();
+        stream
+            .send_data(Bytes::from(vec![0; frames * FRAME]), true)
+            .unwrap();
+        streams.push(stream);
+    }
+
+    poll_fn(|cx| srv.poll_closed(cx)).await.unwrap();
+}
+
+/// Opens the windows once the `num` responses arrived, and returns the
+/// stream of each DATA frame received, in order.
+async fn recv_data_order(client: &mut mock::Handle, num: usize, frames: usize) -> Vec<u32> {
+    for _ in 0..num {
+        let frame = client.next().await.unwrap();
+        assert_headers!(frame.unwrap());
+    }
+
+    // Every stream gets its window at once.
+    let total = (num * frames * FRAME) as u32;
+    client.send_frame(frames::window_update(0, total)).await;
+    client
+        .send_frame(frames::settings().initial_window_size(total))
+        .await;
+
+    let mut order = Vec::new();
+    while order.len() < num * frames {
+        match client.next().await.unwrap().unwrap() {
+            frame::Frame::Data(v) => order.push(u32::from(v.stream_id())),
+            frame::Frame::Settings(v) => assert!(v.is_ack()),
+            frame => panic!("unexpected frame; frame={:?}", frame),
+        }
+    }
+    order
+}
+
+fn priority_request(id: u32, priority: &str) -> frames::Mock<frame::Headers> {
+    frames::headers(id)
+        .request("GET", "https://example.com/")
+        .field("priority", priority)
+        .eos()
+}
+
+#[tokio::test]
+async fn send_data_in_urgency_order() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        // The server can't send DATA until `recv_data_order` opens the
+        // windows.
+        let settings = client
+            .assert_server_handshake_with_settings(frames::settings().initial_window_size(0))
+            .await;
+        assert_default_settings!(settings);
+
+        client.send_frame(priority_request(1, "u=5")).await;
+        client.send_frame(priority_request(3, "u=1")).await;
+        client
+            .send_frame(
+                frames::headers(5)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        let order = recv_data_order(&mut client, 3, 2).await;
+        assert_eq!(order, [3, 3, 5, 5, 1, 1]);
+    };
+
+    join(client, send_data_responses(io, 3, 2)).await;
+}
+
+#[tokio::test]
+async fn send_incremental_data_round_robin() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client
+            .assert_server_handshake_with_settings(frames::settings().initial_window_size(0))
+            .await;
+        assert_default_settings!(settings);
+
+        client.send_frame(priority_request(1, "u=3, i")).await;
+        client.send_frame(priority_request(3, "u=3, i")).await;
+        client.send_frame(priority_request(5, "u=3")).await;
+        client.send_frame(priority_request(7, "u=3, i")).await;
+
+        // The non-incremental stream goes first, in full.
+        let order = recv_data_order(&mut client, 4, 2).await;
+        assert_eq!(order, [5, 5, 1, 3, 7, 1, 3, 7]);
+    };
+
+    join(client, send_data_responses(io, 4, 2)).await;
+}
+
+#[tokio::test]
+async fn recv_priority_update_before_headers() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client
+            .assert_server_handshake_with_settings(frames::settings().initial_window_size(0))
+            .await;
+        assert_default_settings!(settings);
+
+        let params = frame::PriorityParams::new(0, false);
+        client
+            .send_frame(frame::PriorityUpdate::new(3.into(), params))
+            .await;
+        client.send_frame(priority_request(1, "u=3")).await;
+        client.send_frame(priority_request(3, "u=7")).await;
+
+        // The PRIORITY_UPDATE overrides the header of stream 3.
+        let order = recv_data_order(&mut client, 2, 2).await;
+        assert_eq!(order, [3, 3, 1, 1]);
+    };
+
+    join(client, send_data_responses(io, 2, 2)).await;
+}
@@ -0,0 +1,105 @@
+#![deny(warnings)]
+
+use futures::future::poll_fn;
+use futures::task::noop_waker_ref;
+use h2_support::prelude::*;
+use h2_support::util::CountWakes;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Connects a client with a send buffer of 1000 bytes, whose connection is
+/// spawned, to a server whose SETTINGS set the initial window size to
+/// `window`.
+async fn connect_send_buffer(window: u32) -> (client::SendRequest<Bytes>, mock::Handle) {
+    let (io, mut srv) = mock::new();
+
+    let (client, h2) = client::Builder::new()
+        .max_send_buffer_size(1000)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        let _ = h2.await;
+    });
+
+    let settings = srv
+        .assert_client_handshake_with_settings(frames::settings().initial_window_size(window))
+        .await;
+    assert_default_settings!(settings);
+
+    (client, srv)
+}
+
+fn get() -> Request<()> {
+    Request::get("https://example.com/").body(()).unwrap()
+}
+
+#[tokio::test]
+async fn send_buffer_caps_capacity() {
+    h2_support::trace_init!();
+    let (mut client, _srv) = connect_send_buffer(65_535).await;
+
+    let (_response, mut stream) = client.send_request(get(), false).unwrap();
+
+    // The window allows more, the send buffer does not.
+    stream.reserve_capacity(4000);
+    let capacity = poll_fn(|cx| stream.poll_capacity(cx))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(capacity, 1000);
+    assert_eq!(stream.capacity(), 1000);
+}
+
+#[tokio::test]
+async fn send_buffer_poll_ready() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(0).await;
+
+    let (_response, mut stream) = client.send_request(get(), false).unwrap();
+
+    let counter = Arc::new(CountWakes::default());
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(stream.poll_send_buffer_ready(&mut cx).is_ready());
+    stream.send_data(Bytes::from(vec![0; 1000]), false).unwrap();
+
+    // Nothing can be written, so the buffer stays full.
+    assert!(stream.poll_send_buffer_ready(&mut cx).is_pending());
+
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    assert!(stream.poll_send_buffer_ready(&mut cx).is_pending());
+    assert_eq!(counter.count(), 0);
+
+    srv.send_frame(frames::window_update(1, 1000)).await;
+    srv.recv_frame(frames::data(1, vec![0; 1000])).await;
+
+    // Writing the data drained the buffer and woke the task.
+    assert!(counter.count() > 0);
+    match stream.poll_send_buffer_ready(&mut cx) {
+        Poll::Ready(Ok(())) => {}
+        res => panic!("unexpected; res={:?}", res),
+    }
+}
+
+#[tokio::test]
+async fn send_buffer_poll_ready_after_reset() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(0).await;
+
+    let (_response, mut stream) = client.send_request(get(), false).unwrap();
+
+    let mut cx = Context::from_waker(noop_waker_ref());
+    stream.send_data(Bytes::from(vec![0; 1000]), false).unwrap();
+    assert!(stream.poll_send_buffer_ready(&mut cx).is_pending());
+
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.send_frame(frames::reset(1).cancel()).await;
+
+    // The reset wakes the task waiting for the buffer to drain.
+    let res = poll_fn(|cx| stream.poll_send_buffer_ready(cx)).await;
+    assert!(res.is_err());
+}
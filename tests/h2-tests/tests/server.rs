@@ -2,8 +2,10 @@
 
 use futures::future::{join, poll_fn};
 use futures::StreamExt;
-use h2_support::prelude::*;
 use futures_util::io::AsyncWriteExt;
+use h2_support::prelude::*;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 const SETTINGS: &'static [u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
 const SETTINGS_ACK: &'static [u8] = &[0, 0, 0, 4, 1, 0, 0, 0, 0];
@@ -1059,3 +1061,83 @@ async fn request_without_authority() {
 
     join(client, srv).await;
 }
+
+#[tokio::test]
+async fn sniff_http2_preface() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+    };
+
+    let srv = async move {
+        match server::Builder::new().sniff::<_, Bytes>(io).await.unwrap() {
+            server::Sniffed::Http2(handshake) => {
+                let mut srv = handshake.await.expect("handshake");
+                assert!(srv.next().await.is_none());
+            }
+            server::Sniffed::Other(_, read) => {
+                panic!("not sniffed as HTTP/2.0; read={:?}", read)
+            }
+        }
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn sniff_other_protocol() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    client.send_bytes(b"GET / HTTP/1.1\r\n").await;
+
+    match server::Builder::new().sniff::<_, Bytes>(io).await.unwrap() {
+        server::Sniffed::Http2(_) => panic!("sniffed as HTTP/2.0"),
+        server::Sniffed::Other(_, read) => assert_eq!(&read[..], b"GET / HTTP/1.1\r\n"),
+    }
+}
+
+#[tokio::test]
+async fn sniff_split_preface() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut sniff = server::Builder::new().sniff::<_, Bytes>(io);
+
+    client.send_bytes(&MAGIC_PREFACE[..10]).await;
+    assert!(Pin::new(&mut sniff).poll(&mut cx).is_pending());
+
+    client.send_bytes(&MAGIC_PREFACE[10..20]).await;
+    assert!(Pin::new(&mut sniff).poll(&mut cx).is_pending());
+
+    client.send_bytes(&MAGIC_PREFACE[20..]).await;
+    match Pin::new(&mut sniff).poll(&mut cx) {
+        Poll::Ready(Ok(server::Sniffed::Http2(_))) => {}
+        res => panic!("unexpected; res={:?}", res),
+    }
+}
+
+#[tokio::test]
+async fn sniff_split_mismatch() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut sniff = server::Builder::new().sniff::<_, Bytes>(io);
+
+    client.send_bytes(&MAGIC_PREFACE[..10]).await;
+    assert!(Pin::new(&mut sniff).poll(&mut cx).is_pending());
+
+    client.send_bytes(b"x").await;
+    match Pin::new(&mut sniff).poll(&mut cx) {
+        Poll::Ready(Ok(server::Sniffed::Other(_, read))) => {
+            assert_eq!(&read[..10], &MAGIC_PREFACE[..10]);
+            assert_eq!(&read[10..], b"x");
+        }
+        res => panic!("unexpected; res={:?}", res),
+    }
+}
@@ -0,0 +1,105 @@
+#![deny(warnings)]
+
+use futures::channel::oneshot;
+use futures::future::{join, poll_fn};
+use futures::StreamExt;
+use h2_support::prelude::*;
+use h2_support::util::CountWakes;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[tokio::test]
+async fn send_implemented_custom_setting() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+
+        // SETTINGS_INITIAL_WINDOW_SIZE
+        let err = srv
+            .send_custom_settings(&[(0xf0, 1), (0x4, 1)])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            h2::Error::from(UserError::ImplementedSetting).to_string()
+        );
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn remote_settings_tasks_bounded() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+    let (send_settings, settings_sent) = oneshot::channel::<()>();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        settings_sent.await.unwrap();
+
+        srv.send_frame(frames::settings().max_concurrent_streams(10))
+            .await;
+        srv.recv_frame(frames::settings_ack()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.expect("handshake");
+
+        // Wait for the initial SETTINGS, so that the handles wait for the
+        // next ones.
+        h2.drive(poll_fn(|cx| client.poll_remote_settings(cx)))
+            .await
+            .unwrap();
+
+        let mut handles = vec![];
+        for _ in 0..100 {
+            let counter = Arc::new(CountWakes::default());
+            let waker = futures::task::waker(counter.clone());
+            let mut handle = client.clone();
+
+            let mut cx = Context::from_waker(&waker);
+            assert!(handle.poll_remote_settings(&mut cx).is_pending());
+            handles.push((handle, counter));
+        }
+
+        // Only a bounded number of tasks are kept, the others were woken
+        // early.
+        let woken = handles
+            .iter()
+            .filter(|(_, counter)| counter.count() > 0)
+            .count();
+        assert!(woken >= 100 - 32, "woken={}", woken);
+
+        send_settings.send(()).unwrap();
+        let settings = h2
+            .drive(poll_fn(|cx| client.poll_remote_settings(cx)))
+            .await
+            .unwrap();
+        assert_eq!(settings.max_concurrent_streams(), Some(10));
+
+        for (mut handle, counter) in handles {
+            assert!(counter.count() > 0);
+
+            let waker = futures::task::waker(counter);
+            let mut cx = Context::from_waker(&waker);
+            match handle.poll_remote_settings(&mut cx) {
+                Poll::Ready(Some(settings)) => {
+                    assert_eq!(settings.max_concurrent_streams(), Some(10))
+                }
+                res => panic!("unexpected; res={:?}", res),
+            }
+        }
+    };
+
+    join(srv, h2).await;
+}
@@ -0,0 +1,202 @@
+#![deny(warnings)]
+
+use futures::future::{join, poll_fn};
+use futures::StreamExt;
+use h2_support::prelude::*;
+use h2_support::util::MockTimer;
+
+#[tokio::test]
+async fn client_shutdown_deadline_requires_timer() {
+    h2_support::trace_init!();
+    let (io, _srv) = mock::new();
+
+    let (_client, mut h2) = client::handshake(io).await.expect("handshake");
+    let err = h2
+        .graceful_shutdown_with_timeout(Duration::from_secs(1))
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        h2::Error::from(UserError::MissingTimer).to_string()
+    );
+}
+
+#[tokio::test]
+async fn client_shutdown_after_streams_complete() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+
+        // The GOAWAY may be written before the request.
+        let mut is_go_away_received = false;
+        let mut is_request_received = false;
+        while !(is_go_away_received && is_request_received) {
+            match srv.next().await.unwrap().unwrap() {
+                frame::Frame::Headers(_) => is_request_received = true,
+                frame::Frame::GoAway(v) => {
+                    assert_eq!(v.reason(), Reason::NO_ERROR);
+                    is_go_away_received = true;
+                }
+                frame => panic!("unexpected frame; frame={:?}", frame),
+            }
+        }
+
+        // The client waits for the stream to complete.
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .timer(MockTimer::Pending)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        // Yield once to finish the handshake before shutting down.
+        h2.drive(util::yield_once()).await;
+
+        let response = client.get("https://example.com/");
+        let shutdown = h2
+            .graceful_shutdown_with_timeout(Duration::from_secs(1))
+            .unwrap();
+
+        let (response, res) = join(response, h2).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+        res.unwrap();
+
+        let report = shutdown.await;
+        assert!(!report.is_timed_out());
+        assert!(report.cut_off_streams().is_empty());
+    };
+
+    join(srv, h2).await;
+}
+
+/// Answers the shutdown PING, and returns the GOAWAY frames received until
+/// the server closed the connection.
+async fn recv_go_aways(client: &mut mock::Handle) -> Vec<frame::GoAway> {
+    let mut go_aways = Vec::new();
+    while let Some(frame) = client.next().await {
+        match frame.unwrap() {
+            frame::Frame::GoAway(v) => go_aways.push(v),
+            frame::Frame::Ping(v) if !v.is_ack() => {
+                client.send_frame(frames::ping(*v.payload()).pong()).await
+            }
+            _ => {}
+        }
+    }
+    go_aways
+}
+
+#[tokio::test]
+async fn server_shutdown_deadline_cuts_off_streams() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        // The graceful GOAWAY is followed by an abrupt one, telling the
+        // client which streams were processed.
+        let go_aways = recv_go_aways(&mut client).await;
+        let last = go_aways.last().unwrap();
+        assert_eq!(last.last_stream_id(), StreamId::from(3));
+        assert_eq!(last.reason(), Reason::NO_ERROR);
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .timer(MockTimer::Expired)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let (_req1, stream1) = srv.next().await.unwrap().unwrap();
+        let (_req3, stream3) = srv.next().await.unwrap().unwrap();
+
+        let shutdown = srv
+            .graceful_shutdown_with_timeout(Duration::from_secs(1))
+            .unwrap();
+        poll_fn(|cx| srv.poll_closed(cx)).await.unwrap();
+
+        let report = shutdown.await;
+        assert!(report.is_timed_out());
+        assert_eq!(
+            report.cut_off_streams(),
+            [stream1.stream_id(), stream3.stream_id()]
+        );
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn server_shutdown_in_time() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        let go_aways = recv_go_aways(&mut client).await;
+        assert_eq!(go_aways.last().unwrap().reason(), Reason::NO_ERROR);
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .timer(MockTimer::Pending)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let (_req1, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_req3, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let shutdown = srv
+            .graceful_shutdown_with_timeout(Duration::from_secs(1))
+            .unwrap();
+        stream1.send_response(Response::new(()), true).unwrap();
+        stream3.send_response(Response::new(()), true).unwrap();
+        poll_fn(|cx| srv.poll_closed(cx)).await.unwrap();
+
+        let report = shutdown.await;
+        assert!(!report.is_timed_out());
+        assert!(report.cut_off_streams().is_empty());
+    };
+
+    join(client, srv).await;
+}
@@ -0,0 +1,141 @@
+#![deny(warnings)]
+
+use futures::future::poll_fn;
+use futures::sink::{Sink, SinkExt};
+use futures::stream::{self, StreamExt};
+use futures::task::noop_waker_ref;
+use h2_support::prelude::*;
+use std::pin::Pin;
+use std::task::Context;
+
+/// Connects a client with a send buffer of 1000 bytes, whose connection is
+/// spawned, to a server whose SETTINGS set the initial window size to
+/// `window`.
+async fn connect_send_buffer(window: u32) -> (client::SendRequest<Bytes>, mock::Handle) {
+    let (io, mut srv) = mock::new();
+
+    let (client, h2) = client::Builder::new()
+        .max_send_buffer_size(1000)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        let _ = h2.await;
+    });
+
+    let settings = srv
+        .assert_client_handshake_with_settings(frames::settings().initial_window_size(window))
+        .await;
+    assert_default_settings!(settings);
+
+    (client, srv)
+}
+
+fn get() -> Request<()> {
+    Request::get("https://example.com/").body(()).unwrap()
+}
+
+/// Receives the request HEADERS of stream 1, then its body until
+/// `END_STREAM`.
+async fn recv_request_body(srv: &mut mock::Handle) -> Vec<u8> {
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+
+    let mut body = vec![];
+    loop {
+        match srv.next().await {
+            Some(Ok(frame::Frame::Data(v))) => {
+                body.extend_from_slice(v.payload());
+                if v.is_end_stream() {
+                    return body;
+                }
+            }
+            frame => panic!("expected DATA; frame={:?}", frame),
+        }
+    }
+}
+
+#[tokio::test]
+async fn sink_forward() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(65_535).await;
+
+    let (_response, send_stream) = client.send_request(get(), false).unwrap();
+
+    // More than the send buffer holds, so readiness has to wait for the
+    // capacity to be freed as the data is written.
+    let chunks = (0..10u8).map(|i| Ok(Bytes::from(vec![i; 300])));
+    stream::iter(chunks).forward(send_stream).await.unwrap();
+
+    let body = recv_request_body(&mut srv).await;
+    assert_eq!(body.len(), 3000);
+    for (i, chunk) in body.chunks(300).enumerate() {
+        assert!(chunk.iter().all(|&b| b == i as u8));
+    }
+}
+
+#[tokio::test]
+async fn sink_close_twice() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(65_535).await;
+
+    let (_response, mut send_stream) = client.send_request(get(), false).unwrap();
+
+    send_stream
+        .send(Bytes::from_static(b"hello"))
+        .await
+        .unwrap();
+    send_stream.close().await.unwrap();
+    send_stream.close().await.unwrap();
+
+    assert_eq!(recv_request_body(&mut srv).await, b"hello");
+}
+
+#[tokio::test]
+async fn sink_ready_follows_capacity() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(100).await;
+
+    let (_response, mut send_stream) = client.send_request(get(), false).unwrap();
+
+    send_stream.send(Bytes::from(vec![0; 100])).await.unwrap();
+
+    // The stream window is used up, although the send buffer isn't full.
+    let mut cx = Context::from_waker(noop_waker_ref());
+    assert!(Sink::poll_ready(Pin::new(&mut send_stream), &mut cx).is_pending());
+
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.recv_frame(frames::data(1, vec![0; 100])).await;
+    srv.send_frame(frames::window_update(1, 50)).await;
+
+    poll_fn(|cx| Sink::poll_ready(Pin::new(&mut send_stream), cx))
+        .await
+        .unwrap();
+    assert_eq!(send_stream.capacity(), 50);
+}
+
+#[tokio::test]
+async fn sink_ready_follows_send_buffer() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect_send_buffer(65_535).await;
+
+    let (_response, mut send_stream) = client.send_request(get(), false).unwrap();
+
+    // An item larger than the send buffer is accepted, and fills it,
+    // although the stream window isn't used up.
+    send_stream.send(Bytes::from(vec![0; 2000])).await.unwrap();
+
+    let mut cx = Context::from_waker(noop_waker_ref());
+    assert!(Sink::poll_ready(Pin::new(&mut send_stream), &mut cx).is_pending());
+
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.recv_frame(frames::data(1, vec![0; 2000])).await;
+
+    // Writing the data drained the buffer.
+    poll_fn(|cx| Sink::poll_ready(Pin::new(&mut send_stream), cx))
+        .await
+        .unwrap();
+    assert!(send_stream.capacity() > 0);
+}
@@ -0,0 +1,57 @@
+#![deny(warnings)]
+
+use futures::future::join;
+use h2_support::prelude::*;
+
+#[tokio::test]
+async fn connection_and_stream_stats() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        srv.recv_frame(frames::data(1, "hello").eos()).await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, "world!").eos()).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        let request = Request::post("https://example.com/").body(()).unwrap();
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+        stream
+            .send_data(Bytes::from_static(b"hello"), true)
+            .unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        let mut body = response.into_body();
+        let data = h2.drive(body.data()).await.unwrap().unwrap();
+        assert_eq!(&data[..], b"world!");
+
+        assert_eq!(stream.stats().bytes_sent(), 5);
+        assert_eq!(body.stats().bytes_received(), 6);
+
+        // SETTINGS, its ACK, HEADERS with a 1 byte `:status`, and DATA.
+        let stats = h2.stats();
+        assert_eq!(stats.frames_received().settings(), 2);
+        assert_eq!(stats.frames_received().headers(), 1);
+        assert_eq!(stats.frames_received().data(), 1);
+        assert_eq!(stats.frames_received().total(), 4);
+        assert_eq!(stats.bytes_received(), 9 + 9 + 10 + 15);
+
+        assert_eq!(stats.frames_sent().settings(), 2);
+        assert_eq!(stats.frames_sent().headers(), 1);
+        assert_eq!(stats.frames_sent().data(), 1);
+
+        let handle_stats = client.stats();
+        assert_eq!(handle_stats.bytes_received(), stats.bytes_received());
+        assert_eq!(handle_stats.bytes_sent(), stats.bytes_sent());
+    };
+
+    join(srv, h2).await;
+}
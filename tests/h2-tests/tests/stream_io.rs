@@ -0,0 +1,131 @@
+#![deny(warnings)]
+
+use futures::StreamExt;
+use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+use h2_support::prelude::*;
+
+/// Connects a client with a send buffer of 1000 bytes, whose connection is
+/// spawned, to a server.
+async fn connect() -> (client::SendRequest<Bytes>, mock::Handle) {
+    let (io, mut srv) = mock::new();
+
+    let (client, h2) = client::Builder::new()
+        .max_send_buffer_size(1000)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    tokio::spawn(async move {
+        let _ = h2.await;
+    });
+
+    let settings = srv.assert_client_handshake().await;
+    assert_default_settings!(settings);
+
+    (client, srv)
+}
+
+fn get() -> Request<()> {
+    Request::get("https://example.com/").body(()).unwrap()
+}
+
+/// Receives the request HEADERS of stream 1, then its body until
+/// `END_STREAM`.
+async fn recv_request_body(srv: &mut mock::Handle) -> Vec<u8> {
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+
+    let mut body = vec![];
+    loop {
+        match srv.next().await {
+            Some(Ok(frame::Frame::Data(v))) => {
+                body.extend_from_slice(v.payload());
+                if v.is_end_stream() {
+                    return body;
+                }
+            }
+            frame => panic!("expected DATA; frame={:?}", frame),
+        }
+    }
+}
+
+#[tokio::test]
+async fn writer_partial_write() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let (_response, stream) = client.send_request(get(), false).unwrap();
+    let mut writer = stream.into_writer();
+
+    // The send buffer limits how much of the buffer is written.
+    let n = writer.write(&[0; 3000]).await.unwrap();
+    assert_eq!(n, 1000);
+
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.recv_frame(frames::data(1, vec![0; 1000])).await;
+}
+
+#[tokio::test]
+async fn writer_close() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let (_response, stream) = client.send_request(get(), false).unwrap();
+    let mut writer = stream.into_writer();
+
+    writer.write_all(b"hello").await.unwrap();
+    writer.close().await.unwrap();
+
+    // Closing again does nothing, writing fails.
+    writer.close().await.unwrap();
+    let err = writer.write(b"world").await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+
+    assert_eq!(recv_request_body(&mut srv).await, b"hello");
+}
+
+#[tokio::test]
+async fn reader_spans_frames() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let (response, _stream) = client.send_request(get(), false).unwrap();
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.send_frame(frames::headers(1).response(200)).await;
+    srv.send_frame(frames::data(1, "hel")).await;
+    srv.send_frame(frames::data(1, "lo wor")).await;
+    srv.send_frame(frames::data(1, "ld").eos()).await;
+
+    let mut reader = response.await.unwrap().into_body().into_reader();
+
+    let mut buf = [0; 5];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+
+    let mut rest = vec![];
+    reader.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, b" world");
+}
+
+#[tokio::test]
+async fn reader_eof() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let (response, _stream) = client.send_request(get(), false).unwrap();
+    srv.recv_frame(frames::headers(1).request("GET", "https://example.com/"))
+        .await;
+    srv.send_frame(frames::headers(1).response(200)).await;
+    srv.send_frame(frames::data(1, "hello").eos()).await;
+
+    let mut reader = response.await.unwrap().into_body().into_reader();
+
+    let mut buf = [0; 16];
+    assert_eq!(reader.read(&mut buf).await.unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+
+    // The end of the stream reads as 0 bytes, every time.
+    assert_eq!(reader.read(&mut buf).await.unwrap(), 0);
+    assert_eq!(reader.read(&mut buf).await.unwrap(), 0);
+}
@@ -321,7 +321,11 @@ async fn recv_goaway_finishes_processed_streams() {
         // this request will trigger a goaway
         let req2 = async move {
             let err = client.get("https://example.com/").await.unwrap_err();
-            assert_eq!(err.to_string(), "protocol error: not a result of an error");
+            assert!(err.is_unprocessed());
+            assert_eq!(
+                err.to_string(),
+                "stream not processed, peer going away: not a result of an error"
+            );
         };
 
         join3(async move { h2.await.expect("client") }, req1, req2).await;
@@ -1013,3 +1017,93 @@ async fn srv_window_update_on_lower_stream_id() {
     };
     join(srv, client).await;
 }
+
+#[tokio::test]
+async fn too_many_unanswered_remote_resets() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+
+        for id in &[1, 3, 5] {
+            client
+                .send_frame(
+                    frames::headers(*id)
+                        .request("GET", "https://example.com/")
+                        .eos(),
+                )
+                .await;
+            client.send_frame(frames::reset(*id).cancel()).await;
+        }
+
+        let go_away = poll_frame!(GoAway, client);
+        assert_eq!(go_away.reason(), Reason::ENHANCE_YOUR_CALM);
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .max_remote_reset_streams(2)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        while let Some(Ok(_)) = srv.next().await {}
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn answered_remote_resets_not_limited() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+
+        for id in &[1, 3, 5, 7, 9] {
+            client
+                .send_frame(
+                    frames::headers(*id)
+                        .request("GET", "https://example.com/")
+                        .eos(),
+                )
+                .await;
+            client.recv_frame(frames::headers(*id).response(200)).await;
+            client.send_frame(frames::reset(*id).cancel()).await;
+        }
+        client
+            .send_frame(
+                frames::headers(11)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client.recv_eof().await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .max_remote_reset_streams(2)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let mut streams = Vec::new();
+        for _ in 0..5 {
+            let (_req, mut respond) = srv.next().await.unwrap().unwrap();
+            streams.push(respond.send_response(Response::new(()), false).unwrap());
+        }
+
+        // The connection is still up after the client cancelled the answered
+        // streams, which all show up in the stats.
+        let (req, _respond) = srv.next().await.unwrap().unwrap();
+        assert_eq!(req.uri().path(), "/");
+        assert_eq!(srv.stats().remote_reset_streams(), 5);
+    };
+
+    join(client, srv).await;
+}
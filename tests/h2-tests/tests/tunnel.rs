@@ -0,0 +1,264 @@
+#![deny(warnings)]
+
+use futures::future::{join, poll_fn};
+use futures::StreamExt;
+use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+use h2_support::prelude::*;
+use std::io;
+
+/// Connects a client, whose connection is spawned, to a server.
+async fn connect() -> (client::SendRequest<Bytes>, mock::Handle) {
+    let (io, mut srv) = mock::new();
+
+    let (client, h2) = client::handshake(io).await.unwrap();
+    tokio::spawn(async move {
+        let _ = h2.await;
+    });
+
+    let settings = srv.assert_client_handshake().await;
+    assert_default_settings!(settings);
+
+    (client, srv)
+}
+
+/// Accepts a server connection, then spawns it once the first request is
+/// accepted.
+async fn accept_one(io: mock::Mock) -> (Request<RecvStream>, server::SendResponse<Bytes>) {
+    let mut srv = server::handshake(io).await.expect("handshake");
+    let request = srv.next().await.unwrap().unwrap();
+    tokio::spawn(async move {
+        let _ = poll_fn(|cx| srv.poll_closed(cx)).await;
+    });
+    request
+}
+
+fn connect_request() -> Request<()> {
+    Request::connect("example.com:443").body(()).unwrap()
+}
+
+fn response(status: u16) -> Response<()> {
+    Response::builder().status(status).body(()).unwrap()
+}
+
+#[tokio::test]
+async fn send_tunnel_requires_connect() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let err = client
+        .send_tunnel(Request::get("https://example.com/").body(()).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        h2::Error::from(UserError::InvalidTunnel).to_string()
+    );
+
+    let response = client.send_tunnel(connect_request());
+    srv.recv_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+        .await;
+    srv.send_frame(frames::headers(1).response(407).eos()).await;
+
+    let response = response.await.unwrap();
+    assert_eq!(response.status(), StatusCode::PROXY_AUTHENTICATION_REQUIRED);
+}
+
+#[tokio::test]
+async fn accept_tunnel_requires_success() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(407).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let (request, mut respond) = accept_one(io).await;
+
+        let err = respond.accept_tunnel(request, response(407)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            h2::Error::from(UserError::InvalidTunnel).to_string()
+        );
+        respond.send_response(response(407), true).unwrap();
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_tunnel_round_trip() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    // The future doesn't borrow the `SendRequest`.
+    let response = client.send_tunnel(connect_request());
+    poll_fn(|cx| client.poll_ready(cx)).await.unwrap();
+
+    srv.recv_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+        .await;
+    srv.send_frame(frames::headers(1).response(200)).await;
+
+    let response = response.await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut tunnel = response.into_body();
+    tunnel.write_all(b"ping").await.unwrap();
+    srv.recv_frame(frames::data(1, "ping")).await;
+    srv.send_frame(frames::data(1, "pong")).await;
+
+    let mut buf = [0; 4];
+    tunnel.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[tokio::test]
+async fn accept_tunnel_round_trip() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+            .await;
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.send_frame(frames::data(1, "ping")).await;
+        client.recv_frame(frames::data(1, "pong")).await;
+    };
+
+    let srv = async move {
+        let (request, mut respond) = accept_one(io).await;
+        let mut tunnel = respond.accept_tunnel(request, response(200)).unwrap();
+
+        let mut buf = [0; 4];
+        tunnel.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        tunnel.write_all(b"pong").await.unwrap();
+        tunnel
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_tunnel_half_close() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let response = client.send_tunnel(connect_request());
+    srv.recv_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+        .await;
+    srv.send_frame(frames::headers(1).response(200)).await;
+
+    let mut tunnel = response.await.unwrap().into_body();
+    tunnel.write_all(b"hello").await.unwrap();
+    tunnel.close().await.unwrap();
+    srv.recv_frame(frames::data(1, "hello")).await;
+    srv.recv_frame(frames::data(1, "").eos()).await;
+
+    // The client closed its side, the server's side is still open.
+    srv.send_frame(frames::data(1, "world").eos()).await;
+
+    let mut received = vec![];
+    tunnel.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"world");
+}
+
+#[tokio::test]
+async fn accept_tunnel_half_close() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+            .await;
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.send_frame(frames::data(1, "hello").eos()).await;
+        client.recv_frame(frames::data(1, "world")).await;
+        client.recv_frame(frames::data(1, "").eos()).await;
+    };
+
+    let srv = async move {
+        let (request, mut respond) = accept_one(io).await;
+        let mut tunnel = respond.accept_tunnel(request, response(200)).unwrap();
+
+        let mut received = vec![];
+        tunnel.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received, b"hello");
+
+        // The client closed its side, this side is still open.
+        tunnel.write_all(b"world").await.unwrap();
+        tunnel.close().await.unwrap();
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn recv_tunnel_reset() {
+    h2_support::trace_init!();
+    let (mut client, mut srv) = connect().await;
+
+    let response = client.send_tunnel(connect_request());
+    srv.recv_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+        .await;
+    srv.send_frame(frames::headers(1).response(200)).await;
+
+    let mut tunnel = response.await.unwrap().into_body();
+    tunnel.write_all(b"hello").await.unwrap();
+    srv.recv_frame(frames::data(1, "hello")).await;
+    srv.send_frame(frames::reset(1).reason(Reason::CONNECT_ERROR))
+        .await;
+
+    let mut buf = [0; 5];
+    let err = tunnel.read(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    let err = err.into_inner().unwrap().downcast::<h2::Error>().unwrap();
+    assert_eq!(err.reason(), Some(Reason::CONNECT_ERROR));
+
+    // The reset is known, so writing doesn't race with it.
+    let err = tunnel.write_all(b"world").await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+}
+
+#[tokio::test]
+async fn send_tunnel_reset() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("CONNECT", "example.com:443"))
+            .await;
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.send_frame(frames::data(1, "hello")).await;
+        client
+            .recv_frame(frames::reset(1).reason(Reason::CONNECT_ERROR))
+            .await;
+    };
+
+    let srv = async move {
+        let (request, mut respond) = accept_one(io).await;
+        let mut tunnel = respond.accept_tunnel(request, response(200)).unwrap();
+
+        let mut buf = [0; 5];
+        tunnel.read_exact(&mut buf).await.unwrap();
+        tunnel.send_reset(Reason::CONNECT_ERROR);
+    };
+
+    join(client, srv).await;
+}
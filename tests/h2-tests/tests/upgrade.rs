@@ -0,0 +1,96 @@
+#![deny(warnings)]
+
+use futures::future::join;
+use futures::StreamExt;
+use h2_support::prelude::*;
+
+#[tokio::test]
+async fn server_handshake_upgraded() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+
+        // The upgrade request is stream 1, which the client already ended.
+        client
+            .recv_frame(frames::headers(1).response(204).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let request = Request::get("/path")
+            .header("host", "example.com")
+            .header("connection", "Upgrade, HTTP2-Settings")
+            .header("upgrade", "h2c")
+            .header("http2-settings", client::Builder::new().http2_settings())
+            .body(())
+            .unwrap();
+        let mut srv = server::Builder::new()
+            .handshake_upgraded::<_, Bytes>(io, request)
+            .unwrap()
+            .await
+            .expect("handshake");
+
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+        assert_eq!(req.uri(), "http://example.com/path");
+        assert!(req.body().is_end_stream());
+
+        let rsp = Response::builder().status(204).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[test]
+fn server_upgrade_invalid_host() {
+    h2_support::trace_init!();
+
+    for host in &[&b"exa\xffmple.com"[..], b"exa mple.com"] {
+        let (io, _client) = mock::new();
+        let request = Request::get("/path")
+            .header("host", http::HeaderValue::from_bytes(host).unwrap())
+            .header("http2-settings", client::Builder::new().http2_settings())
+            .body(())
+            .unwrap();
+
+        let err = match server::Builder::new().handshake_upgraded::<_, Bytes>(io, request) {
+            Err(err) => err,
+            Ok(_) => panic!("upgraded with host {:?}", host),
+        };
+        assert_eq!(
+            err.to_string(),
+            h2::Error::from(UserError::InvalidUpgradeRequest).to_string()
+        );
+    }
+}
+
+#[tokio::test]
+async fn client_handshake_upgraded() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+
+        // The response to the upgrade request comes on stream 1.
+        srv.send_frame(frames::headers(1).response(204).eos()).await;
+    };
+
+    let h2 = async move {
+        // The builder is a temporary, the future owns what it needs.
+        let request = Request::get("/path").body(()).unwrap();
+        let handshake = client::Builder::new().handshake_upgraded::<_, Bytes>(io, request);
+        let (_client, mut h2, response) = handshake.await.unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    };
+
+    join(srv, h2).await;
+}